@@ -0,0 +1,23 @@
+//! Compares `branch` with the `small-rng` feature to `rand::thread_rng()`.
+//!
+//! Run with `cargo bench --features small-rng`.
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use random_branch::branch;
+use random_branch::branch_using;
+
+
+fn branches(c: &mut Criterion) {
+	let mut group = c.benchmark_group("branch");
+	group.bench_function("small-rng", |b| b.iter(|| branch!(black_box(1_u32), 2, 3, 4, 5)));
+	group.bench_function("thread_rng", |b| {
+		b.iter(|| branch_using!(rand::thread_rng(), { black_box(1_u32), 2, 3, 4, 5 }))
+	});
+	group.finish();
+}
+
+criterion_group!(benches, branches);
+criterion_main!(benches);
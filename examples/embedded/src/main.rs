@@ -0,0 +1,69 @@
+//! An interrupt-safe use of the global RNG of `random-branch`.
+//!
+//! This is a `no_std` binary for a bare-metal Cortex-M target, which is
+//! `thumbv7em-none-eabihf` by default, see `.cargo/config.toml`. It enables
+//! the `critical-section` feature, so that `branch!` can be used from thread
+//! mode as well as from an interrupt handler, with the critical sections of
+//! the `cortex-m` crate for single-core chips. The global RNG requires an
+//! allocator, which is a small static heap here. It is only meant to be built:
+//!
+//! ```sh
+//! cd examples/embedded
+//! cargo build --release
+//! ```
+
+#![no_std]
+#![no_main]
+
+use core::mem::MaybeUninit;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use embedded_alloc::LlffHeap as Heap;
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::set_global_rng;
+
+
+#[global_allocator]
+static HEAP: Heap = Heap::empty();
+
+/// The size of the heap, which only has to hold the global RNG.
+const HEAP_SIZE: usize = 256;
+
+/// The brightness of an LED, set from thread mode and the interrupt.
+static BRIGHTNESS: AtomicU32 = AtomicU32::new(0);
+
+
+/// The `SysTick` exception handler, e.g. registered via `cortex-m-rt`.
+///
+/// It may interrupt `main` while it is drawing from the global RNG, but only
+/// before or after the critical section of that draw, so it can branch too.
+#[no_mangle]
+pub extern "C" fn SysTick() {
+	let flicker = branch!(0, 1, 2);
+	BRIGHTNESS.fetch_add(flicker, Ordering::Relaxed);
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> ! {
+	{
+		static mut HEAP_MEM: [MaybeUninit<u8>; HEAP_SIZE] = [MaybeUninit::uninit(); HEAP_SIZE];
+		// SAFETY: called once, before any allocation
+		unsafe { HEAP.init(core::ptr::addr_of_mut!(HEAP_MEM) as usize, HEAP_SIZE) }
+	}
+
+	// A real firmware would seed this from a hardware RNG
+	set_global_rng(Lcg64Xsh32::new(0xcafe_f00d_d15e_a5e5, 0x0a02_bdbf_7bb3_c0a7)).ok();
+
+	loop {
+		let level = branch!(10, 50, 100);
+		BRIGHTNESS.store(level, Ordering::Relaxed);
+		cortex_m::asm::wfi();
+	}
+}
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo<'_>) -> ! {
+	loop {}
+}
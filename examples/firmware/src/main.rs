@@ -0,0 +1,76 @@
+//! A firmware-style use of the `defmt` feature of `random-branch`.
+//!
+//! This is a `no_std` binary for a bare-metal target, which is
+//! `thumbv7em-none-eabihf` by default, see `.cargo/config.toml`. Its global
+//! logger writes the `defmt` frames of each selected branch into a fixed
+//! buffer, standing in for e.g. an RTT channel. It uses neither an allocator
+//! nor `core::fmt`. It is only meant to be built:
+//!
+//! ```sh
+//! cd examples/firmware
+//! cargo build --release
+//! ```
+
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::AtomicU8;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_using;
+use random_branch::branch_weighted_using;
+
+
+/// The buffer of the logged frames, e.g. the up channel of RTT.
+static BUFFER: [AtomicU8; 1024] = [const { AtomicU8::new(0) }; 1024];
+
+/// The number of bytes written into `BUFFER`, which wraps around.
+static WRITTEN: AtomicUsize = AtomicUsize::new(0);
+
+#[defmt::global_logger]
+struct BufferLogger;
+
+unsafe impl defmt::Logger for BufferLogger {
+	fn acquire() {}
+
+	unsafe fn flush() {}
+
+	unsafe fn release() {}
+
+	unsafe fn write(bytes: &[u8]) {
+		for &byte in bytes {
+			let at = WRITTEN.fetch_add(1, Ordering::Relaxed) % BUFFER.len();
+			BUFFER[at].store(byte, Ordering::Relaxed);
+		}
+	}
+}
+
+defmt::timestamp!("");
+
+
+/// Blinks an LED in a random pattern, logging each decision.
+fn blink(rng: &mut Lcg64Xsh32, led: &mut bool) -> u16 {
+	*led = branch_using!(*rng, "led", { true, false, !*led });
+	branch_weighted_using!(*rng, {
+		8 => 100,
+		1 => 500,
+		1 => 1_000,
+	})
+}
+
+#[no_mangle]
+pub extern "C" fn main() -> ! {
+	// A real firmware would seed this from a hardware RNG
+	let mut rng = Lcg64Xsh32::new(0xcafe_f00d_d15e_a5e5, 0x0a02_bdbf_7bb3_c0a7);
+	let mut led = false;
+	loop {
+		let _delay_ms = blink(&mut rng, &mut led);
+	}
+}
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo<'_>) -> ! {
+	loop {}
+}
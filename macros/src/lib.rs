@@ -0,0 +1,135 @@
+//! Provides the procedural macros of the `random-branch` crate.
+//!
+//! Don't depend on this crate directly, instead enable the `macros` feature
+//! of `random-branch` and use the macros from there, e.g. as
+//! `#[random_branch::seeded_test]`.
+
+#![forbid(unsafe_code)]
+#![deny(unreachable_pub)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse_macro_input;
+use syn::Ident;
+use syn::ItemFn;
+use syn::LitInt;
+
+
+/// Turns a function into a test with a seeded RNG.
+///
+/// See `random_branch::seeded_test` for the documentation.
+#[proc_macro_attribute]
+pub fn seeded_test(args: TokenStream, item: TokenStream) -> TokenStream {
+	let binding = if args.is_empty() {
+		Ident::new("rng", Span::call_site())
+	} else {
+		parse_macro_input!(args as Ident)
+	};
+	let ItemFn {
+		attrs,
+		vis,
+		sig,
+		block,
+	} = parse_macro_input!(item as ItemFn);
+
+	// Only add `#[test]` if there isn't already a test attribute such as
+	// `#[test]` or `#[tokio::test]`.
+	let has_test = attrs.iter().any(|attr| {
+		attr.path()
+			.segments
+			.last()
+			.is_some_and(|segment| segment.ident == "test")
+	});
+	let test = if has_test {
+		quote!()
+	} else {
+		quote!(#[test])
+	};
+
+	let name = &sig.ident;
+	let guard = Ident::new("guard", Span::mixed_site());
+	let stmts = &block.stmts;
+
+	quote!(
+		#( #attrs )*
+		#test
+		#vis #sig {
+			let #guard = ::random_branch::test_seed::SeedGuard::new(
+				::core::concat!(::core::module_path!(), "::", ::core::stringify!(#name))
+			);
+			#[allow(unused_mut, unused_variables)]
+			let mut #binding = #guard.rng();
+			#( #stmts )*
+		}
+	)
+	.into()
+}
+
+
+/// Expands a test function into a number of independently seeded tests.
+///
+/// See `random_branch::cases` for the documentation.
+#[proc_macro_attribute]
+pub fn cases(args: TokenStream, item: TokenStream) -> TokenStream {
+	let count = parse_macro_input!(args as LitInt);
+	let count: usize = match count.base10_parse() {
+		Ok(count) if count > 0 => count,
+		Ok(_) => {
+			return syn::Error::new(count.span(), "There must be at least one case.")
+				.to_compile_error()
+				.into()
+		},
+		Err(err) => return err.to_compile_error().into(),
+	};
+	let item = parse_macro_input!(item as ItemFn);
+	if let Some(asyncness) = &item.sig.asyncness {
+		return syn::Error::new(asyncness.span, "Async test cases aren't supported.")
+			.to_compile_error()
+			.into();
+	}
+
+	let ItemFn {
+		attrs,
+		sig,
+		..
+	} = &item;
+	let name = &sig.ident;
+	let output = &sig.output;
+	let vis = &item.vis;
+	let width = (count - 1).to_string().len().max(2);
+
+	let cases = (0..count).map(|index| {
+		let case = Ident::new(&format!("{}_case_{:0width$}", name, index, width = width), name.span());
+		quote!(
+			#( #attrs )*
+			#[test]
+			#vis fn #case() #output {
+				let mut rng = ::random_branch::test_seed::case_rng(
+					::core::concat!(::core::module_path!(), "::", ::core::stringify!(#name)),
+					#index,
+				);
+				#name(&mut rng)
+			}
+		)
+	});
+
+	// The test attributes only go to the cases
+	let mut item = item.clone();
+	item.attrs.retain(|attr| {
+		let path = attr.path();
+		!(path.is_ident("should_panic")
+			|| path.is_ident("ignore")
+			|| path.segments.last().is_some_and(|segment| segment.ident == "test"))
+	});
+
+	quote!(
+		#[allow(dead_code)]
+		#item
+
+		#( #cases )*
+	)
+	.into()
+}
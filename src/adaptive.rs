@@ -0,0 +1,102 @@
+//! The state of [`branch_adaptive_using`](crate::branch_adaptive_using).
+
+use rand::Rng;
+
+use crate::choose_index;
+
+
+/// Weights of `N` branches, that decay when selected and recover over time.
+///
+/// This is the state used by
+/// [`branch_adaptive_using`](crate::branch_adaptive_using). Each branch has a
+/// weight, initially [`FULL`](Self::FULL), and branches are selected with a
+/// probability proportional to their weight. After each selection:
+/// 1. every branch regains `recovery` weight, up to [`FULL`](Self::FULL),
+/// 2. the weight of the selected branch is multiplied by the `decay` factor.
+///
+/// Thus, a recently selected branch is less likely to be selected again, but
+/// it recovers over subsequent selections. This is a softer alternative to
+/// [`NoRepeat`](crate::NoRepeat), reducing streaks while keeping the long-run
+/// frequencies of all branches equal.
+///
+/// Both `decay` and `recovery` are fixed-point values in units of
+/// `1 / FULL`, so e.g. a `decay` of `FULL / 4` keeps a quarter of the weight,
+/// and a `recovery` of `FULL / 8` recovers an eighth of the full weight per
+/// selection. All math is done with integers, so this works on any `no_std`
+/// target.
+#[derive(Debug, Clone)]
+pub struct AdaptiveWeights<const N: usize> {
+	weights: [u32; N],
+	decay: u32,
+	recovery: u32,
+}
+
+impl<const N: usize> AdaptiveWeights<N> {
+	/// The full weight of a branch, i.e. fixed-point `1.0`.
+	pub const FULL: u32 = 1 << 16;
+
+	/// Creates weights with the given `decay` factor and `recovery` rate.
+	///
+	/// See the type-level docs for their meaning. A `decay` greater than
+	/// [`FULL`](Self::FULL) is capped at `FULL`, which disables the decay.
+	pub const fn new(decay: u32, recovery: u32) -> Self {
+		Self {
+			weights: [Self::FULL; N],
+			decay: if decay > Self::FULL { Self::FULL } else { decay },
+			recovery,
+		}
+	}
+
+	/// Returns the current weights of all branches.
+	pub fn weights(&self) -> &[u32; N] {
+		&self.weights
+	}
+
+	/// Resets all weights to [`FULL`](Self::FULL).
+	pub fn reset(&mut self) {
+		self.weights = [Self::FULL; N];
+	}
+
+	/// Selects the index of one of the `N` branches according to the current
+	/// weights, and updates the weights accordingly.
+	///
+	/// If all weights are zero, which can only happen with a zero `recovery`,
+	/// the selection is uniform.
+	///
+	/// # Panics
+	///
+	/// Panics if `N` is zero.
+	#[track_caller]
+	pub fn select<R: Rng + ?Sized>(&mut self, rng: &mut R) -> usize {
+		assert!(N > 0, "You must provide at least one choice.");
+
+		let total: u64 = self.weights.iter().map(|&w| u64::from(w)).sum();
+		let index = if total == 0 {
+			choose_index(rng, N)
+		} else {
+			let mut remaining = rng.gen_range(0..total);
+			let mut index = 0;
+			while remaining >= u64::from(self.weights[index]) {
+				remaining -= u64::from(self.weights[index]);
+				index += 1;
+			}
+			index
+		};
+
+		for w in self.weights.iter_mut() {
+			*w = w.saturating_add(self.recovery).min(Self::FULL);
+		}
+		let decayed = u64::from(self.weights[index]) * u64::from(self.decay) / u64::from(Self::FULL);
+		self.weights[index] = decayed as u32;
+
+		index
+	}
+}
+
+impl<const N: usize> Default for AdaptiveWeights<N> {
+	/// Creates weights that keep a quarter of the weight of a selected branch
+	/// and recover an eighth of the full weight per selection.
+	fn default() -> Self {
+		Self::new(Self::FULL / 4, Self::FULL / 8)
+	}
+}
@@ -0,0 +1,223 @@
+//! Walker's alias method for repeated weighted selections.
+
+use alloc::vec::Vec;
+
+use rand::distributions::Distribution;
+use rand::Rng;
+
+use crate::choose_index;
+use crate::BranchError;
+
+
+/// A precomputed table to select weighted indices in constant time.
+///
+/// This implements Walker's alias method (in Vose's variant): building the
+/// table from `n` weights takes `O(n)` time and memory, but afterwards, each
+/// [`sample`](Self::sample) takes `O(1)` time, regardless of the number of
+/// weights, while [`choose_weighted_index`](crate::choose_weighted_index)
+/// and the weighted macros, such as
+/// [`branch_weighted_using`](crate::branch_weighted_using), scan all weights
+/// on each selection. So, use this table if there are many weights, which
+/// are sampled repeatedly.
+///
+/// The table is built with integer arithmetic only, thus the probability of
+/// each index is exactly proportional to its weight, and an index with a
+/// weight of zero is never selected. Each sample draws two integers from the
+/// RNG, one for the column and one for the threshold within the column.
+///
+/// This type requires the `alloc` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_on;
+/// use random_branch::AliasTable;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let table = AliasTable::new(&[1, 0, 3]).unwrap();
+/// for _ in 0..10 {
+///     let item = branch_on!(table.sample(&mut my_rng), {
+///         "rare",
+///         "never",
+///         "common",
+///     });
+///     assert_ne!(item, "never");
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AliasTable {
+	/// The sum of all weights, which is the height of each column.
+	total: u64,
+	/// The threshold of each column, below which the column selects itself.
+	threshold: Vec<u64>,
+	/// The index selected by each column above its threshold.
+	alias: Vec<usize>,
+}
+
+impl AliasTable {
+	/// Builds the table from the given weights.
+	///
+	/// Returns [`BranchError::Empty`] if there are no weights, and
+	/// [`BranchError::AllWeightsZero`] if all of them are zero.
+	///
+	/// # Panics
+	///
+	/// Panics if the sum of all weights overflows a `u64`.
+	#[track_caller]
+	pub fn new(weights: &[u32]) -> Result<Self, BranchError> {
+		let n = weights.len();
+		if n == 0 {
+			return Err(BranchError::Empty);
+		}
+		let total = weights
+			.iter()
+			.try_fold(0_u64, |acc, &w| acc.checked_add(u64::from(w)))
+			.expect("The sum of all weights overflows a u64");
+		if total == 0 {
+			return Err(BranchError::AllWeightsZero);
+		}
+
+		// Scale each weight by `n`, so that the average equals `total`, i.e.
+		// the height of a column
+		let mut scaled: Vec<u128> = weights.iter().map(|&w| u128::from(w) * n as u128).collect();
+		let height = u128::from(total);
+
+		let mut small: Vec<usize> = (0..n).filter(|&i| scaled[i] < height).collect();
+		let mut large: Vec<usize> = (0..n).filter(|&i| scaled[i] >= height).collect();
+
+		let mut threshold = alloc::vec![total; n];
+		let mut alias: Vec<usize> = (0..n).collect();
+
+		while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+			small.pop();
+			// Fill up the column `s` with the excess of `l`
+			threshold[s] = scaled[s] as u64;
+			alias[s] = l;
+			scaled[l] = scaled[l] + scaled[s] - height;
+			if scaled[l] < height {
+				large.pop();
+				small.push(l);
+			}
+		}
+		// With exact arithmetic, all remaining columns are exactly full,
+		// and keep their defaults
+
+		Ok(Self {
+			total,
+			threshold,
+			alias,
+		})
+	}
+
+	/// Returns the number of weights, i.e. the indices range over
+	/// `0..count()`.
+	pub fn count(&self) -> usize {
+		self.threshold.len()
+	}
+
+	/// Selects an index with a probability proportional to its weight.
+	pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+		let column = choose_index(rng, self.count());
+		if rng.gen_range(0..self.total) < self.threshold[column] {
+			column
+		} else {
+			self.alias[column]
+		}
+	}
+}
+
+impl Distribution<usize> for AliasTable {
+	fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+		AliasTable::sample(self, rng)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand_pcg::Lcg64Xsh32;
+
+	/// Returns the exact probability of each index, scaled by `count * total`.
+	fn scaled_probabilities(table: &AliasTable) -> Vec<u128> {
+		let mut p = alloc::vec![0_u128; table.count()];
+		for column in 0..table.count() {
+			let keep = u128::from(table.threshold[column]);
+			p[column] += keep;
+			p[table.alias[column]] += u128::from(table.total) - keep;
+		}
+		p
+	}
+
+	/// Generates random weights, with some zeros and some large ones.
+	fn random_weights(rng: &mut Lcg64Xsh32) -> Vec<u32> {
+		let n = rng.gen_range(1..50);
+		(0..n)
+			.map(|_| match rng.gen_range(0..4) {
+				0 => 0,
+				1 => rng.gen(),
+				_ => rng.gen_range(0..100),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!(AliasTable::new(&[]), Err(BranchError::Empty));
+		assert_eq!(AliasTable::new(&[0, 0]), Err(BranchError::AllWeightsZero));
+	}
+
+	#[test]
+	fn exact_probabilities() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		for _ in 0..1000 {
+			let weights = random_weights(&mut rng);
+			let table = match AliasTable::new(&weights) {
+				Ok(table) => table,
+				Err(_) => continue,
+			};
+			let n = weights.len() as u128;
+			let expected: Vec<u128> = weights.iter().map(|&w| u128::from(w) * n).collect();
+			assert_eq!(scaled_probabilities(&table), expected, "{:?}", weights);
+		}
+	}
+
+	#[test]
+	fn empirical_distribution() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		for _ in 0..20 {
+			let weights: Vec<u32> = {
+				let n = rng.gen_range(1..10);
+				(0..n).map(|_| rng.gen_range(0..10)).collect()
+			};
+			let table = match AliasTable::new(&weights) {
+				Ok(table) => table,
+				Err(_) => continue,
+			};
+			let total: u32 = weights.iter().sum();
+			let samples = 20_000;
+			let mut counts = alloc::vec![0_u32; weights.len()];
+			for _ in 0..samples {
+				counts[table.sample(&mut rng)] += 1;
+			}
+			for (&w, &c) in weights.iter().zip(&counts) {
+				let expected = f64::from(w) / f64::from(total);
+				let actual = f64::from(c) / f64::from(samples);
+				assert!((expected - actual).abs() < 0.02, "{:?}, {:?}", weights, counts);
+				if w == 0 {
+					assert_eq!(c, 0);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn single_weight() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let table = AliasTable::new(&[7]).unwrap();
+		assert_eq!(table.count(), 1);
+		assert_eq!(table.sample(&mut rng), 0);
+	}
+}
@@ -0,0 +1,57 @@
+//! Runtime support for [`branch_balanced_using`](crate::branch_balanced_using).
+
+use alloc::vec::Vec;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+
+/// Apportions `n` selections to the given weights, using the largest
+/// remainder method.
+///
+/// Each index gets the integer part of its exact share `n * weight / total`,
+/// and the remaining selections go to the indices with the largest
+/// fractional parts, preferring lower indices on ties.
+///
+/// # Panics
+///
+/// Panics if all weights are zero, unless `n` is zero.
+#[track_caller]
+pub fn apportion(weights: &[u32], n: usize) -> Vec<usize> {
+	let total: u128 = weights.iter().map(|&w| u128::from(w)).sum();
+	if n == 0 {
+		return alloc::vec![0; weights.len()];
+	}
+	assert!(total > 0, "All weights are zero.");
+
+	let n_wide = n as u128;
+	let mut counts: Vec<usize> = weights.iter()
+		.map(|&w| (n_wide * u128::from(w) / total) as usize)
+		.collect();
+
+	let assigned: usize = counts.iter().sum();
+	let mut by_remainder: Vec<usize> = (0..weights.len()).collect();
+	// Stable sort, so ties keep preferring lower indices
+	by_remainder.sort_by_key(|&i| core::cmp::Reverse(n_wide * u128::from(weights[i]) % total));
+	for &i in by_remainder.iter().take(n - assigned) {
+		counts[i] += 1;
+	}
+
+	counts
+}
+
+/// Returns the indices of the apportioned selections in a random order.
+///
+/// See [`apportion`] for how the counts are computed.
+#[track_caller]
+pub fn shuffled<R: Rng + ?Sized>(weights: &[u32], n: usize, rng: &mut R) -> Vec<usize> {
+	let counts = apportion(weights, n);
+	let mut order = Vec::with_capacity(n);
+	for (index, &count) in counts.iter().enumerate() {
+		order.extend(core::iter::repeat(index).take(count));
+	}
+	if !crate::select::DETERMINISTIC {
+		order.shuffle(rng);
+	}
+	order
+}
@@ -0,0 +1,50 @@
+//! Runtime support for [`branch_batch_using`](crate::branch_batch_using).
+
+use rand::RngCore;
+
+
+/// Returns the product of all counts, i.e. the number of combinations.
+///
+/// # Panics
+///
+/// Panics if the product does not fit into a `u64`. Since this is evaluated
+/// in a constant, that is a compile-time error.
+pub const fn total(counts: &[usize]) -> u64 {
+	let mut total: u64 = 1;
+	let mut i = 0;
+	while i < counts.len() {
+		total = match total.checked_mul(counts[i] as u64) {
+			Some(total) => total,
+			None => panic!("The total number of combinations must fit into 64 bits."),
+		};
+		i += 1;
+	}
+	total
+}
+
+/// Selects an index for each count from a single uniform draw in
+/// `0..total`.
+///
+/// The draw is made from one `u64` with Lemire's multiply-and-reject method,
+/// which only rarely needs another `u64`, and then split into the indices as
+/// a mixed-radix number, so the indices are uniform and independent.
+pub fn select<R: RngCore + ?Sized, const K: usize>(rng: &mut R, total: u64, counts: &[usize; K]) -> [usize; K] {
+	if crate::select::DETERMINISTIC {
+		return [0; K];
+	}
+
+	let threshold = total.wrapping_neg() % total;
+	let mut combination = loop {
+		let product = u128::from(rng.next_u64()) * u128::from(total);
+		if product as u64 >= threshold {
+			break (product >> 64) as u64;
+		}
+	};
+
+	counts.map(|count| {
+		let count = count as u64;
+		let index = combination % count;
+		combination /= count;
+		index as usize
+	})
+}
@@ -0,0 +1,134 @@
+//! A fast and stable RNG for benchmarks, and the SplitMix64 generator
+//! behind it.
+
+use rand::RngCore;
+use rand::SeedableRng;
+
+
+/// The increment of SplitMix64, the golden ratio as 64-bit fixed point.
+pub(crate) const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The output function of SplitMix64.
+pub(crate) fn mix(mut z: u64) -> u64 {
+	z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+	z ^ (z >> 31)
+}
+
+/// Advances the given SplitMix64 state and returns the next output.
+pub(crate) fn next(state: &mut u64) -> u64 {
+	*state = state.wrapping_add(GOLDEN_GAMMA);
+	mix(*state)
+}
+
+/// Fills `dest` with the little-endian bytes of `u64`s drawn from `rng`.
+pub(crate) fn fill_bytes_via_u64<R: RngCore + ?Sized>(rng: &mut R, dest: &mut [u8]) {
+	for chunk in dest.chunks_mut(8) {
+		let bytes = rng.next_u64().to_le_bytes();
+		chunk.copy_from_slice(&bytes[..chunk.len()]);
+	}
+}
+
+
+/// A tiny, fast, and deterministic RNG for benchmarks.
+///
+/// Benchmarking code that branches randomly, e.g. with `criterion`, needs a
+/// generator that is cheap, so it doesn't dominate the measurement, and that
+/// is seeded with a fixed seed, so every run takes the same branches and the
+/// results aren't noisy. Pass it to [`branch_using`](crate::branch_using) and
+/// friends, to exercise their selection with a stable generator.
+///
+/// The generator is SplitMix64, whose state is just the `u64` seed. Its
+/// output is the same on all platforms and in all versions of this crate,
+/// changing it is considered a breaking change. However, the branches
+/// selected from it are only as stable as the uniform range sampling of
+/// [`rand`], use
+#[cfg_attr(feature = "pcg", doc = "[`SeededRng`](crate::SeededRng)")]
+#[cfg_attr(not(feature = "pcg"), doc = "`SeededRng`")]
+/// where that matters.
+///
+/// This generator is **not** cryptographically secure: its output is easily
+/// predicted, so never use it for secrets or anything security related.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_using;
+/// use random_branch::BenchRng;
+///
+/// fn simulate(rng: &mut BenchRng) -> u32 {
+///     (0..1000).map(|_| branch_using!(*rng, { 1, 2, 3 })).sum()
+/// }
+///
+/// // In a benchmark, e.g. `b.iter(|| simulate(&mut rng))`
+/// let mut rng = BenchRng::new(42);
+/// let first = simulate(&mut rng);
+/// assert_eq!(simulate(&mut BenchRng::new(42)), first);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BenchRng {
+	state: u64,
+}
+
+impl BenchRng {
+	/// Creates the generator from the given seed.
+	pub const fn new(seed: u64) -> Self {
+		Self {
+			state: seed,
+		}
+	}
+}
+
+impl RngCore for BenchRng {
+	fn next_u32(&mut self) -> u32 {
+		(self.next_u64() >> 32) as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		next(&mut self.state)
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		fill_bytes_via_u64(self, dest)
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
+
+impl SeedableRng for BenchRng {
+	type Seed = [u8; 8];
+
+	fn from_seed(seed: [u8; 8]) -> Self {
+		Self::new(u64::from_le_bytes(seed))
+	}
+
+	fn seed_from_u64(seed: u64) -> Self {
+		Self::new(seed)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn splitmix_reference() {
+		// The first outputs of SplitMix64 seeded with 0
+		let mut rng = BenchRng::new(0);
+		assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+		assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+		assert_eq!(BenchRng::seed_from_u64(7), BenchRng::from_seed(7u64.to_le_bytes()));
+	}
+
+	#[test]
+	fn fills_bytes_from_u64s() {
+		let mut bytes = [0; 11];
+		BenchRng::new(0).fill_bytes(&mut bytes);
+		assert_eq!(bytes[..8], 0xe220_a839_7b1d_cdaf_u64.to_le_bytes());
+		assert_eq!(bytes[8..], 0x6e78_9e6a_a1b9_65f4_u64.to_le_bytes()[..3]);
+	}
+}
@@ -0,0 +1,30 @@
+//! Runtime support for [`random_bits_using`](crate::random_bits_using).
+
+use rand::Rng;
+use rand::RngCore;
+
+
+/// Returns `N` independent fair coin flips.
+///
+/// Up to 8 flips are extracted from a single `u8`, up to 32 from a single
+/// `u32`, and longer sequences from one `u64` per 64 flips. The flip `i` is
+/// the bit `i % 64` of its word.
+pub fn bits<R: RngCore + ?Sized, const N: usize>(rng: &mut R) -> [bool; N] {
+	if crate::select::DETERMINISTIC {
+		return [false; N];
+	}
+
+	let mut word = 0;
+	core::array::from_fn(|i| {
+		if i % 64 == 0 {
+			word = if N <= 8 {
+				u64::from(rng.gen::<u8>())
+			} else if N <= 32 {
+				u64::from(rng.next_u32())
+			} else {
+				rng.next_u64()
+			};
+		}
+		(word >> (i % 64)) & 1 == 1
+	})
+}
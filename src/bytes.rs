@@ -0,0 +1,181 @@
+//! The cursor of [`branch_from_bytes`](crate::branch_from_bytes).
+
+
+/// A cursor over a byte slice, which selects branches from its bytes.
+///
+/// This is the input of [`branch_from_bytes`](crate::branch_from_bytes),
+/// typically wrapping the data given by a fuzzer, so that the fuzzer, instead
+/// of an RNG, decides which branches are taken.
+///
+/// Each selection via [`select`](Self::select) consumes the minimal number of
+/// bytes that can represent all indices, i.e. no byte for a single branch,
+/// one byte for up to 256 branches, two bytes for up to 65536 branches, and
+/// so on. These bytes are read as a little-endian integer, which is reduced
+/// modulo the number of branches. Thus, unless the number of branches is a
+/// power of two, lower indices are slightly more likely for uniformly random
+/// bytes, e.g. with three branches, the 86 byte values `0, 3, ..., 255` with
+/// residue `0` select the first one, but only the 85 byte values
+/// `1, 4, ..., 253` with residue `1` select the second one. For a fuzzer,
+/// which mutates the bytes anyway, this is of no concern, and in return, the
+/// mapping is simple and stable: the same bytes always select the same
+/// branches.
+///
+/// Once there are not enough bytes left for a selection, the remaining bytes
+/// are consumed and the first branch is selected, which is also what all
+/// later selections will do.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::ByteCursor;
+///
+/// let mut cursor = ByteCursor::new(&[4, 1, 0x2c, 0x01]);
+/// assert_eq!(cursor.select(3), 1); // 4 % 3
+/// assert_eq!(cursor.select(1), 0); // consumes nothing
+/// assert_eq!(cursor.select(2), 1); // 1 % 2
+/// assert_eq!(cursor.remaining(), 2);
+/// assert_eq!(cursor.select(1000), 300); // 0x012c % 1000
+/// assert_eq!(cursor.remaining(), 0);
+/// assert_eq!(cursor.select(3), 0); // exhausted
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteCursor<'a> {
+	bytes: &'a [u8],
+}
+
+impl<'a> ByteCursor<'a> {
+	/// Creates a cursor at the start of the given bytes.
+	pub const fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			bytes,
+		}
+	}
+
+	/// Returns the number of bytes that have not been consumed yet.
+	pub const fn remaining(&self) -> usize {
+		self.bytes.len()
+	}
+
+	/// Returns the bytes that have not been consumed yet.
+	pub const fn as_bytes(&self) -> &'a [u8] {
+		self.bytes
+	}
+
+	/// Selects an index in `0..count` from the next bytes.
+	///
+	/// See the [type-level documentation](Self) for the mapping.
+	///
+	/// # Panics
+	///
+	/// Panics if `count` is zero.
+	#[track_caller]
+	pub fn select(&mut self, count: usize) -> usize {
+		assert!(count > 0, "You must provide at least one choice.");
+
+		let max = (count - 1) as u64;
+		let needed = (64 - max.leading_zeros() as usize).div_ceil(8);
+		if self.bytes.len() < needed {
+			self.bytes = &[];
+			return 0;
+		}
+
+		let (head, tail) = self.bytes.split_at(needed);
+		self.bytes = tail;
+		let value = head
+			.iter()
+			.rev()
+			.fold(0_u64, |acc, &b| (acc << 8) | u64::from(b));
+		(value % count as u64) as usize
+	}
+}
+
+impl<'a> From<&'a [u8]> for ByteCursor<'a> {
+	fn from(bytes: &'a [u8]) -> Self {
+		Self::new(bytes)
+	}
+}
+
+
+#[cfg(all(test, not(feature = "defmt")))]
+mod tests {
+	use super::*;
+	use crate::branch_from_bytes;
+
+	#[test]
+	fn bytes_per_selection() {
+		let bytes = [0xff; 16];
+		for &(count, needed) in &[
+			(1, 0),
+			(2, 1),
+			(256, 1),
+			(257, 2),
+			(65536, 2),
+			(65537, 3),
+			(usize::MAX, core::mem::size_of::<usize>()),
+		] {
+			let mut cursor = ByteCursor::new(&bytes);
+			cursor.select(count);
+			assert_eq!(cursor.remaining(), 16 - needed, "count = {}", count);
+		}
+	}
+
+	#[test]
+	fn stable_mapping() {
+		let mut cursor = ByteCursor::new(&[0, 1, 2, 3, 255, 0x34, 0x12, 7]);
+		let indices: [usize; 6] = core::array::from_fn(|i| {
+			let count = [3, 3, 3, 3, 3, 0x1000][i];
+			cursor.select(count)
+		});
+		assert_eq!(indices, [0, 1, 2, 0, 0, 0x234]);
+		assert_eq!(cursor.as_bytes(), &[7]);
+	}
+
+	#[test]
+	fn exhaustion_falls_back_to_first() {
+		let mut cursor = ByteCursor::new(&[2, 0xff]);
+		assert_eq!(branch_from_bytes!(cursor, { 'a', 'b', 'c' }), 'c');
+		// Two bytes are needed, but only one is left
+		assert_eq!(cursor.select(300), 0);
+		assert_eq!(cursor.remaining(), 0);
+		for _ in 0..3 {
+			assert_eq!(branch_from_bytes!(cursor, { 'a', 'b', 'c' }), 'a');
+		}
+	}
+
+	#[test]
+	fn nested() {
+		let mut cursor = ByteCursor::new(&[1, 0, 1, 1]);
+		let mut pick = || {
+			branch_from_bytes!(cursor, {
+				(0, branch_from_bytes!(cursor, { 0, 1 })),
+				(1, branch_from_bytes!(cursor, { 0, 1 })),
+			})
+		};
+		assert_eq!(pick(), (1, 0));
+		assert_eq!(pick(), (1, 1));
+		assert_eq!(pick(), (0, 0));
+	}
+
+	#[test]
+	fn round_trip() {
+		// A byte string as recorded from a fuzzer run, one byte per decision
+		let recorded = [7_u8, 3, 4, 0, 9];
+		let run = |bytes: &[u8]| {
+			let mut cursor = ByteCursor::new(bytes);
+			let choices: [usize; 5] = core::array::from_fn(|_| {
+				branch_from_bytes!(cursor, { 0, 1, 2, 3, 4 })
+			});
+			choices
+		};
+		let choices = run(&recorded);
+		assert_eq!(choices, [2, 3, 4, 0, 4]);
+
+		// Encoding the choices again gives a canonical byte string, which
+		// replays the same run
+		let mut canonical = [0_u8; 5];
+		for (b, &c) in canonical.iter_mut().zip(&choices) {
+			*b = c as u8;
+		}
+		assert_eq!(run(&canonical), choices);
+	}
+}
@@ -0,0 +1,149 @@
+//! The uniform index selection shared by all macros.
+
+use rand::Rng;
+
+use crate::BranchError;
+
+
+/// Chooses a uniformly distributed index in `0..n` using the given RNG.
+///
+/// This is the building block of [`branch_using`](crate::branch_using) and
+/// most other macros of this crate: they all select the index of their branch
+/// via this function. It uses [`Rng::gen_range`] and thus is exactly uniform,
+/// without any modulo bias.
+///
+/// # Panics
+///
+/// Panics if `n` is zero, since there is no index to choose from.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::choose_index;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let index = choose_index(&mut my_rng, 3);
+/// assert!(index < 3);
+/// ```
+#[track_caller]
+pub fn choose_index<R: Rng + ?Sized>(rng: &mut R, n: usize) -> usize {
+	assert!(n > 0, "You must provide at least one choice.");
+	rng.gen_range(0..n)
+}
+
+/// Chooses a uniformly distributed index in `0..n`, or fails if `n` is zero.
+///
+/// This function dose essentially the same as [`choose_index`], but instead
+/// of panicking, it returns [`BranchError::Empty`] if `n` is zero, without
+/// touching the RNG.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::try_choose_index;
+/// use random_branch::BranchError;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// assert!(try_choose_index(&mut my_rng, 3).unwrap() < 3);
+/// assert_eq!(try_choose_index(&mut my_rng, 0), Err(BranchError::Empty));
+/// ```
+pub fn try_choose_index<R: Rng + ?Sized>(rng: &mut R, n: usize) -> Result<usize, BranchError> {
+	if n == 0 {
+		return Err(BranchError::Empty);
+	}
+	Ok(choose_index(rng, n))
+}
+
+
+/// Chooses an index with a probability proportional to its weight.
+///
+/// This is the building block of
+/// [`try_branch_weighted_using`](crate::try_branch_weighted_using), as a
+/// function for weights that are only known at runtime. It scans all weights
+/// on each call, so for many weights that are sampled repeatedly, build an
+/// `AliasTable` once instead (with the `alloc` feature), which samples in
+/// constant time.
+///
+/// Returns [`BranchError::Empty`] if there are no weights, and
+/// [`BranchError::AllWeightsZero`] if all of them are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::choose_weighted_index;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let index = choose_weighted_index(&mut my_rng, &[1, 0, 3]).unwrap();
+/// assert!(index == 0 || index == 2);
+/// ```
+pub fn choose_weighted_index<R: Rng + ?Sized>(rng: &mut R, weights: &[u32]) -> Result<usize, BranchError> {
+	if weights.is_empty() {
+		return Err(BranchError::Empty);
+	}
+	Ok(crate::weighted::select(weights, rng)?)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand_pcg::Lcg64Xsh32;
+
+	#[test]
+	fn uniform_for_various_counts() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		for n in 1..=10 {
+			let samples = 2000 * n;
+			let mut counts = [0_usize; 10];
+			for _ in 0..samples {
+				counts[choose_index(&mut rng, n)] += 1;
+			}
+			// Pearson's chi-squared statistic, with `n - 1` degrees of freedom
+			let expected = (samples / n) as f64;
+			let chi2: f64 = counts[..n]
+				.iter()
+				.map(|&c| (c as f64 - expected).powi(2) / expected)
+				.sum();
+			// The 99.9% quantile of the chi-squared distribution for 9
+			// degrees of freedom, which is above that of less degrees
+			assert!(chi2 < 27.88, "n = {}, chi2 = {}, {:?}", n, chi2, counts);
+			assert!(counts[n..].iter().all(|&c| c == 0));
+		}
+	}
+
+	#[test]
+	fn single_choice() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		assert_eq!(choose_index(&mut rng, 1), 0);
+	}
+
+	#[test]
+	#[should_panic(expected = "You must provide at least one choice.")]
+	fn zero_choices() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		choose_index(&mut rng, 0);
+	}
+
+	#[test]
+	fn try_zero_choices() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let before = rng.clone();
+		assert_eq!(try_choose_index(&mut rng, 0), Err(BranchError::Empty));
+		assert_eq!(rng, before);
+		assert_eq!(try_choose_index(&mut rng, 1), Ok(0));
+	}
+
+	#[test]
+	fn weighted_errors() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		assert_eq!(choose_weighted_index(&mut rng, &[]), Err(BranchError::Empty));
+		assert_eq!(choose_weighted_index(&mut rng, &[0, 0]), Err(BranchError::AllWeightsZero));
+		assert_eq!(choose_weighted_index(&mut rng, &[0, 5, 0]), Ok(1));
+	}
+}
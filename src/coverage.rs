@@ -0,0 +1,228 @@
+//! Counting how often each call site takes each of its branches.
+//!
+//! With the `coverage` feature, every macro invocation of this crate counts
+//! how often each of its branches is taken. This allows randomized tests to
+//! check that they still exercise all branches, e.g. after a refactoring
+//! changed some weights or probabilities:
+//!
+//! ```rust
+//! # use rand_pcg::Lcg64Xsh32;
+//! use random_branch::branch_using;
+//! use random_branch::coverage;
+//! let mut my_rng = /* snip */
+//! # Lcg64Xsh32::new(0,0);
+//!
+//! for _ in 0..100 {
+//!     branch_using!(my_rng, { 1, 2, 3 });
+//! }
+//!
+//! // At the end of the test run
+//! # #[cfg(not(feature = "deterministic"))]
+//! coverage::assert_all_branches_hit();
+//! ```
+//!
+//! Each call site is registered in a global registry when it is executed for
+//! the first time, thus a call site that is never executed is not reported at
+//! all. Afterwards, taking a branch costs a single relaxed atomic increment
+//! (besides a relaxed load checking the registration). Without the feature,
+//! the counters are zero-sized and their updates are empty functions, so they
+//! compile to nothing.
+
+use core::fmt;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::vec::Vec;
+
+
+/// All call sites that have been executed so far.
+static REGISTRY: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// A registered call site.
+struct Entry {
+	file: &'static str,
+	line: u32,
+	column: u32,
+	hits: &'static [AtomicU64],
+}
+
+
+/// The counters of a single call site with `N` branches.
+///
+/// Each macro invocation expands to a `static` of this type.
+#[doc(hidden)]
+pub struct Site<const N: usize> {
+	file: &'static str,
+	line: u32,
+	column: u32,
+	#[cfg(any(feature = "tracing", feature = "log"))]
+	label: Option<&'static str>,
+	registered: AtomicBool,
+	hits: [AtomicU64; N],
+}
+
+impl<const N: usize> Site<N> {
+	/// Creates the counters of the given call site.
+	pub const fn new(file: &'static str, line: u32, column: u32) -> Self {
+		Self {
+			file,
+			line,
+			column,
+			#[cfg(any(feature = "tracing", feature = "log"))]
+			label: None,
+			registered: AtomicBool::new(false),
+			hits: [const { AtomicU64::new(0) }; N],
+		}
+	}
+
+	/// Attaches the given label, which is logged with the `tracing` or `log`
+	/// feature.
+	#[cfg_attr(not(any(feature = "tracing", feature = "log")), allow(unused_variables))]
+	pub const fn with_label(self, label: &'static str) -> Self {
+		#[cfg(any(feature = "tracing", feature = "log"))]
+		return Self { label: Some(label), ..self };
+		#[cfg(not(any(feature = "tracing", feature = "log")))]
+		self
+	}
+
+	/// Counts that the branch `index` was taken.
+	#[inline]
+	pub fn hit(&'static self, index: usize) {
+		if !self.registered.load(Ordering::Relaxed) {
+			self.register();
+		}
+		if let Some(hits) = self.hits.get(index) {
+			hits.fetch_add(1, Ordering::Relaxed);
+		}
+
+		#[cfg(any(feature = "tracing", feature = "log"))]
+		crate::trace::selected(self.file, self.line, self.column, self.label, index, N);
+	}
+
+	#[cold]
+	fn register(&'static self) {
+		let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+		if !self.registered.swap(true, Ordering::Relaxed) {
+			registry.push(Entry {
+				file: self.file,
+				line: self.line,
+				column: self.column,
+				hits: &self.hits,
+			});
+		}
+	}
+}
+
+
+/// The counters of a single call site, as reported by [`report`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SiteCoverage {
+	/// The source file of the macro invocation.
+	pub file: &'static str,
+	/// The line of the macro invocation.
+	pub line: u32,
+	/// The column of the macro invocation.
+	pub column: u32,
+	/// How often each branch was taken, by index.
+	pub hits: Vec<u64>,
+}
+
+impl SiteCoverage {
+	/// Returns the indices of the branches that were never taken.
+	pub fn never_hit(&self) -> impl Iterator<Item = usize> + '_ {
+		self.hits.iter().enumerate().filter(|(_, &h)| h == 0).map(|(i, _)| i)
+	}
+
+	/// Returns the total number of times this call site was executed.
+	pub fn total(&self) -> u64 {
+		self.hits.iter().sum()
+	}
+}
+
+impl fmt::Display for SiteCoverage {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:{}:{}: {:?}", self.file, self.line, self.column, self.hits)
+	}
+}
+
+
+/// A snapshot of the counters of all call sites executed so far.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Report {
+	sites: Vec<SiteCoverage>,
+}
+
+impl Report {
+	/// Returns the call sites, in the order they were first executed.
+	pub fn sites(&self) -> &[SiteCoverage] {
+		&self.sites
+	}
+
+	/// Returns the call sites at the given file and line.
+	pub fn at<'a>(&'a self, file: &'a str, line: u32) -> impl Iterator<Item = &'a SiteCoverage> + 'a {
+		self.sites.iter().filter(move |s| s.file == file && s.line == line)
+	}
+
+	/// Asserts that every branch of every call site in this report was taken
+	/// at least once.
+	///
+	/// # Panics
+	///
+	/// Panics, listing all call sites with branches that were never taken.
+	#[track_caller]
+	pub fn assert_all_branches_hit(&self) {
+		let missed: Vec<&SiteCoverage> = self.sites.iter().filter(|s| s.never_hit().next().is_some()).collect();
+		if !missed.is_empty() {
+			let mut message = std::string::String::from("Some branches were never taken:");
+			for site in missed {
+				let never: Vec<usize> = site.never_hit().collect();
+				message += &std::format!("\n  {} (never taken: {:?})", site, never);
+			}
+			panic!("{}", message);
+		}
+	}
+}
+
+impl fmt::Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for site in &self.sites {
+			writeln!(f, "{}", site)?;
+		}
+		Ok(())
+	}
+}
+
+
+/// Returns a snapshot of the counters of all call sites executed so far.
+///
+/// The counters of concurrently executing call sites may be updated while
+/// the snapshot is taken.
+pub fn report() -> Report {
+	let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+	Report {
+		sites: registry
+			.iter()
+			.map(|e| SiteCoverage {
+				file: e.file,
+				line: e.line,
+				column: e.column,
+				hits: e.hits.iter().map(|h| h.load(Ordering::Relaxed)).collect(),
+			})
+			.collect(),
+	}
+}
+
+/// Asserts that every branch of every call site executed so far was taken at
+/// least once.
+///
+/// This is a shorthand for `report().assert_all_branches_hit()`, meant to be
+/// called at the end of a test run.
+///
+/// # Panics
+///
+/// Panics, listing all call sites with branches that were never taken.
+#[track_caller]
+pub fn assert_all_branches_hit() {
+	report().assert_all_branches_hit();
+}
@@ -0,0 +1,78 @@
+//! The shuffle bag of [`branch_deck`](crate::branch_deck).
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+
+/// A shuffle bag of `N` branches.
+///
+/// A deck is created by [`branch_deck`](crate::branch_deck). Each call to
+/// [`draw`](Deck::draw) evaluates one branch, just like
+/// [`branch_using`](crate::branch_using) would do. However, the branches are
+/// drawn like cards from a shuffled deck: each cycle of `N` draws uses a fresh
+/// random permutation of the branches, so every branch is drawn exactly once
+/// before any branch is repeated. Once a cycle is completed, the deck is
+/// reshuffled.
+///
+/// This avoids the streaks that pure uniform sampling produces, which is
+/// often desired e.g. in games and playlists.
+///
+/// The branches are stored as a single function `F`, which evaluates a branch
+/// given its index. Together with the permutation being stored inline, this
+/// requires neither `std` nor an allocator.
+#[derive(Debug, Clone)]
+pub struct Deck<F, const N: usize> {
+	branch: F,
+	order: [usize; N],
+	drawn: usize,
+}
+
+impl<F, const N: usize> Deck<F, N> {
+	/// Creates a deck with `N` branches, evaluated by `branch` given the index
+	/// of the branch.
+	///
+	/// The first draw will shuffle the deck.
+	pub fn new<T>(branch: F) -> Self
+	where
+		F: FnMut(usize) -> T,
+	{
+		Self {
+			branch,
+			order: core::array::from_fn(|i| i),
+			drawn: N,
+		}
+	}
+
+	/// Returns the number of branches that are left in the current cycle.
+	///
+	/// Returns zero if the next draw will reshuffle the deck.
+	pub fn remaining(&self) -> usize {
+		N - self.drawn
+	}
+
+	/// Evaluates the next branch of the deck.
+	///
+	/// If all branches of the current cycle have been drawn, the deck is
+	/// reshuffled using the given RNG first. Otherwise, the RNG is not used.
+	///
+	/// # Panics
+	///
+	/// Panics if the deck has no branches, i.e. `N` is zero.
+	#[track_caller]
+	pub fn draw<R: Rng + ?Sized, T>(&mut self, rng: &mut R) -> T
+	where
+		F: FnMut(usize) -> T,
+	{
+		assert!(N > 0, "You must provide at least one choice.");
+
+		if self.drawn == N {
+			if !crate::select::DETERMINISTIC {
+				self.order.shuffle(rng);
+			}
+			self.drawn = 0;
+		}
+		let index = self.order[self.drawn];
+		self.drawn += 1;
+		(self.branch)(index)
+	}
+}
@@ -0,0 +1,180 @@
+//! The branch selection as [`Distribution`] of indices.
+
+use rand::distributions::Distribution;
+use rand::Rng;
+
+use crate::weighted;
+use crate::BranchError;
+
+
+/// The uniform selection of one of `n` branches, as a [`Distribution`] of
+/// branch indices in `0..n`.
+///
+/// This is the selection of [`branch_using`](crate::branch_using) as a
+/// first-class distribution, so it can be combined with the sampling
+/// utilities of `rand`, such as [`Rng::sample_iter`]. Like the macros, each
+/// sample goes through the optional hooks, e.g. of the `test-override`
+/// feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use rand::Rng;
+/// use random_branch::branch_on;
+/// use random_branch::UniformBranch;
+/// let my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let directions: Vec<&str> = my_rng
+///     .sample_iter(UniformBranch::new(4))
+///     .take(10)
+///     .map(|index| branch_on!(index, { "north", "east", "south", "west" }))
+///     .collect();
+/// assert_eq!(directions.len(), 10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UniformBranch {
+	n: usize,
+}
+
+impl UniformBranch {
+	/// Creates the selection of one of `n` branches.
+	///
+	/// # Panics
+	///
+	/// Panics if `n` is zero, since there is no branch to select.
+	#[track_caller]
+	pub const fn new(n: usize) -> Self {
+		assert!(n > 0, "You must provide at least one choice.");
+		Self { n }
+	}
+
+	/// Creates the selection of a branch with a probability proportional to
+	/// its weight.
+	///
+	/// This is the selection of
+	/// [`branch_weighted_using`](crate::branch_weighted_using). Each sample
+	/// scans all weights, so for many weights, which are sampled repeatedly,
+	/// consider an [`AliasTable`](crate::AliasTable) instead, which is a
+	/// `Distribution<usize>` as well.
+	///
+	/// Returns [`BranchError::Empty`] if there are no weights, and
+	/// [`BranchError::AllWeightsZero`] if all of them are zero.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// # use rand_pcg::Lcg64Xsh32;
+	/// use rand::Rng;
+	/// use random_branch::UniformBranch;
+	/// let my_rng = /* snip */
+	/// # Lcg64Xsh32::new(0,0);
+	///
+	/// let dist = UniformBranch::weighted(&[3, 0, 1]).unwrap();
+	/// assert!(my_rng.sample_iter(dist).take(100).all(|index| index != 1));
+	/// ```
+	pub fn weighted(weights: &[u32]) -> Result<WeightedBranch<'_>, BranchError> {
+		WeightedBranch::new(weights)
+	}
+
+	/// Returns the number of branches, i.e. the indices range over
+	/// `0..count()`.
+	pub const fn count(&self) -> usize {
+		self.n
+	}
+}
+
+impl Distribution<usize> for UniformBranch {
+	#[track_caller]
+	fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+		crate::select::uniform(rng, self.n)
+	}
+}
+
+
+/// The selection of a branch with a probability proportional to its weight,
+/// as a [`Distribution`] of branch indices.
+///
+/// See [`UniformBranch::weighted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WeightedBranch<'a> {
+	weights: &'a [u32],
+}
+
+impl<'a> WeightedBranch<'a> {
+	/// Creates the selection with the given weights.
+	///
+	/// Returns [`BranchError::Empty`] if there are no weights, and
+	/// [`BranchError::AllWeightsZero`] if all of them are zero.
+	pub fn new(weights: &'a [u32]) -> Result<Self, BranchError> {
+		if weights.is_empty() {
+			return Err(BranchError::Empty);
+		}
+		weighted::first(weights)?;
+		Ok(Self { weights })
+	}
+
+	/// Returns the number of branches, i.e. the indices range over
+	/// `0..count()`.
+	pub const fn count(&self) -> usize {
+		self.weights.len()
+	}
+}
+
+impl Distribution<usize> for WeightedBranch<'_> {
+	fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+		match weighted::select(self.weights, rng) {
+			Ok(index) => index,
+			// Checked by `new`
+			Err(_) => unreachable!(),
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	#[cfg(not(feature = "deterministic"))]
+	use rand_pcg::Lcg64Xsh32;
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn uniform_sample_iter() {
+		let rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [0_u32; 3];
+		for index in rng.sample_iter(UniformBranch::new(3)).take(30_000) {
+			counts[index] += 1;
+		}
+		for &count in &counts {
+			assert!(9_500 < count && count < 10_500, "{:?}", counts);
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn weighted_sample_iter() {
+		let rng = Lcg64Xsh32::new(42, 0);
+		let dist = UniformBranch::weighted(&[1, 0, 3]).unwrap();
+		let mut counts = [0_u32; 3];
+		for index in rng.sample_iter(&dist).take(40_000) {
+			counts[index] += 1;
+		}
+		assert_eq!(counts[1], 0);
+		assert!(9_500 < counts[0] && counts[0] < 10_500, "{:?}", counts);
+		assert_eq!(dist.count(), 3);
+	}
+
+	#[test]
+	fn weighted_errors() {
+		assert_eq!(UniformBranch::weighted(&[]), Err(BranchError::Empty));
+		assert_eq!(UniformBranch::weighted(&[0, 0]), Err(BranchError::AllWeightsZero));
+	}
+
+	#[test]
+	#[should_panic(expected = "at least one choice")]
+	fn uniform_rejects_zero() {
+		UniformBranch::new(0);
+	}
+}
@@ -0,0 +1,68 @@
+//! Branching over a runtime list of closures.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use rand::RngCore;
+
+use crate::BranchError;
+
+
+/// A boxed branch of [`choose_and_run`].
+pub type Branch<'a, T> = Box<dyn FnOnce() -> T + 'a>;
+
+
+/// Runs one uniformly selected closure of `branches` and returns its result.
+///
+/// This is the runtime counterpart of [`branch_using`](crate::branch_using),
+/// for when the number of branches is only known at runtime. The remaining
+/// closures are dropped without being called.
+///
+/// This function only requires the `alloc` feature, so it can be used in
+/// `no_std` crates that have an allocator.
+///
+/// # Panics
+///
+/// Panics if `branches` is empty.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::choose_and_run;
+/// use random_branch::Branch;
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let mut branches: Vec<Branch<u32>> = Vec::new();
+/// for i in 1..=3 {
+///     branches.push(Box::new(move || i * 10));
+/// }
+///
+/// let num = choose_and_run(&mut my_rng, branches);
+/// assert!([10, 20, 30].contains(&num));
+/// ```
+#[track_caller]
+pub fn choose_and_run<T, R: RngCore + ?Sized>(rng: &mut R, mut branches: Vec<Branch<'_, T>>) -> T {
+	assert!(!branches.is_empty(), "You must provide at least one choice.");
+	let index = crate::select::uniform(rng, branches.len());
+	branches.swap_remove(index)()
+}
+
+/// Runs one uniformly selected closure of `branches`, or fails if there are
+/// none.
+///
+/// This function dose essentially the same as [`choose_and_run`], but instead
+/// of panicking, it returns [`BranchError::Empty`] if `branches` is empty,
+/// without touching the RNG.
+#[track_caller]
+pub fn try_choose_and_run<T, R: RngCore + ?Sized>(
+	rng: &mut R,
+	branches: Vec<Branch<'_, T>>,
+) -> Result<T, BranchError> {
+	if branches.is_empty() {
+		return Err(BranchError::Empty);
+	}
+	Ok(choose_and_run(rng, branches))
+}
@@ -0,0 +1,58 @@
+//! The common error of the fallible entry points.
+
+use core::fmt;
+
+use crate::AllWeightsZero;
+
+
+/// The error of the fallible (`try_*`) functions and macros of this crate.
+///
+/// Which variant is produced by which operation:
+/// * [`Empty`](Self::Empty): there are no branches at all, i.e.
+///   [`try_choose_index`](crate::try_choose_index) with zero choices,
+///   [`choose_weighted_index`](crate::choose_weighted_index),
+///   [`UniformBranch::weighted`](crate::UniformBranch::weighted), and
+///   `AliasTable::new` without weights, or `try_choose_and_run` with an
+///   empty vector. The macros instead reject an empty list of branches at
+///   compile time.
+/// * [`AllWeightsZero`](Self::AllWeightsZero): there are branches, but all
+///   their weights are zero, e.g. in
+///   [`try_branch_weighted_using`](crate::try_branch_weighted_using),
+///   [`choose_weighted_index`](crate::choose_weighted_index),
+///   [`UniformBranch::weighted`](crate::UniformBranch::weighted), or
+///   `AliasTable::new`.
+/// * [`IndexOutOfRange`](Self::IndexOutOfRange): a caller-provided index
+///   doesn't select any of the branches, e.g. in
+///   [`try_branch_on`](crate::try_branch_on).
+///
+/// In each case, no branch is evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BranchError {
+	/// There are no branches to choose from.
+	Empty,
+	/// All branches have a weight of zero.
+	AllWeightsZero,
+	/// The given index is not less than the number of branches.
+	IndexOutOfRange,
+}
+
+impl fmt::Display for BranchError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Empty => f.write_str("there are no branches"),
+			Self::AllWeightsZero => fmt::Display::fmt(&AllWeightsZero, f),
+			Self::IndexOutOfRange => f.write_str("the branch index is out of range"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+impl std::error::Error for BranchError {}
+
+impl From<AllWeightsZero> for BranchError {
+	fn from(_: AllWeightsZero) -> Self {
+		Self::AllWeightsZero
+	}
+}
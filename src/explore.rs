@@ -0,0 +1,156 @@
+//! Exploring every path of random branches through some code, instead of
+//! sampling them.
+
+use core::cell::RefCell;
+use core::fmt;
+use core::panic::Location;
+use std::vec::Vec;
+
+
+std::thread_local! {
+	/// The path of the current execution of the current thread, if exploring.
+	static PATH: RefCell<Option<Path>> = const { RefCell::new(None) };
+}
+
+/// The branches taken by a single execution.
+#[derive(Debug)]
+struct Path {
+	/// The indices to take at the first decisions, new decisions take `0`.
+	prefix: Vec<usize>,
+	/// The taken index and the number of branches of each decision so far.
+	decisions: Vec<(usize, usize)>,
+}
+
+
+/// The error of [`explore_all`], if there are more paths than allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TooManyPaths {
+	max_paths: usize,
+}
+
+impl TooManyPaths {
+	/// Returns the number of paths that were explored before giving up.
+	pub fn max_paths(&self) -> usize {
+		self.max_paths
+	}
+}
+
+impl fmt::Display for TooManyPaths {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "there are more than {} branch paths", self.max_paths)
+	}
+}
+
+impl std::error::Error for TooManyPaths {}
+
+
+/// Runs `f` once for every distinct sequence of branches it can take.
+///
+/// While `f` runs on the current thread, every macro that selects a single
+/// branch uniformly, such as [`branch`](crate::branch),
+/// [`branch_using`](crate::branch_using) or
+/// [`branch_fast`](crate::branch_fast), doesn't draw from its RNG, but takes
+/// a branch chosen by the exploration, the same macros that
+/// [`with_forced`](crate::test_override::with_forced) affects. The tree of
+/// decisions is discovered lazily and depth-first: the first execution takes
+/// the first branch at every decision, while recording how many branches
+/// each decision had. Each further execution repeats the decisions of the
+/// previous one up to the last decision with an untaken branch, takes that
+/// next branch, and then again the first branches of all following
+/// decisions. Thus, nested decisions and decisions whose number of branches
+/// depends on earlier ones are explored as well, as they are rediscovered on
+/// each path.
+///
+/// Returns the number of executions, i.e. the number of distinct paths, or
+/// an error if there are more than `max_paths` of them, e.g. for an unbounded
+/// recursive generator. In the latter case, `f` has run `max_paths` times.
+///
+/// `f` must be deterministic apart from the random branches, i.e. given the
+/// same branches, it must reach the same decisions with the same number of
+/// branches. Branches forced by `with_forced` inside of `f` are not explored.
+///
+/// This function is only available with the `test-override` feature.
+///
+/// # Panics
+///
+/// A macro panics, reporting its call site, if a repeated decision has fewer
+/// branches than in the previous execution. If `f` panics, the exploration
+/// is aborted and the panic is propagated.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch;
+/// use random_branch::explore::explore_all;
+///
+/// let mut seen = Vec::new();
+/// let paths = explore_all(100, || {
+///     let size = branch!("small", "medium", "large");
+///     let color = branch!("red", "blue");
+///     seen.push((size, color));
+/// });
+/// assert_eq!(paths, Ok(6));
+/// assert_eq!(seen[0], ("small", "red"));
+/// assert_eq!(seen[1], ("small", "blue"));
+/// assert_eq!(seen[5], ("large", "blue"));
+/// ```
+pub fn explore_all(max_paths: usize, mut f: impl FnMut()) -> Result<usize, TooManyPaths> {
+	/// Restores the previous path when dropped.
+	struct Restore(Option<Path>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			let previous = self.0.take();
+			PATH.with(|p| *p.borrow_mut() = previous);
+		}
+	}
+
+	let mut prefix = Vec::new();
+	let mut paths = 0;
+	loop {
+		if paths == max_paths {
+			return Err(TooManyPaths {
+				max_paths,
+			});
+		}
+
+		let path = Path {
+			prefix,
+			decisions: Vec::new(),
+		};
+		let restore = Restore(PATH.with(|p| p.borrow_mut().replace(path)));
+		f();
+		let decisions = PATH.with(|p| p.borrow_mut().take()).map(|path| path.decisions).unwrap_or_default();
+		drop(restore);
+		paths += 1;
+
+		// Continue with the next branch of the last decision that has one
+		match decisions.iter().rposition(|&(index, count)| index + 1 < count) {
+			Some(depth) => {
+				prefix = decisions[..=depth].iter().map(|&(index, _)| index).collect();
+				prefix[depth] += 1;
+			},
+			None => return Ok(paths),
+		}
+	}
+}
+
+/// Takes the next explored index for a selection among `count` branches, if
+/// exploring.
+#[track_caller]
+pub(crate) fn next(count: usize) -> Option<usize> {
+	let location = Location::caller();
+	PATH.with(|p| {
+		let mut p = p.borrow_mut();
+		let path = p.as_mut()?;
+		let index = path.prefix.get(path.decisions.len()).copied().unwrap_or(0);
+		if index >= count {
+			panic!(
+				"The explored branch index {} is out of range for {} branches at {}, the decisions must not change between executions",
+				index, count, location,
+			);
+		}
+		path.decisions.push((index, count));
+		Some(index)
+	})
+}
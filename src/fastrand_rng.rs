@@ -0,0 +1,95 @@
+//! The `fastrand` backend of [`branch`](crate::branch) and
+//! [`branch_fastrand_using`](crate::branch_fastrand_using).
+
+#[cfg(feature = "rand")]
+use rand::RngCore;
+
+use crate::select::FastrandIndex;
+
+
+/// An RNG drawing from a [`fastrand::Rng`].
+///
+/// This adapter lets a `fastrand` generator be used with all macros of this
+/// crate, e.g. with [`branch_using`](crate::branch_using), while
+/// [`branch_fastrand_using`](crate::branch_fastrand_using) takes the
+/// generator itself, and doesn't need `rand` at all.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_using;
+/// use random_branch::FastrandRng;
+///
+/// let mut rng = fastrand::Rng::with_seed(42);
+/// let x = branch_using!(FastrandRng::new(&mut rng), { 1, 2, 3 });
+/// # assert!((1..=3).contains(&x));
+/// ```
+#[derive(Debug)]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(all(feature = "fastrand", feature = "rand"))))]
+pub struct FastrandRng<'a>(&'a mut fastrand::Rng);
+
+#[cfg(feature = "rand")]
+impl<'a> FastrandRng<'a> {
+	/// Wraps the given generator.
+	pub fn new(rng: &'a mut fastrand::Rng) -> Self {
+		Self(rng)
+	}
+}
+
+#[cfg(feature = "rand")]
+impl RngCore for FastrandRng<'_> {
+	fn next_u32(&mut self) -> u32 {
+		self.0.u32(..)
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0.u64(..)
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		self.0.fill(dest)
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
+
+
+/// An RNG drawing from the thread-local generator of `fastrand`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FastrandGlobal;
+
+#[cfg(feature = "rand")]
+impl RngCore for FastrandGlobal {
+	fn next_u32(&mut self) -> u32 {
+		fastrand::u32(..)
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		fastrand::u64(..)
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		fastrand::fill(dest)
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
+
+impl FastrandIndex for FastrandGlobal {
+	fn index(&mut self, count: usize) -> usize {
+		fastrand::usize(..count)
+	}
+}
+
+impl FastrandIndex for fastrand::Rng {
+	fn index(&mut self, count: usize) -> usize {
+		self.usize(..count)
+	}
+}
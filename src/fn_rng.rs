@@ -0,0 +1,79 @@
+//! An RNG adapter for plain closures.
+
+use rand::RngCore;
+
+
+/// Wraps a closure yielding random `u64`s into an RNG.
+///
+/// This allows arbitrary sources of randomness, that are just a function
+/// returning random `u64`s, to be used with all macros of this crate, e.g.
+/// with [`branch_using`](crate::branch_using), without implementing
+/// [`RngCore`] by hand. All other values are derived from the `u64`s, e.g.
+/// `next_u32` uses the lower half of a `u64`.
+///
+/// The closure should return uniformly distributed `u64`s, i.e. all bits
+/// should be random. Branches are then selected without bias: this crate
+/// does *not* simply take the `u64` modulo the number of branches, which
+/// would favor the lower indices whenever the number of branches does not
+/// divide `2^64`, instead it uses [`rand`]'s unbiased rejection sampling,
+/// which may call the closure more than once per selection.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_using;
+/// use random_branch::from_fn;
+///
+/// // A xorshift generator, as an example of some exotic source
+/// let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+/// let mut next_u64 = move || {
+///     state ^= state << 13;
+///     state ^= state >> 7;
+///     state ^= state << 17;
+///     state
+/// };
+///
+/// let num = branch_using!(from_fn(&mut next_u64), {
+///     1,
+///     2,
+///     3,
+/// });
+/// assert!((1..=3).contains(&num));
+/// ```
+pub fn from_fn<F: FnMut() -> u64>(f: F) -> FnRng<F> {
+	FnRng(f)
+}
+
+
+/// An RNG that draws from a closure, created by [`from_fn`].
+#[derive(Debug, Clone, Copy)]
+pub struct FnRng<F>(F);
+
+impl<F: FnMut() -> u64> FnRng<F> {
+	/// Returns the wrapped closure.
+	pub fn into_inner(self) -> F {
+		self.0
+	}
+}
+
+impl<F: FnMut() -> u64> RngCore for FnRng<F> {
+	fn next_u32(&mut self) -> u32 {
+		(self.0)() as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		(self.0)()
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		for chunk in dest.chunks_mut(8) {
+			let bytes = (self.0)().to_le_bytes();
+			chunk.copy_from_slice(&bytes[..chunk.len()]);
+		}
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
@@ -0,0 +1,67 @@
+//! The generator of [`branch_generator`](crate::branch_generator).
+
+use core::ops::Bound;
+
+use bolero_generator::driver::Driver;
+use bolero_generator::ValueGenerator;
+
+
+/// A `bolero` generator, which yields the value of a branch selected by the
+/// driver.
+///
+/// This is created by [`branch_generator`](crate::branch_generator).
+#[derive(Debug, Clone)]
+pub struct BranchGenerator<F, const N: usize> {
+	weights: Option<[u32; N]>,
+	branch: F,
+}
+
+impl<F, const N: usize> BranchGenerator<F, N> {
+	/// Creates a generator selecting one of `N` branches uniformly.
+	pub const fn uniform(branch: F) -> Self {
+		Self {
+			weights: None,
+			branch,
+		}
+	}
+
+	/// Creates a generator selecting one of `N` branches with the given
+	/// weights.
+	///
+	/// # Panics
+	///
+	/// Panics if all weights are zero.
+	#[track_caller]
+	pub fn weighted(weights: [u32; N], branch: F) -> Self {
+		assert!(weights.iter().any(|&w| w > 0), "All weights are zero.");
+		Self {
+			weights: Some(weights),
+			branch,
+		}
+	}
+}
+
+impl<F, T, const N: usize> ValueGenerator for BranchGenerator<F, N>
+where
+	F: Fn(usize) -> T,
+	T: 'static,
+{
+	type Output = T;
+
+	fn generate<D: Driver>(&self, driver: &mut D) -> Option<T> {
+		let index = match &self.weights {
+			None => driver.gen_variant(N, 0)?,
+			Some(weights) => {
+				let total: u64 = weights.iter().map(|&w| u64::from(w)).sum();
+				let mut remaining = driver.gen_u64(Bound::Included(&0), Bound::Excluded(&total))?;
+				let mut index = 0;
+				while remaining >= u64::from(weights[index]) {
+					remaining -= u64::from(weights[index]);
+					index += 1;
+				}
+				index
+			}
+		};
+		Some((self.branch)(index))
+	}
+}
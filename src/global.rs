@@ -0,0 +1,159 @@
+//! The global RNG of [`branch`](crate::branch) without `std`.
+
+use alloc::boxed::Box;
+#[cfg(feature = "critical-section")]
+use core::cell::RefCell;
+use core::fmt;
+
+use rand::RngCore;
+
+
+/// The global RNG, once set, guarded by a spin lock.
+#[cfg(not(feature = "critical-section"))]
+static GLOBAL: spin::Once<spin::Mutex<Box<dyn RngCore + Send>>> = spin::Once::new();
+
+/// The global RNG, if set, guarded by critical sections.
+#[cfg(feature = "critical-section")]
+static GLOBAL: critical_section::Mutex<RefCell<Option<Box<dyn RngCore + Send>>>> =
+	critical_section::Mutex::new(RefCell::new(None));
+
+/// Stores the global RNG, unless it is already set.
+#[cfg(not(feature = "critical-section"))]
+fn store(rng: Box<dyn RngCore + Send>) -> Result<(), SetGlobalRngError> {
+	let mut rng = Some(rng);
+	GLOBAL.call_once(|| spin::Mutex::new(rng.take().unwrap()));
+	match rng {
+		Some(_) => Err(SetGlobalRngError),
+		None => Ok(()),
+	}
+}
+
+/// Stores the global RNG, unless it is already set.
+#[cfg(feature = "critical-section")]
+fn store(rng: Box<dyn RngCore + Send>) -> Result<(), SetGlobalRngError> {
+	critical_section::with(|cs| {
+		let mut global = GLOBAL.borrow_ref_mut(cs);
+		match *global {
+			Some(_) => Err(SetGlobalRngError),
+			None => {
+				*global = Some(rng);
+				Ok(())
+			},
+		}
+	})
+}
+
+/// Calls `f` with the locked global RNG, if set.
+#[cfg(not(feature = "critical-section"))]
+fn with_global<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> Option<T> {
+	GLOBAL.get().map(|global| f(&mut **global.lock()))
+}
+
+/// Calls `f` with the global RNG within a critical section, if set.
+#[cfg(feature = "critical-section")]
+fn with_global<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> Option<T> {
+	critical_section::with(|cs| GLOBAL.borrow_ref_mut(cs).as_mut().map(|rng| f(&mut **rng)))
+}
+
+
+/// Sets the global RNG, used by [`GlobalRng`].
+///
+/// Without `std`, the [`branch`](crate::branch) macro draws from this RNG,
+/// so it must be set before the first invocation, e.g. at the start of
+/// `main`, typically seeded from a hardware RNG. It can only be set once,
+/// further calls return an error and drop the given RNG.
+///
+/// This function is only available with the `global-rng` feature, which
+/// requires an allocator.
+///
+/// # Examples
+///
+/// ```rust
+/// use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+/// use random_branch::set_global_rng;
+/// use random_branch::GlobalRng;
+///
+/// set_global_rng(Lcg64Xsh32::new(42, 0)).unwrap();
+/// assert!(set_global_rng(Lcg64Xsh32::new(43, 0)).is_err());
+///
+/// let x = branch_using!(GlobalRng, { 1, 2, 3 });
+/// # assert!((1..=3).contains(&x));
+/// ```
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "global-rng")))]
+pub fn set_global_rng<R: RngCore + Send + 'static>(rng: R) -> Result<(), SetGlobalRngError> {
+	store(Box::new(rng))
+}
+
+
+/// The error of [`set_global_rng`], if the global RNG was already set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "global-rng")))]
+pub struct SetGlobalRngError;
+
+impl fmt::Display for SetGlobalRngError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("the global RNG is already set")
+	}
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+impl std::error::Error for SetGlobalRngError {}
+
+
+/// An RNG drawing from the global RNG, see [`set_global_rng`].
+///
+/// Each call locks the global RNG just for drawing the requested values, e.g.
+/// a single `u32` or `u64` for most selections. By default, the lock is a
+/// spin lock, which is fine for multiple threads, but this RNG must not be
+/// used from an interrupt handler that may interrupt another use of it, which
+/// would spin forever.
+///
+/// With the `critical-section` feature, the global RNG is instead guarded by
+/// the [`critical-section`](https://docs.rs/critical-section) crate, i.e. a
+/// critical section is acquired for each draw, which e.g. disables interrupts
+/// on a single-core microcontroller. Thus, an interrupt handler can't
+/// interrupt a draw, but only run before or after it, so it can use this RNG,
+/// and [`branch`](crate::branch), without deadlocking or corrupting the state
+/// of the RNG. Only the draws happen within the critical section, the
+/// selected branch is evaluated outside of it. The final binary must provide
+/// an implementation of the critical section, e.g. via the
+/// `critical-section-single-core` feature of the `cortex-m` crate, or the
+/// `std` feature of the `critical-section` crate for hosted targets.
+///
+/// Without `std`, [`branch`](crate::branch) uses this RNG implicitly, but
+/// you can pass it to other macros or functions that expect an RNG too. With
+/// `std`, `branch` uses the [`ThreadBranchRng`](crate::ThreadBranchRng)
+/// instead, but this type can still be used explicitly.
+///
+/// # Panics
+///
+/// Drawing from this RNG panics if the global RNG has not been set yet.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "global-rng")))]
+pub struct GlobalRng;
+
+impl GlobalRng {
+	fn with<T>(self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+		with_global(f).expect("The global RNG is not set, call `random_branch::set_global_rng` first")
+	}
+}
+
+impl RngCore for GlobalRng {
+	fn next_u32(&mut self) -> u32 {
+		self.with(|rng| rng.next_u32())
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.with(|rng| rng.next_u64())
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		self.with(|rng| rng.fill_bytes(dest))
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.with(|rng| rng.try_fill_bytes(dest))
+	}
+}
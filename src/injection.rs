@@ -0,0 +1,52 @@
+//! The global default rate of
+//! [`with_probability_default_using`](crate::with_probability_default_using).
+
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+
+/// The bits of the default rate as `f64`, initially `0.0`.
+static DEFAULT_RATE: AtomicU64 = AtomicU64::new(0);
+
+
+/// Sets the process-wide default injection rate.
+///
+/// This is the probability used by
+/// [`with_probability_default_using`](crate::with_probability_default_using)
+/// and [`with_probability_default`](crate::with_probability_default), so it
+/// is a single knob to e.g. crank up the rate of injected faults in
+/// integration tests without editing any call site. Initially, the rate is
+/// `0.0`, i.e. those macros never evaluate their expression.
+///
+/// The rate is stored in an atomic with relaxed ordering. So it is safe to
+/// set it from any thread at any time, and every macro sees either the old or
+/// the new rate, but it does not synchronize any other memory. In particular,
+/// a new rate is not guaranteed to be seen immediately by other threads,
+/// unless they synchronize with the setting thread by other means, e.g. by
+/// being spawned afterwards or via a lock.
+///
+/// # Panics
+///
+/// Panics if `rate` is not within `0.0..=1.0`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::default_injection_rate;
+/// use random_branch::set_default_injection_rate;
+///
+/// set_default_injection_rate(0.25);
+/// assert_eq!(default_injection_rate(), 0.25);
+/// ```
+#[track_caller]
+pub fn set_default_injection_rate(rate: f64) {
+	assert!((0.0..=1.0).contains(&rate), "The injection rate must be within 0.0..=1.0, but is {}", rate);
+	DEFAULT_RATE.store(rate.to_bits(), Ordering::Relaxed);
+}
+
+/// Returns the process-wide default injection rate.
+///
+/// See [`set_default_injection_rate`] for details.
+pub fn default_injection_rate() -> f64 {
+	f64::from_bits(DEFAULT_RATE.load(Ordering::Relaxed))
+}
@@ -0,0 +1,67 @@
+//! The iterator of [`branch_iter_using`](crate::branch_iter_using).
+
+use core::iter::FusedIterator;
+
+use rand::RngCore;
+
+
+/// An infinite iterator of random branches.
+///
+/// This iterator is created by [`branch_iter_using`](crate::branch_iter_using)
+/// and [`branch_iter`](crate::branch_iter). On each call to `next`, it calls
+/// the given function with the RNG, which is supposed to draw a fresh random
+/// branch and evaluate it. Thus, it is lazy, nothing is evaluated and no
+/// randomness is consumed until `next` is called.
+///
+/// The iterator holds the RNG `R` by value. However, since `&mut R` is an
+/// RNG too, you can also just pass a mutable reference to keep using the RNG
+/// after the iterator has been dropped.
+///
+/// This iterator never ends, so use e.g. [`Iterator::take`] to limit it.
+#[derive(Debug, Clone)]
+pub struct BranchIter<R, F> {
+	rng: R,
+	branch: F,
+}
+
+impl<R, F> BranchIter<R, F> {
+	/// Creates an iterator calling `branch` with `rng` for each item.
+	pub fn new<T>(rng: R, branch: F) -> Self
+	where
+		R: RngCore,
+		F: FnMut(&mut R) -> T,
+	{
+		Self {
+			rng,
+			branch,
+		}
+	}
+
+	/// Returns the RNG, consuming the iterator.
+	pub fn into_rng(self) -> R {
+		self.rng
+	}
+}
+
+impl<R, F, T> Iterator for BranchIter<R, F>
+where
+	R: RngCore,
+	F: FnMut(&mut R) -> T,
+{
+	type Item = T;
+
+	fn next(&mut self) -> Option<T> {
+		Some((self.branch)(&mut self.rng))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(usize::MAX, None)
+	}
+}
+
+impl<R, F, T> FusedIterator for BranchIter<R, F>
+where
+	R: RngCore,
+	F: FnMut(&mut R) -> T,
+{
+}
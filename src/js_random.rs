@@ -0,0 +1,40 @@
+//! The `Math.random()` backend of [`branch`](crate::branch) on wasm32.
+
+use rand::RngCore;
+
+use crate::bench::fill_bytes_via_u64;
+
+
+/// An RNG drawing from JavaScript's `Math.random()`.
+///
+/// Each `u32` is taken from the upper bits of a single `Math.random()`
+/// number, which the common engines generate with 52 or more random bits,
+/// e.g. via xorshift128+ in V8. It is cheap and needs no source of entropy
+/// on the Rust side, but it is **not** cryptographically secure, and its
+/// quality depends on the JavaScript engine.
+///
+/// This type is only available with the `js-random` feature on wasm32
+/// targets.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(all(feature = "js-random", target_arch = "wasm32"))))]
+pub struct JsRandomRng;
+
+impl RngCore for JsRandomRng {
+	fn next_u32(&mut self) -> u32 {
+		// `Math.random()` is in `[0, 1)`, so this is within `u32`
+		(js_sys::Math::random() * 4_294_967_296.0) as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		(u64::from(self.next_u32()) << 32) | u64::from(self.next_u32())
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		fill_bytes_via_u64(self, dest)
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
@@ -0,0 +1,199 @@
+//! Runtime support for [`branch_keyed`](crate::branch_keyed).
+
+use crate::AllWeightsZero;
+
+
+/// A key with a stable byte encoding, that can be used with
+/// [`branch_keyed`](crate::branch_keyed).
+///
+/// Unlike [`Hash`](core::hash::Hash), whose encoding may change between
+/// versions of Rust and differs between platforms, this trait defines the
+/// exact bytes that are hashed by [`key_hash`]:
+/// * strings and byte slices are encoded as their bytes,
+/// * integers are encoded as their little-endian bytes, `usize` and `isize`
+///   as `u64` and `i64` respectively, so they are the same on all platforms,
+/// * `char` is encoded as `u32`, and `bool` as a single byte `0` or `1`,
+/// * references are encoded as their referent.
+///
+/// Notice that the encoding does not include the type, so e.g. `1_u8` and
+/// `true` are the same key.
+pub trait StableKey {
+	/// Calls `write` with the bytes of this key.
+	fn write_key(&self, write: &mut dyn FnMut(&[u8]));
+}
+
+impl StableKey for str {
+	fn write_key(&self, write: &mut dyn FnMut(&[u8])) {
+		write(self.as_bytes())
+	}
+}
+
+impl StableKey for [u8] {
+	fn write_key(&self, write: &mut dyn FnMut(&[u8])) {
+		write(self)
+	}
+}
+
+impl<const N: usize> StableKey for [u8; N] {
+	fn write_key(&self, write: &mut dyn FnMut(&[u8])) {
+		write(self)
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl StableKey for alloc::string::String {
+	fn write_key(&self, write: &mut dyn FnMut(&[u8])) {
+		write(self.as_bytes())
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl StableKey for alloc::vec::Vec<u8> {
+	fn write_key(&self, write: &mut dyn FnMut(&[u8])) {
+		write(self)
+	}
+}
+
+impl<T: StableKey + ?Sized> StableKey for &T {
+	fn write_key(&self, write: &mut dyn FnMut(&[u8])) {
+		(**self).write_key(write)
+	}
+}
+
+macro_rules! impl_stable_key_for_int {
+	( $( $int:ty => $as:ty ),* ) => {
+		$(
+			impl StableKey for $int {
+				fn write_key(&self, write: &mut dyn FnMut(&[u8])) {
+					write(&(*self as $as).to_le_bytes())
+				}
+			}
+		)*
+	};
+}
+
+impl_stable_key_for_int!(
+	u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => u64,
+	i8 => i8, i16 => i16, i32 => i32, i64 => i64, i128 => i128, isize => i64,
+	char => u32, bool => u8
+);
+
+
+/// Hashes the given key in a stable way.
+///
+/// The hash is the 64-bit FNV-1a hash of the bytes of the key, as defined by
+/// [`StableKey`], followed by the `fmix64` finalizer of MurmurHash3 to mix
+/// all bits. This function is guaranteed to return the same hash for the
+/// same key in all versions of this crate, on all platforms.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::key_hash;
+///
+/// # #[cfg(feature = "alloc")]
+/// assert_eq!(key_hash("user-42"), key_hash(&String::from("user-42")));
+/// assert_ne!(key_hash("user-42"), key_hash("user-43"));
+/// ```
+pub fn key_hash<K: StableKey + ?Sized>(key: &K) -> u64 {
+	let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+	key.write_key(&mut |bytes| {
+		for &byte in bytes {
+			hash ^= u64::from(byte);
+			hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+		}
+	});
+
+	hash ^= hash >> 33;
+	hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+	hash ^= hash >> 33;
+	hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+	hash ^= hash >> 33;
+	hash
+}
+
+/// Maps the hash of the given key uniformly to an index in `0..count`.
+///
+/// The index is the upper 64 bits of the 128-bit product of the
+/// [`key_hash`] and `count`.
+///
+/// # Panics
+///
+/// Panics if `count` is zero.
+#[track_caller]
+pub fn index<K: StableKey + ?Sized>(key: &K, count: usize) -> usize {
+	assert!(count > 0, "You must provide at least one choice.");
+	((u128::from(key_hash(key)) * count as u128) >> 64) as usize
+}
+
+/// Maps the hash of the given key to an index, proportional to the given
+/// weights.
+///
+/// The hash is mapped to a position in `0..total`, as in [`index`], which is
+/// then mapped to the index whose cumulative weights cover it.
+pub fn weighted_index<K: StableKey + ?Sized>(key: &K, weights: &[u32]) -> Result<usize, AllWeightsZero> {
+	let total: u64 = weights.iter().map(|&w| u64::from(w)).sum();
+	if total == 0 {
+		return Err(AllWeightsZero);
+	}
+
+	let mut remaining = ((u128::from(key_hash(key)) * u128::from(total)) >> 64) as u64;
+	let mut index = 0;
+	while remaining >= u64::from(weights[index]) {
+		remaining -= u64::from(weights[index]);
+		index += 1;
+	}
+	Ok(index)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn golden_hashes() {
+		assert_eq!(key_hash(""), 0xefd0_1f60_ba99_2926);
+		assert_eq!(key_hash("a"), 0x82a2_a958_a9be_ce5b);
+		assert_eq!(key_hash("user-42"), 0xa395_32c7_ab05_1e8d);
+		assert_eq!(key_hash(&0_u64), 0x7bd3_144f_29c0_cc9e);
+		assert_eq!(key_hash(&42_u64), 0xa624_5a5d_cf27_8758);
+		assert_eq!(key_hash(&u64::MAX), 0x6a92_c022_8678_c02e);
+	}
+
+	#[test]
+	fn golden_indices() {
+		let indices: [usize; 8] = core::array::from_fn(|k| index(&(k as u64), 10));
+		assert_eq!(indices, [4, 2, 4, 1, 2, 0, 5, 7]);
+
+		let weighted: [usize; 8] = core::array::from_fn(|k| weighted_index(&(k as u64), &[1, 2, 7]).unwrap());
+		assert_eq!(weighted, [2, 1, 2, 1, 1, 0, 2, 2]);
+	}
+
+	#[test]
+	fn encodings_are_portable() {
+		assert_eq!(key_hash(&42_usize), key_hash(&42_u64));
+		assert_eq!(key_hash(&-1_isize), key_hash(&-1_i64));
+		assert_eq!(key_hash(&'a'), key_hash(&97_u32));
+		assert_eq!(key_hash(&true), key_hash(&1_u8));
+		assert_eq!(key_hash(b"abc"), key_hash("abc"));
+		assert_eq!(key_hash(&&"abc"), key_hash("abc"));
+	}
+
+	#[test]
+	fn population_is_balanced() {
+		let mut counts = [0_u32; 3];
+		for key in 0..30_000_u64 {
+			counts[index(&key, 3)] += 1;
+		}
+		assert!(counts.iter().all(|&c| 9_700 < c && c < 10_300), "{:?}", counts);
+
+		let mut counts = [0_u32; 3];
+		for key in 0..30_000_u64 {
+			counts[weighted_index(&key, &[1, 1, 4]).unwrap()] += 1;
+		}
+		assert!((4_700..5_300).contains(&counts[0]), "{:?}", counts);
+		assert!((4_700..5_300).contains(&counts[1]), "{:?}", counts);
+		assert!((19_400..20_600).contains(&counts[2]), "{:?}", counts);
+	}
+}
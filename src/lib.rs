@@ -48,15 +48,382 @@
 //!
 //! For more details see [`branch`](crate::branch) and
 //! [`branch_using`](crate::branch_using). The basic difference between them is,
-//! that `branch` uses [`rand::thread_rng()`](rand::thread_rng()) whereas
+//! that `branch` uses the [`ThreadBranchRng`](crate::ThreadBranchRng) whereas
 //! `branch_using` uses the the given [`rand::Rng`](rand::Rng).
 //!
+//! # Backends
+//!
+//! The macros draw from the RNGs of [`rand`] by default, but some of them can
+//! use other generators via cargo features. Backends with distinct macros can
+//! be enabled at the same time, while [`branch`](crate::branch) draws from
+//! the first enabled backend that provides it, in the order of this table:
+//!
+//! | Backend | Feature | Macros |
+//! |---|---|---|
+//! | Any [`rand::Rng`](rand::Rng) | | `branch_using` and all other `*_using` macros |
+//! | `ThreadBranchRng` | `std` (default) | `branch` and all other macros without `_using` |
+//! | `GlobalRng` | `global-rng` | `branch` |
+//! | `OsRng` | `getrandom` | `branch` |
+//! | `fastrand` | `fastrand` | `branch`, `branch_fastrand_using` |
+//! | `Math.random()` | `js-random`, only on wasm32 | `branch` |
+//! | `nanorand` | `nanorand` | `branch_nanorand_using`, and with `std` `branch_nanorand` |
+//!
+//! The `rand` crate itself is behind the `rand` feature, which is not a
+//! default feature itself, but is implied by the default `std` feature, by
+//! `alloc`, and by the other features that need it. Thus, with
+//! `default-features = false` and just the `fastrand` feature, `rand` isn't
+//! built at all, and only `branch`, `branch_fastrand_using` and the macros
+//! that need no RNG are available.
+//!
 
 
 // Reexport our version of rand so we can use it from our macros.
+#[cfg(feature = "rand")]
 #[doc(hidden)]
 pub use rand;
 
+// Reexport fastrand for users of the `fastrand` backend.
+#[cfg(feature = "fastrand")]
+#[doc(hidden)]
+pub use fastrand;
+
+// Reexport nanorand for the `branch_nanorand` macro.
+#[cfg(feature = "nanorand")]
+#[doc(hidden)]
+pub use nanorand;
+
+// Reexport proptest for the `branch_strategy` macro.
+#[cfg(feature = "proptest")]
+#[doc(hidden)]
+pub use proptest;
+
+// Reexport defmt for the log statements of our macros.
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+pub use defmt;
+
+// Reexport alloc and std so our macros can use them even if the user crate
+// doesn't.
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub extern crate alloc;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub extern crate std;
+
+#[cfg(feature = "quickcheck")]
+#[doc(hidden)]
+pub mod qc;
+
+#[cfg(feature = "bolero")]
+#[doc(hidden)]
+pub mod generator;
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod resilient;
+
+#[cfg(feature = "coverage")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "coverage")))]
+pub mod coverage;
+
+#[cfg(feature = "recorder")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "recorder")))]
+pub mod recorder;
+
+#[cfg(feature = "rand")]
+#[doc(hidden)]
+pub mod retry;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use retry::Pick;
+
+#[cfg(feature = "rand")]
+mod adaptive;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use adaptive::AdaptiveWeights;
+
+#[cfg(feature = "alloc")]
+mod alias;
+#[cfg(feature = "alloc")]
+pub use alias::AliasTable;
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub mod balanced;
+
+#[cfg(feature = "rand")]
+#[doc(hidden)]
+pub mod batch;
+
+#[cfg(feature = "rand")]
+mod bench;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use bench::BenchRng;
+
+#[cfg(feature = "rand")]
+#[doc(hidden)]
+pub mod bits;
+
+mod bytes;
+pub use bytes::ByteCursor;
+
+#[cfg(feature = "rand")]
+mod choose;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use choose::{choose_index, choose_weighted_index, try_choose_index};
+
+#[cfg(feature = "rand")]
+mod deck;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use deck::Deck;
+
+#[cfg(feature = "rand")]
+mod distribution;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use distribution::{UniformBranch, WeightedBranch};
+
+#[cfg(feature = "alloc")]
+mod dynamic;
+#[cfg(feature = "alloc")]
+pub use dynamic::{choose_and_run, try_choose_and_run, Branch};
+
+mod error;
+pub use error::BranchError;
+
+#[cfg(feature = "test-override")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "test-override")))]
+pub mod explore;
+
+#[cfg(feature = "rand")]
+mod fn_rng;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use fn_rng::{from_fn, FnRng};
+
+#[cfg(target_has_atomic = "64")]
+mod injection;
+#[cfg(target_has_atomic = "64")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(target_has_atomic = "64")))]
+pub use injection::{default_injection_rate, set_default_injection_rate};
+
+#[cfg(feature = "rand")]
+mod iter;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use iter::BranchIter;
+
+#[doc(hidden)]
+pub mod keyed;
+pub use keyed::{key_hash, StableKey};
+
+#[cfg(feature = "rand")]
+#[doc(hidden)]
+pub mod norepeat;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use norepeat::NoRepeat;
+
+#[cfg(feature = "pcg")]
+mod seeded;
+#[cfg(feature = "pcg")]
+pub use seeded::SeededRng;
+
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod secure;
+
+#[doc(hidden)]
+pub mod select;
+
+#[cfg(feature = "rand")]
+mod shared;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use shared::SharedRng;
+
+#[cfg(feature = "alloc")]
+mod site;
+#[cfg(feature = "alloc")]
+pub use site::{SiteRng, SiteStream};
+
+mod sink;
+pub use sink::PushSink;
+
+#[cfg(feature = "rand")]
+mod stratified;
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+pub use stratified::StratifiedPicker;
+
+#[cfg(feature = "text")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "text")))]
+pub mod text;
+
+#[cfg(feature = "test-override")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "test-override")))]
+pub mod test_override;
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+pub mod testing;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod test_seed;
+
+/// Turns a function into a test with a seeded RNG, printing the seed if it
+/// fails.
+///
+/// The attributed function becomes a `#[test]`, which starts with a
+/// [`SeededRng`] bound to `rng` (or to the identifier given as argument, as
+/// in `#[seeded_test(my_rng)]`). The seed is taken from the
+/// `RANDOM_BRANCH_SEED` environment variable if set, otherwise each test run
+/// draws a fresh one. If the test panics, the seed is printed to stderr along
+/// with the command to reproduce the failure:
+///
+/// ```text
+/// random-branch: test tests::flaky failed with seed 42, reproduce with RANDOM_BRANCH_SEED=42 cargo test -- --exact tests::flaky
+/// ```
+///
+/// Other attributes are kept, so it composes with `#[should_panic]`,
+/// `#[ignore]`, etc. If the function already has a test attribute, such as
+/// `#[tokio::test]` for an `async fn`, no additional `#[test]` is added, as
+/// long as `#[seeded_test]` is written first. The test may also return a
+/// `Result`.
+///
+/// Notice that the generated code refers to this crate as `random_branch`,
+/// so it must not be renamed.
+///
+/// # Example
+///
+/// ```rust
+/// use random_branch::branch_using;
+/// use random_branch::seeded_test;
+///
+/// #[seeded_test]
+/// fn branches_are_positive() {
+///     let x = branch_using!(rng, { 1, 2, 3 });
+///     assert!(x > 0);
+/// }
+///
+/// #[seeded_test(my_rng)]
+/// #[should_panic]
+/// fn always_panics() {
+///     branch_using!(my_rng, { panic!(), panic!("Boom") });
+/// }
+/// ```
+#[cfg(feature = "macros")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "macros")))]
+pub use random_branch_macros::seeded_test;
+
+/// Expands a test function into `n` independently seeded tests.
+///
+/// Instead of a single test looping over many random runs, the attributed
+/// function is called by `n` separate tests, each with its own fixed seed.
+/// The function must take an RNG, e.g. as `rng: &mut impl Rng`, and the
+/// tests are named after it with a `_case_` suffix and the zero-padded index,
+/// i.e. `my_test` becomes `my_test_case_00` through `my_test_case_15` for
+/// `#[cases(16)]`. Thus, a failure pinpoints its case, which can be rerun
+/// alone with e.g. `cargo test my_test_case_07`.
+///
+/// Each case gets a [`SeededRng`], seeded with the stable hash (see
+/// [`key_hash`]) of the path of the function and the index of the case. Thus,
+/// the seeds of the cases differ, but they are the same in every run, and
+/// only change if the function is renamed or moved to another module.
+///
+/// Other attributes, such as `#[should_panic]` or `#[ignore]`, are added to
+/// every case. If the function returns a value, e.g. a `Result`, it is
+/// returned from the tests. Async functions aren't supported.
+///
+/// Notice that the generated code refers to this crate as `random_branch`,
+/// so it must not be renamed.
+///
+/// # Example
+///
+/// ```rust
+/// use rand::Rng;
+/// use random_branch::branch_using;
+/// use random_branch::cases;
+///
+/// #[cases(16)]
+/// fn branches_are_positive(rng: &mut impl Rng) {
+///     let x = branch_using!(*rng, { 1, 2, 3 });
+///     assert!(x > 0);
+/// }
+/// ```
+#[cfg(feature = "macros")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "macros")))]
+pub use random_branch_macros::cases;
+
+#[cfg(any(feature = "tracing", feature = "log"))]
+mod trace;
+
+#[cfg(feature = "arbitrary")]
+#[doc(hidden)]
+pub mod unstructured;
+
+#[doc(hidden)]
+pub mod until;
+
+#[cfg(all(feature = "verification", kani))]
+mod verification;
+
+#[doc(hidden)]
+pub mod weighted;
+pub use weighted::AllWeightsZero;
+
+#[cfg(feature = "std")]
+mod thread;
+#[cfg(feature = "std")]
+pub use thread::{reset_thread_branch_rng, set_thread_branch_rng, ThreadBranchRng};
+#[cfg(feature = "env-seed")]
+pub use thread::env_seed;
+#[cfg(feature = "repro")]
+pub use thread::repro_rng;
+
+#[cfg(feature = "global-rng")]
+mod global;
+#[cfg(feature = "global-rng")]
+pub use global::{set_global_rng, GlobalRng, SetGlobalRngError};
+
+// The RNG of the `branch` macro, the global RNG is only used without std, and
+// the OS RNG only without either.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use thread::ThreadBranchRng as DefaultBranchRng;
+#[cfg(all(feature = "global-rng", not(feature = "std")))]
+#[doc(hidden)]
+pub use global::GlobalRng as DefaultBranchRng;
+#[cfg(all(feature = "getrandom", not(any(feature = "std", feature = "global-rng"))))]
+#[doc(hidden)]
+pub use rand::rngs::OsRng as DefaultBranchRng;
+#[cfg(all(
+	feature = "js-random",
+	target_arch = "wasm32",
+	not(any(feature = "std", feature = "global-rng", feature = "getrandom", feature = "fastrand"))
+))]
+#[doc(hidden)]
+pub use js_random::JsRandomRng as DefaultBranchRng;
+
+#[cfg(feature = "fastrand")]
+mod fastrand_rng;
+#[cfg(all(feature = "fastrand", feature = "rand"))]
+pub use fastrand_rng::FastrandRng;
+#[cfg(feature = "fastrand")]
+#[doc(hidden)]
+pub use fastrand_rng::FastrandGlobal;
+
+#[cfg(all(feature = "js-random", target_arch = "wasm32"))]
+mod js_random;
+#[cfg(all(feature = "js-random", target_arch = "wasm32"))]
+pub use js_random::JsRandomRng;
+
 
 /// Branches into one of the given expressions using the given RNG.
 ///
@@ -94,6 +461,17 @@ pub use rand;
 /// }
 /// ```
 ///
+/// # Limits
+///
+/// A single invocation can have at most `isize::MAX / size_of::<usize>()`
+/// branches, which are 16383 branches on targets with a 16-bit `usize`,
+/// e.g. AVR or MSP430, and practically unlimited on others. The number of
+/// branches must fit in a `usize` to be drawn via `gen_range`, and some
+/// macros and features keep a table with an entry per branch, which must fit
+/// into memory. This limit applies to all macros of this crate that select
+/// among a list of branches via an RNG, and exceeding it is a compile-time
+/// error, e.g. for a large generated list of branches on a microcontroller.
+///
 /// # Examples
 ///
 /// You can use functions, macros and other arbitrary expressions:
@@ -135,25 +513,325 @@ pub use rand;
 /// });
 /// assert!(num == 10 || num == 21 || num == 42 || num == 85);
 /// ```
+///
+/// The branches are inlined as the arms of a `match`, they are not wrapped in
+/// any closure. Thus, control flow expressions such as `return`, `?`, `break`
+/// and `continue` within a branch act on the surrounding function or loop,
+/// just like they would in a hand-written `match`:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+///
+/// fn pick(mut my_rng: Lcg64Xsh32) -> u32 {
+///     let num = branch_using!( my_rng, {
+///         return 7,
+///         1,
+///         2,
+///     });
+///     num * 10
+/// }
+///
+/// // These seeds select the first, second and third branch respectively
+/// # #[cfg(not(feature = "deterministic"))] {
+/// assert_eq!(pick(Lcg64Xsh32::new(0, 0)), 7);
+/// assert_eq!(pick(Lcg64Xsh32::new(3, 0)), 10);
+/// assert_eq!(pick(Lcg64Xsh32::new(2, 0)), 20);
+/// # }
+///
+/// let mut my_rng = Lcg64Xsh32::new(0, 0);
+/// let mut passes = 0;
+/// loop {
+///     passes += 1;
+///     branch_using!( my_rng, {
+///         break,
+///         continue,
+///     });
+/// }
+/// assert!(passes >= 1);
+/// ```
+///
+/// This macro, like all others of this crate, can be invoked from your own
+/// `macro_rules!` macros, forwarding the branches e.g. as
+/// `$( $arm:expr ),*`. Each forwarded expression stays a single branch, even
+/// if it contains commas or braces:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+///
+/// macro_rules! my_pick {
+///     ( $rng:expr, $( $arm:expr ),* $(,)? ) => {
+///         branch_using!($rng, { $( $arm ),* })
+///     };
+/// }
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let (a, b) = my_pick!(my_rng, (1, 2), { let x = 3; (x, 4) }, (5, 6));
+/// assert!(a + 1 == b);
+/// ```
+///
+/// Since each branch is parsed as a whole expression, complex expressions,
+/// such as `if`, `match`, and closures, need no extra braces, even if they
+/// contain commas themselves:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let flag = true;
+/// let num = Some(3);
+///
+/// let value = branch_using!( my_rng, {
+///     if flag { 1 } else { 2 },
+///     match num { Some(x) => x, None => 0 },
+///     if let Some(x) = num { x * 2 } else { 0 },
+/// });
+/// assert!(value == 1 || value == 3 || value == 6);
+///
+/// let pair: fn(u8) -> (u8, u8) = branch_using!( my_rng, {
+///     |x| (x, x),
+///     |x| (x, x + 1),
+/// });
+/// let (a, b) = pair(1);
+/// assert!(a == 1 && (b == 1 || b == 2));
+/// ```
+///
+/// For side-effecting code, the branches may also be separated by semicolons
+/// instead of commas, so they read like the statements of a block, where
+/// each statement is a branch on its own:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut log = Vec::new();
+///
+/// branch_using!( my_rng, {
+///     log.push("step one");
+///     log.push("step two");
+///     log.clear();
+/// });
+/// # assert!(log.len() <= 1);
+/// ```
+///
+/// Each branch still has to be a single expression, e.g. a `let` statement
+/// is not a branch, but it can be wrapped in a block. Both separators can't
+/// be mixed within one invocation. Prefer the comma form, which is the
+/// canonical one, in particular if the branches yield a value, and use the
+/// semicolon form only for branches that are executed just for their side
+/// effects, where a trailing comma after a call would look odd.
+///
+/// Any RNG implementing [`Rng`](rand::Rng) can be used, which includes every
+/// [`RngCore`](rand::RngCore), e.g. a cryptographically secure generator
+/// such as `rand_chacha::ChaCha20Rng`, as well as `dyn RngCore` trait
+/// objects. The RNG is borrowed mutably, so if you only have a `&mut`
+/// reference to it, e.g. as function argument, pass it dereferenced:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use rand::RngCore;
+/// use random_branch::branch_using;
+///
+/// fn pick(rng: &mut dyn RngCore) -> char {
+///     branch_using!(*rng, { 'a', 'b' })
+/// }
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let c = pick(&mut my_rng);
+/// # assert!(c == 'a' || c == 'b');
+/// ```
+///
+/// The RNG is only borrowed to select the branch, and that borrow ends before
+/// the selected branch is evaluated. So, an RNG stored in a field can be used
+/// as `self.rng`, while the branches still use `self` freely, even mutably:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+///
+/// struct Walker {
+///     rng: Lcg64Xsh32,
+///     x: i32,
+///     steps: u32,
+/// }
+///
+/// impl Walker {
+///     fn step(&mut self) {
+///         branch_using!(self.rng, {
+///             self.x -= 1,
+///             self.x += 1,
+///             self.rest(),
+///         });
+///         self.steps += 1;
+///     }
+///
+///     fn rest(&mut self) {}
+/// }
+///
+/// let mut walker = Walker { rng: /* snip */
+/// # Lcg64Xsh32::new(0,0),
+///     x: 0, steps: 0 };
+/// walker.step();
+/// # assert_eq!(walker.steps, 1);
+/// ```
+///
+/// Branches may be configured out with `#[cfg(..)]` attributes, if the list
+/// of branches ends with a `default:` branch, which is always present, but only
+/// taken if all other branches are configured out. So, if any other branch
+/// remains, the default branch is never taken, and the remaining branches are
+/// selected uniformly as usual. This prevents the compile error of having no
+/// branches at all in heavily configured code:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let backend = branch_using!( my_rng, {
+///     #[cfg(feature = "fast-backend")] "fast",
+///     #[cfg(feature = "small-backend")] "small",
+///     default: "portable",
+/// });
+/// # assert_eq!(backend, "portable");
+/// ```
+///
+/// With the `verification` feature, when verifying with the Kani model
+/// checker, this macro ignores the given RNG (it is still evaluated) and
+/// selects a nondeterministic branch instead, so that Kani explores all of
+/// them. In normal builds, the feature has no effect.
+///
+/// With the `deterministic` feature, this macro always takes the first branch
+/// and the RNG expression is not evaluated at all, so it may not even have
+/// side effects. However, the feature only replaces the random draw, thus
+/// branches forced or explored with the `test-override` feature, or replayed
+/// with the `recorder` feature, still take precedence. The other macros take
+/// their first branches as well: weighted ones take the first branch with a
+/// non-zero weight, and the ones picking several branches, such as
+/// [`branch_retry_using`] or [`branch_fallback_using`], take them in the given
+/// order. Yet, most of them still evaluate their RNG expression, e.g.
+/// [`branch_array_using`], `branch_vec_using`, [`branch_fill_using`] and
+/// [`branch_iter_using`] evaluate it once without drawing from it. Macros that
+/// keep their own state or distribution, such as [`branch_norepeat_using`],
+/// and the ones evaluating an expression with some probability, such as
+/// [`maybe_using`], [`perhaps_using`] and `with_probability_default_using`,
+/// even draw from the RNG as usual. Finally, [`branch_secure`] always draws
+/// from the OS RNG, since any dependency may enable this feature.
+///
+/// With the `tracing` feature, every macro emits a `trace` level event with
+/// the target `random_branch` right before evaluating the selected branch,
+/// with the fields `file`, `line`, `column`, `index`, `count`, and `label`.
+/// Only this macro, `branch_nanorand_using` and `branch_fastrand_using`
+/// take a label, given as string literal before the branches, for all other
+/// macros the label is empty:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let greeting = branch_using!(my_rng, "greeting", { "Hi", "Hello" });
+/// # assert!(greeting == "Hi" || greeting == "Hello");
+/// ```
+///
+/// Likewise, with the `log` feature, every macro logs a `trace` level record
+/// with the target `random_branch` via the [`log`](https://docs.rs/log)
+/// facade, with a message such as `src/main.rs:4:13: selected branch 1 of 3
+/// (greeting)`, where the label is omitted if there is none.
+///
+/// And with the `defmt` feature, intended for embedded targets, every macro
+/// emits a `defmt::trace!` statement at its call site, with a message such as
+/// `selected branch 1 of 3 (greeting)`, but without `core::fmt`. The message
+/// and label are interned, the file and line are given by the location
+/// information of `defmt`, and the index and count use the smallest integer
+/// type that fits the count. The user crate need not depend on `defmt` itself,
+/// but it must provide a global logger, as for any `defmt` statement.
+///
+/// These features may be enabled together, then each selection is logged to
+/// all of them. Without them, the label is ignored and nothing is logged.
 #[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
 macro_rules! branch_using {
+	// Branches with attributes must not reach the plain rules, which would
+	// accept them as expressions with attributes
+	( $rng:expr, { # $( $arms:tt )* }) => {
+		{
+			$crate::branch_internal!(@cfg_scan [uniform] $rng, [] [] # $( $arms )*)
+		}
+	};
 	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
 		{
 			$crate::branch_internal!(
-				$rng,
-				{ $( { $branch } )* },
+				[uniform] $rng,
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $rng:expr, { $( $branch:expr );+ $(;)? }) => {
+		{
+			$crate::branch_internal!(
+				[uniform] $rng,
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $rng:expr, $label:literal, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[uniform] $rng,
+				{ $( $branch )* },
+				$label
+			)
+		}
+	};
+	( $rng:expr, $label:literal, { $( $branch:expr );+ $(;)? }) => {
+		{
+			$crate::branch_internal!(
+				[uniform] $rng,
+				{ $( $branch )* },
+				$label
 			)
 		}
 	};
+	( $rng:expr, { $( $arms:tt )* }) => {
+		{
+			$crate::branch_internal!(@cfg_scan [uniform] $rng, [] [] $( $arms )*)
+		}
+	};
 }
 
 
 /// Branches into one of the given expressions.
 ///
 /// This macro dose essentially the same as [`branch_using`] instead of giving
-/// it some RNG, this macro will simply use the [`rand::thread_rng()`].
+/// it some RNG, this macro will simply use the [`ThreadBranchRng`].
 /// However, this then requires `std`, unlike `branch_using`.
 ///
+/// The RNG can be overridden per thread via [`set_thread_branch_rng`], e.g.
+/// to make tests reproducible.
+///
+/// With the `small-rng` feature, this macro instead draws from a thread-local
+/// [`SmallRng`](https://docs.rs/rand/0.8/rand/rngs/struct.SmallRng.html),
+/// which each thread seeds once from the operating system. It is considerably
+/// faster, e.g. for tight loops making millions of cosmetic choices, but it
+/// is neither cryptographically secure, nor of the statistical quality of
+/// the thread RNG, and its algorithm may differ between platforms and
+/// versions of `rand`. So, only enable it if the branches don't need those
+/// properties. The `env-seed` and `repro` features take precedence over it.
+///
+/// The RNG is only used to select the branch, and released before the
+/// selected branch is evaluated. So, a branch may `.await` within an
+/// `async fn` without making its future `!Send`, as `ThreadRng` would.
+///
 /// This macro turns something like this:
 ///
 /// ```rust
@@ -168,6 +846,7 @@ macro_rules! branch_using {
 /// into something similar to this using the `thread_rng()`:
 ///
 /// ```rust
+/// # #[cfg(feature = "std")] {
 /// # use rand::Rng;
 /// match rand::thread_rng().gen_range(0..3) {
 ///     0 => println!("First line."),
@@ -175,8 +854,54 @@ macro_rules! branch_using {
 ///     2 => println!("Third line!"),
 ///     _ => unreachable!(),
 /// }
+/// # }
 /// ```
 ///
+/// Without `std`, this macro is available with the `global-rng` feature,
+/// which requires an allocator. It then draws from the global RNG, which must
+/// be set once via
+#[cfg_attr(feature = "global-rng", doc = "[`set_global_rng`](crate::set_global_rng),")]
+#[cfg_attr(not(feature = "global-rng"), doc = "`set_global_rng`,")]
+/// before this macro is invoked the first time, otherwise it panics. See
+#[cfg_attr(feature = "global-rng", doc = "[`GlobalRng`](crate::GlobalRng)")]
+#[cfg_attr(not(feature = "global-rng"), doc = "`GlobalRng`")]
+/// for its locking. With `std`, the thread RNG is used regardless of this
+/// feature.
+///
+/// Without `std` and `global-rng`, this macro is also available with the
+/// `getrandom` feature. It then doesn't keep any RNG, but fetches the few
+/// random bytes needed for each selection directly from the operating system
+/// via [`getrandom`](https://docs.rs/getrandom), i.e. [`OsRng`](rand::rngs::OsRng),
+/// into a buffer on the stack. Thus, it works on every target supported by
+/// `getrandom`, e.g. `wasm32-wasi`, but each invocation is a system call. It
+/// panics with the error of `getrandom` if that fails.
+///
+/// Without all of the above, this macro is available with the
+/// `fastrand` feature, drawing from the thread-local generator of
+/// [`fastrand`](https://docs.rs/fastrand). The crate is re-exported, so it
+/// doesn't need to be a dependency of your crate. So, to just use `fastrand`
+/// instead of `rand`, disable the default features. Then, `rand` isn't built
+/// at all, and only this macro, `branch_fastrand_using` and the macros that
+/// need no RNG are available. See `branch_fastrand_using` to use a
+/// `fastrand::Rng` instance instead.
+///
+/// Finally, on wasm32 targets, this macro is available with the `js-random`
+/// feature, drawing from JavaScript's `Math.random()` via `js-sys`, see
+#[cfg_attr(all(feature = "js-random", target_arch = "wasm32"), doc = "[`JsRandomRng`](crate::JsRandomRng).")]
+#[cfg_attr(not(all(feature = "js-random", target_arch = "wasm32")), doc = "`JsRandomRng`.")]
+/// It needs no source of entropy in Rust, which `wasm32-unknown-unknown`
+/// lacks, but isn't cryptographically secure. Alternatively, enable the
+/// `wasm-js` feature, which makes `getrandom` draw from the JavaScript crypto
+/// API on that target, and thus makes all the other RNGs work there,
+/// including the default thread RNG. Without either of them, invoking this
+/// macro on `wasm32-unknown-unknown` fails to compile with a hint to these
+/// features.
+///
+/// In summary, this macro uses the first of the following RNGs that is
+/// enabled: the thread RNG with `std`, the global RNG with `global-rng`, the
+/// OS RNG with `getrandom`, the generator of `fastrand` with `fastrand`, or
+/// `Math.random()` with `js-random` on wasm32.
+///
 ///
 /// # Examples
 ///
@@ -215,69 +940,6218 @@ macro_rules! branch_using {
 /// println!("The best number is {}", num);
 /// # assert!(num == 10 || num == 21 || num == 42 || num == 85);
 /// ```
-#[macro_export]
-#[cfg(feature = "std")]
-#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
-macro_rules! branch {
-	( $( $branch:expr ),* $(,)? ) => {
-		{
-			$crate::branch_internal!(
-				$crate::rand::thread_rng(),
-				{ $( { $branch } )* },
-			)
-		}
-	};
-}
-
-
-/// Internal branching macro
 ///
-/// Each branch must be enclosed in braces e.g. `{ }` so it is a single `tt`.
+/// Branches may be arbitrary expressions, including `if` and `match`
+/// expressions and closures, without extra braces:
+///
+/// ```rust
+/// use random_branch::branch;
+///
+/// let cond = false;
+/// let value = branch!(
+///     if cond { 'a' } else { 'b' },
+///     match cond { true => 'c', false => 'd' },
+///     'e',
+/// );
+/// assert!("bde".contains(value));
+///
+/// let f: fn(i32) -> (i32, i32) = branch!(|x| (x, x), |x| (x, -x));
+/// assert_eq!(f(0), (0, 0));
+/// ```
+///
+/// Parentheses group as usual, so a tuple is a single branch, and only the
+/// commas outside of any parentheses, brackets or braces separate branches:
+///
+/// ```rust
+/// use random_branch::branch;
+///
+/// let pair: (i32, i32) = branch!((1, 2), (3, 4));
+/// assert!(pair == (1, 2) || pair == (3, 4));
+///
+/// let nested = branch!(((1, 2), [3, 4]), ((5, 6), [7, 8]));
+/// assert!(nested.0 .0 + 2 == nested.1[0]);
+///
+/// let single: (char,) = branch!(('a',), ('b',));
+/// assert!(single == ('a',) || single == ('b',));
+/// ```
+///
+/// For side-effecting code, the branches may also be separated by semicolons,
+/// see [`branch_using`] for when to prefer which form:
+///
+/// ```rust
+/// use random_branch::branch;
+///
+/// branch! {
+///     println!("First line.");
+///     println!("Second line?");
+///     println!("Third line!");
+/// }
+/// ```
+///
+/// Branches may also diverge, e.g. by panicking or exiting, since `!`
+/// coerces to any type, so the whole expression has the type of the other
+/// branches:
+///
+/// ```rust
+/// use random_branch::branch;
+///
+/// fn compute() -> u32 {
+///     42
+/// }
+///
+/// fn pick() -> u32 {
+///     branch!(compute(), panic!("nope"), std::process::exit(1))
+/// }
+///
+/// // Even if the diverging branches come first
+/// fn pick_last() -> u32 {
+///     branch!(panic!("nope"), std::process::exit(1), compute())
+/// }
+/// # let _: [fn() -> u32; 2] = [pick, pick_last];
+///
+/// # #[cfg(feature = "rand")] {
+/// # use random_branch::branch_using;
+/// let mut my_rng = /* snip */
+/// # random_branch::from_fn(|| 0);
+/// let n: u32 = branch_using!(my_rng, { compute(), panic!("nope") });
+/// # assert_eq!(n, 42);
+/// # }
+/// ```
+///
+/// Branches with `#[cfg(..)]` attributes need a final `default:` branch, see
+/// [`branch_using`]:
+///
+/// ```rust
+/// use random_branch::branch;
+///
+/// let greeting = branch!(
+///     #[cfg(feature = "formal")] "Good day",
+///     #[cfg(feature = "casual")] "Hey",
+///     default: "Hello",
+/// );
+/// # assert_eq!(greeting, "Hello");
+/// ```
+#[macro_export]
+#[cfg(any(
+	feature = "std",
+	feature = "global-rng",
+	feature = "getrandom",
+	feature = "fastrand",
+	all(feature = "js-random", target_arch = "wasm32"),
+))]
+#[cfg_attr(
+	feature = "doc_cfg",
+	doc(cfg(any(feature = "std", feature = "global-rng", feature = "getrandom", feature = "fastrand", feature = "js-random")))
+)]
+macro_rules! branch {
+	// Branches with attributes must not reach the plain rules, which would
+	// accept them as expressions with attributes
+	( # $( $arms:tt )* ) => {
+		{
+			$crate::branch_internal!(@cfg_scan [backend] (), [] [] # $( $arms )*)
+		}
+	};
+	( $( $branch:expr ),* $(,)? ) => {
+		{
+			$crate::branch_internal!(
+				[backend] (),
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $( $branch:expr );+ $(;)? ) => {
+		{
+			$crate::branch_internal!(
+				[backend] (),
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $( $arms:tt )* ) => {
+		{
+			$crate::branch_internal!(@cfg_scan [backend] (), [] [] $( $arms )*)
+		}
+	};
+}
+
+// Explains how to get a source of randomness on wasm32, where `branch` would
+// otherwise just be missing
+#[doc(hidden)]
+#[macro_export]
+#[cfg(all(
+	target_arch = "wasm32",
+	not(any(
+		feature = "std",
+		feature = "global-rng",
+		feature = "getrandom",
+		feature = "fastrand",
+		feature = "js-random",
+	)),
+))]
+macro_rules! branch {
+	( $( $arms:tt )* ) => {
+		::core::compile_error!(
+			"`branch!` needs a source of randomness on wasm32: enable the `js-random` feature of \
+			`random-branch` to use `Math.random()`, or its `wasm-js` feature together with `std` or \
+			`getrandom` to use the JavaScript crypto API, or use `branch_using!` with your own RNG"
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, each of
+/// which may take ownership of the given value.
+///
+/// This macro dose essentially the same as [`branch_using`], but it first
+/// moves the given variable, or the value of `name = expression`, into a
+/// binding of that name, which the branches can then move. This is sound,
+/// since only one branch is evaluated, and it compiles, since the branches
+/// become the arms of a single `match`, for which the borrow checker knows
+/// that they are mutually exclusive. Actually, this holds for all macros of
+/// this crate, but this one makes the intent explicit, and it drops the
+/// value at its end if the selected branch doesn't consume it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_owned_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// fn archive(report: String) -> usize { report.len() }
+/// fn publish(report: String) -> usize { report.len() * 2 }
+///
+/// let report = String::from("quarterly numbers");
+/// let size = branch_owned_using!(my_rng, report, {
+///     archive(report),
+///     publish(report),
+///     0,
+/// });
+/// # assert!(size == 0 || size == 17 || size == 34);
+///
+/// // The value can also be given as an expression
+/// let size = branch_owned_using!(my_rng, report = String::from("annual numbers"), {
+///     archive(report),
+///     publish(report),
+/// });
+/// # assert!(size == 14 || size == 28);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_owned_using {
+	( $rng:expr, $name:ident = $value:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let $name = $value;
+			$crate::branch_using!($rng, { $( $branch ),* })
+		}
+	};
+	( $rng:expr, $name:ident, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_owned_using!($rng, $name = $name, { $( $branch ),* })
+	};
+}
+
+
+/// Branches into one of the given expressions, each of which may take
+/// ownership of the given value.
+///
+/// This macro is to [`branch_owned_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_owned;
+/// use std::sync::mpsc::channel;
+///
+/// let (first, first_rx) = channel();
+/// let (second, second_rx) = channel();
+///
+/// let job = vec![1, 2, 3];
+/// branch_owned!(job, {
+///     first.send(job).unwrap(),
+///     second.send(job).unwrap(),
+/// });
+/// # assert_eq!(first_rx.try_iter().count() + second_rx.try_iter().count(), 1);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_owned {
+	( $name:ident = $value:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_owned_using!($crate::ThreadBranchRng, $name = $value, { $( $branch ),* })
+	};
+	( $name:ident, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_owned_using!($crate::ThreadBranchRng, $name = $name, { $( $branch ),* })
+	};
+}
+
+
+/// Branches into one of the given expressions using the given `fastrand`
+/// generator.
+///
+/// This macro dose essentially the same as `branch_using`, but instead of
+/// an RNG of `rand`, it takes a `fastrand::Rng`, and draws the index of the
+/// branch via `fastrand::Rng::usize`. Thus, it doesn't need the `rand`
+/// feature. Like `branch_using`, it optionally takes a label for the
+/// `tracing` and `log` features. See [`branch`] for the `fastrand` backend
+/// without an explicit generator.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_fastrand_using;
+///
+/// let mut rng = fastrand::Rng::with_seed(7);
+/// let greeting = branch_fastrand_using!(rng, {
+///     "Hello",
+///     "Hi",
+/// });
+/// assert!(greeting == "Hello" || greeting == "Hi");
+/// ```
+#[macro_export]
+#[cfg(feature = "fastrand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "fastrand")))]
+macro_rules! branch_fastrand_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[fastrand] $rng,
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $rng:expr, $label:literal, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[fastrand] $rng,
+				{ $( $branch )* },
+				$label
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the given `nanorand`
+/// RNG.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// an RNG of [`rand`], it takes any generator implementing
+/// [`nanorand::Rng`](https://docs.rs/nanorand/0.8/nanorand/trait.Rng.html),
+/// such as `WyRand` or `ChaCha20`, and selects the branch via its
+/// `generate_range` method. It is only available with the `nanorand`
+/// feature, which doesn't interfere with the other backends, see the
+/// [crate-level documentation](crate#backends). Like [`branch_using`], it
+/// accepts an optional label.
+///
+/// # Examples
+///
+/// ```rust
+/// use nanorand::WyRand;
+/// use random_branch::branch_nanorand_using;
+///
+/// let mut rng = WyRand::new_seed(42);
+/// let greeting = branch_nanorand_using!(rng, {
+///     "Hello",
+///     "Hi",
+///     "Hey",
+/// });
+/// # assert!(["Hello", "Hi", "Hey"].contains(&greeting));
+/// ```
+#[macro_export]
+#[cfg(feature = "nanorand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "nanorand")))]
+macro_rules! branch_nanorand_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[nanorand] $rng,
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $rng:expr, $label:literal, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[nanorand] $rng,
+				{ $( $branch )* },
+				$label
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the thread-local
+/// `WyRand` of `nanorand`.
+///
+/// This macro is to [`branch_nanorand_using`] what [`branch`] is to
+/// [`branch_using`], it uses the lazily initialized, thread-local generator
+/// of `nanorand`, i.e. `nanorand::tls_rng()`, and thus requires `std`. It
+/// can be used alongside [`branch`], which still draws from the RNGs of
+/// [`rand`].
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_nanorand;
+///
+/// let coin = branch_nanorand!("heads", "tails");
+/// # assert!(coin == "heads" || coin == "tails");
+/// ```
+#[macro_export]
+#[cfg(all(feature = "std", feature = "nanorand"))]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(all(feature = "std", feature = "nanorand"))))]
+macro_rules! branch_nanorand {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_nanorand_using!($crate::nanorand::tls_rng(), { $( $branch ),* })
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, printing
+/// the chosen branch to stderr.
+///
+/// This macro dose essentially the same as [`branch_using`], but like
+/// [`std::dbg!`], before evaluating the chosen branch, it prints the location
+/// of the call site, the index of the branch, the number of branches, and the
+/// source text of the branch, e.g. `[src/main.rs:4:13] branch 1 of 3: "b"`.
+/// The value of the branch is returned unchanged. Both the comma and the
+/// semicolon form of [`branch_using`] are supported.
+///
+/// Like [`debug_assert!`], the printing is only compiled in with
+/// `debug_assertions`, so in release builds this macro is just
+/// [`branch_using`]. To print in release builds too, start the invocation
+/// with `@always`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_dbg_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// // Prints e.g. `[src/main.rs:7:14] branch 0 of 2: 'a'`
+/// let c = branch_dbg_using!(my_rng, { 'a', 'b' });
+/// # assert!(c == 'a' || c == 'b');
+///
+/// // Prints even in release builds
+/// let c = branch_dbg_using!(@always my_rng, { 'a', 'b' });
+/// # assert!(c == 'a' || c == 'b');
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_dbg_using {
+	// Prints the chosen branch
+	(@print $selected:ident, $count:ident, $branch:expr) => {
+		$crate::std::eprintln!(
+			"[{}:{}:{}] branch {} of {}: {}",
+			::core::file!(),
+			::core::line!(),
+			::core::column!(),
+			$selected,
+			$count,
+			::core::stringify!($branch),
+		)
+	};
+	// Prints the chosen branch only with `debug_assertions`, then evaluates it
+	(@arm [debug] $selected:ident, $count:ident, $branch:expr) => {
+		{
+			#[cfg(debug_assertions)]
+			$crate::branch_dbg_using!(@print $selected, $count, $branch);
+			$branch
+		}
+	};
+	// Prints the chosen branch unconditionally, then evaluates it
+	(@arm [always] $selected:ident, $count:ident, $branch:expr) => {
+		{
+			$crate::branch_dbg_using!(@print $selected, $count, $branch);
+			$branch
+		}
+	};
+	// Selects a branch, wrapping each one in an `@arm`
+	(@expand [$mode:ident] $rng:expr, { $( $branch:expr ),* }) => {
+		{
+			const COUNT: usize = $crate::branch_internal!(@count { $( $branch )* });
+			let selected: usize = $crate::branch_internal!(@select [uniform] $rng, COUNT);
+			$crate::branch_internal!(
+				[index] selected,
+				{ $( { $crate::branch_dbg_using!(@arm [$mode] selected, COUNT, $branch) } )* },
+			)
+		}
+	};
+	(@always $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_dbg_using!(@expand [always] $rng, { $( $branch ),* })
+	};
+	(@always $rng:expr, { $( $branch:expr );+ $(;)? }) => {
+		$crate::branch_dbg_using!(@expand [always] $rng, { $( $branch ),* })
+	};
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_dbg_using!(@expand [debug] $rng, { $( $branch ),* })
+	};
+	( $rng:expr, { $( $branch:expr );+ $(;)? }) => {
+		$crate::branch_dbg_using!(@expand [debug] $rng, { $( $branch ),* })
+	};
+}
+
+
+/// Branches into one of the given expressions, printing the chosen branch to
+/// stderr.
+///
+/// This macro is to [`branch_dbg_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_dbg;
+///
+/// // Prints e.g. `[src/main.rs:4:13] branch 2 of 3: 3 * 3`
+/// let n = branch_dbg!(1, 2 * 2, 3 * 3);
+/// # assert!(n == 1 || n == 4 || n == 9);
+///
+/// // Prints even in release builds
+/// branch_dbg! {
+///     @always
+///     println!("First line.");
+///     println!("Second line?");
+/// }
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_dbg {
+	(@always $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_dbg_using!(@always $crate::ThreadBranchRng, { $( $branch ),* })
+	};
+	(@always $( $branch:expr );+ $(;)? ) => {
+		$crate::branch_dbg_using!(@always $crate::ThreadBranchRng, { $( $branch );* })
+	};
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_dbg_using!($crate::ThreadBranchRng, { $( $branch ),* })
+	};
+	( $( $branch:expr );+ $(;)? ) => {
+		$crate::branch_dbg_using!($crate::ThreadBranchRng, { $( $branch );* })
+	};
+}
+
+
+/// Branches into one of the given expressions using the RNG stream of this
+/// call site, derived from the given master seed.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// an RNG, it takes a [`SiteRng`] and an identifier of the call site, and
+/// uses the stream of that identifier, see [`SiteRng::stream`]. So, the
+/// decisions of a call site are not affected by inserting or removing other
+/// call sites, as long as they use different identifiers. The identifier may
+/// be any [`StableKey`], e.g. a string label. If the master is only available
+/// as `&mut SiteRng`, pass it dereferenced, as `*master`.
+///
+/// Without an identifier, the location of the macro invocation is used as
+/// identifier, i.e. its file, line, and column. Notice that this location
+/// changes when code is inserted above the invocation in the same file, so
+/// prefer explicit labels for decisions that are recorded as expectations.
+///
+/// This macro requires the `alloc` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_site;
+/// use random_branch::SiteRng;
+///
+/// fn level(master: &mut SiteRng) -> (&'static str, u32) {
+///     let terrain = branch_site!(*master, "terrain", { "forest", "desert", "swamp" });
+///     let enemies = branch_site!(*master, "enemies", { 1, 2, 3 });
+///     (terrain, enemies)
+/// }
+///
+/// let mut master = SiteRng::new(42);
+/// let first = level(&mut master);
+///
+/// // Decisions at other call sites don't shift the ones above
+/// let mut master = SiteRng::new(42);
+/// let _ = branch_site!(master, "weather", { "sunny", "rainy" });
+/// assert_eq!(level(&mut master), first);
+/// ```
+#[macro_export]
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "alloc")))]
+macro_rules! branch_site {
+	( $master:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_internal!(
+			[uniform] $crate::SiteRng::stream(
+				&mut $master,
+				::core::concat!(::core::file!(), ":", ::core::line!(), ":", ::core::column!()),
+			),
+			{ $( $branch )* },
+		)
+	};
+	( $master:expr, $site:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_internal!(
+			[uniform] $crate::SiteRng::stream(&mut $master, &$site),
+			{ $( $branch )* },
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, with the
+/// RNG last.
+///
+/// This macro is just an alternative syntax for [`branch_using`], which reads
+/// like "choose one of these, using this RNG". Apart from the order, it
+/// accepts the same branches, including a trailing comma and block branches.
+///
+/// `choose!({ a, b, c } using rng)` expands to
+/// `branch_using!(rng, { a, b, c })`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::choose;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let greeting = choose!({
+///     "Hello",
+///     {
+///         let informal = "Hi";
+///         informal
+///     },
+///     "Howdy",
+/// } using my_rng);
+/// assert!(["Hello", "Hi", "Howdy"].contains(&greeting));
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! choose {
+	( { $( $branch:expr ),* $(,)? } using $rng:expr ) => {
+		$crate::branch_using!($rng, { $( $branch ),* })
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, optimized
+/// for power-of-two branch counts.
+///
+/// This macro dose essentially the same as [`branch_using`], but if the number
+/// of branches is a power of two (e.g. 2, 4, 8, 16, …), the branch is selected
+/// by masking the low bits of a single `u32` drawn from the
+/// [`Rng`](rand::Rng) instead of calling `gen_range`. Since the number of
+/// branches is known at compile-time, this decision is made statically and
+/// costs nothing at runtime.
+///
+/// For power-of-two branch counts the selection is exactly uniform, since
+/// every branch corresponds to the same number of `u32` values. For any other
+/// branch count, this macro simply falls back to `gen_range` just like
+/// [`branch_using`], so it is always uniform, but only faster for powers of
+/// two.
+///
+/// Notice that this macro will consume the RNG differently than
+/// [`branch_using`], thus the same seeded RNG might select a different branch.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_fast_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// // Four branches, so this just masks two bits of a single `u32`
+/// let num = branch_fast_using!( my_rng, {
+///     1,
+///     2,
+///     3,
+///     4,
+/// });
+/// assert!(1 <= num && num <= 4);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_fast_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[fast] $rng,
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, optimized for power-of-two
+/// branch counts.
+///
+/// This macro is to [`branch_fast_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_fast;
+///
+/// let direction = branch_fast!(
+///     "north",
+///     "east",
+///     "south",
+///     "west",
+/// );
+/// println!("Go {}!", direction);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_fast {
+	( $( $branch:expr ),* $(,)? ) => {
+		{
+			$crate::branch_internal!(
+				[fast] $crate::ThreadBranchRng,
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions within the given range of
+/// indices, using the given RNG.
+///
+/// This macro dose essentially the same as [`branch_using`], but it only
+/// selects among the branches whose index is within the given range, e.g.
+/// `1..=2` for the second and the third branch, uniformly. Thus, the range
+/// can enable or disable leading or trailing branches at runtime, e.g. to
+/// unlock more branches as a game progresses. The range can be any
+/// [`RangeBounds<usize>`](core::ops::RangeBounds), e.g. `1..3`, `1..=2`,
+/// `..n` or `n..`.
+///
+/// # Panics
+///
+/// Panics if the range is empty, or if it is not within `0..n` for `n`
+/// branches, i.e. if it refers to a branch that doesn't exist.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_subset_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let level = 2;
+/// let enemy = branch_subset_using!(my_rng, ..level, {
+///     "slime",
+///     "goblin",
+///     "dragon",
+/// });
+/// assert!(enemy == "slime" || enemy == "goblin");
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_subset_using {
+	( $rng:expr, $range:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let index = $crate::select::subset(
+				&mut $rng,
+				$range,
+				$crate::branch_internal!(@count { $( $branch )* }),
+			);
+			$crate::branch_internal!(
+				[index] index,
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions within the given range of
+/// indices.
+///
+/// This macro is to [`branch_subset_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_subset;
+///
+/// let greeting = branch_subset!(1.., { "Hello", "Hi", "Hey" });
+/// assert!(greeting == "Hi" || greeting == "Hey");
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_subset {
+	( $range:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_subset_using!(
+			$crate::ThreadBranchRng,
+			$range,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Executes all of the given expressions in a random order using the given
+/// RNG, continuing even if some of them panic.
+///
+/// Unlike [`branch_using`], this macro does not pick a single branch, instead
+/// every branch is executed exactly once, in an order shuffled by the given
+/// [`Rng`](rand::Rng). This is useful for test sweeps, where all branches
+/// should be exercised in random order.
+///
+/// Each branch is run within [`std::panic::catch_unwind`], so a panicking
+/// branch does not prevent the remaining branches from running. Once all
+/// branches have been executed, the macro returns a
+/// `Vec<(usize, Box<dyn Any + Send>)>` containing the branch index and the
+/// panic payload of every branch that panicked, in the order they were
+/// executed. An empty `Vec` means that no branch panicked. The panics are not
+/// resumed, if you want to propagate them, you can pass any payload to
+/// [`std::panic::resume_unwind`].
+///
+/// The values of the branches are discarded, so the branches may have
+/// different types. Also notice that the branches are executed within a
+/// closure, so `return`, `break`, `continue`, and `?` are not available within
+/// them.
+///
+/// # Unwind Safety
+///
+/// The branches are treated as [`AssertUnwindSafe`](std::panic::AssertUnwindSafe),
+/// so they may share mutable state, such as a counter. This means that a
+/// branch may observe state which a previously panicked branch left in an
+/// inconsistent state. Since you explicitly opt-in to continue after a panic
+/// by using this macro, it is up to the branches to cope with that.
+///
+/// Notice that the regular panic hook still runs for each panicking branch,
+/// so the panic messages are still printed.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::shuffle_branches_resilient_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut executed = [false; 4];
+///
+/// let failures = shuffle_branches_resilient_using!( my_rng, {
+///     executed[0] = true,
+///     {
+///         executed[1] = true;
+///         panic!("Deliberately failed")
+///     },
+///     executed[2] = true,
+///     executed[3] = true,
+/// });
+///
+/// // All branches have been executed, regardless of their order
+/// assert_eq!(executed, [true; 4]);
+///
+/// // Exactly the second branch has failed
+/// assert_eq!(failures.len(), 1);
+/// assert_eq!(failures[0].0, 1);
+/// assert_eq!(
+///     failures[0].1.downcast_ref::<&str>(),
+///     Some(&"Deliberately failed"),
+/// );
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! shuffle_branches_resilient_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::resilient::run_shuffled(
+			&mut $rng,
+			$crate::branch_internal!(@count { $( $branch )* }),
+			|index| $crate::branch_internal!(
+				[index] index,
+				{ $( { let _ = $branch; } )* },
+			),
+		)
+	};
+}
+
+
+/// Executes all of the given expressions in a random order, continuing even
+/// if some of them panic.
+///
+/// This macro is to [`shuffle_branches_resilient_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`].
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::shuffle_branches_resilient;
+/// let mut count = 0;
+///
+/// let failures = shuffle_branches_resilient!(
+///     count += 1,
+///     count += 10,
+///     if count > 0 {
+///         panic!("Not the first branch")
+///     },
+/// );
+///
+/// assert_eq!(count, 11);
+/// assert!(failures.len() <= 1);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! shuffle_branches_resilient {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::shuffle_branches_resilient_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Shuffles the given mutable slice in place, using the given RNG.
+///
+/// This macro is a thin wrapper around
+/// [`SliceRandom::shuffle`](rand::seq::SliceRandom::shuffle), i.e. a
+/// Fisher–Yates shuffle, which makes every permutation equally likely. It
+/// accepts anything that dereferences to a mutable slice, such as
+/// `&mut [T; N]` or `&mut Vec<T>`. While the branching macros select one of
+/// several expressions, this one randomizes the order of some values.
+///
+/// With the `deterministic` feature, the slice is left in its original order
+/// and the RNG expression is not evaluated.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::shuffle_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let mut cards = [1, 2, 3, 4, 5, 6];
+/// shuffle_using!(my_rng, &mut cards);
+///
+/// cards.sort();
+/// assert_eq!(cards, [1, 2, 3, 4, 5, 6]);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! shuffle_using {
+	( $rng:expr, $slice:expr $(,)? ) => {
+		{
+			let slice: &mut [_] = $slice;
+			if !$crate::select::DETERMINISTIC {
+				$crate::rand::seq::SliceRandom::shuffle(slice, &mut $rng);
+			}
+		}
+	};
+}
+
+
+/// Shuffles the given mutable slice in place.
+///
+/// This macro is to [`shuffle_using`] what [`branch`] is to [`branch_using`],
+/// it uses the [`ThreadBranchRng`] and thus requires `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::shuffle;
+///
+/// let mut playlist = vec!["intro", "verse", "chorus", "outro"];
+/// shuffle!(&mut playlist);
+/// println!("{:?}", playlist);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! shuffle {
+	( $slice:expr $(,)? ) => {
+		$crate::shuffle_using!($crate::ThreadBranchRng, $slice)
+	};
+}
+
+
+/// Picks a reference to one of the items of a slice, with a probability
+/// proportional to the weight at the same index of another slice, using the
+/// given RNG.
+///
+/// This macro dose essentially the same as
+/// [`SliceRandom::choose_weighted`](rand::seq::SliceRandom::choose_weighted),
+/// i.e. it samples a [`WeightedIndex`](rand::distributions::WeightedIndex),
+/// but it takes the weights as a separate slice instead of a function of the
+/// items. Unlike [`branch_weighted_using`], the items and weights are runtime
+/// slices, which may be of any length, and the weights may be of any type
+/// supported by `WeightedIndex`, e.g. `u32` or `f64`. Anything that
+/// dereferences to a slice, such as `&[T; N]` or `&Vec<T>`, is accepted.
+///
+/// Instead of panicking, this macro evaluates to `None` if the slices differ in
+/// length, or if the weights are invalid, i.e. empty, all zero, negative or
+/// NaN. Otherwise, it evaluates to `Some` reference to the picked item.
+///
+/// With the `deterministic` feature, the first item with a positive weight is
+/// picked, without drawing from the RNG.
+///
+/// This macro requires an allocator, i.e. the `alloc` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::weighted_pick_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let items = ["common", "rare", "never"];
+/// let weights = [9.5, 0.5, 0.0];
+/// let item = weighted_pick_using!(my_rng, &items, &weights).unwrap();
+/// assert_ne!(*item, "never");
+///
+/// // Mismatched or empty slices don't pick anything
+/// assert_eq!(weighted_pick_using!(my_rng, &items, &[1, 2]), None);
+/// assert_eq!(weighted_pick_using!(my_rng, &[] as &[&str], &[] as &[u32]), None);
+/// ```
+#[macro_export]
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "alloc")))]
+macro_rules! weighted_pick_using {
+	( $rng:expr, $items:expr, $weights:expr $(,)? ) => {
+		{
+			let items: &[_] = $items;
+			let weights: &[_] = $weights;
+			$crate::weighted::pick(&mut $rng, items, weights)
+		}
+	};
+}
+
+
+/// Picks a reference to one of the items of a slice, with a probability
+/// proportional to the weight at the same index of another slice.
+///
+/// This macro is to [`weighted_pick_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::weighted_pick;
+///
+/// let servers = vec!["primary", "secondary"];
+/// let capacities = vec![3_u32, 1];
+/// let server = weighted_pick!(&servers, &capacities);
+/// println!("{:?}", server);
+/// # assert!(server.is_some());
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! weighted_pick {
+	( $items:expr, $weights:expr $(,)? ) => {
+		$crate::weighted_pick_using!($crate::ThreadBranchRng, $items, $weights)
+	};
+}
+
+
+/// Creates an array by branching into one of the given expressions for every
+/// element independently, using the given RNG.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// a single value it yields an array `[T; N]`, where each element is the result
+/// of an independent random branch. Thus, exactly `N` branches are executed.
+///
+/// `N` must be a constant expression, e.g. a literal or a `const`. Since the
+/// array is built using [`core::array::from_fn`], the element type needs
+/// neither implement `Default` nor `Copy`, and it works without `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_array_using;
+/// #[derive(Debug, PartialEq)]
+/// enum Tile {
+///     Grass,
+///     Water,
+///     Rock,
+/// }
+/// const SIZE: usize = 64;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let board: [Tile; SIZE] = branch_array_using!( my_rng, SIZE, {
+///     Tile::Grass,
+///     Tile::Water,
+///     Tile::Rock,
+/// });
+/// assert_eq!(board.len(), SIZE);
+/// # assert!(board.contains(&Tile::Grass));
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_array_using {
+	( $rng:expr, $n:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let rng = &mut $rng;
+			::core::array::from_fn::<_, { $n }, _>(|_| {
+				$crate::branch_internal!(
+					[uniform] *rng,
+					{ $( $branch )* },
+				)
+			})
+		}
+	};
+}
+
+
+/// Creates an array by branching into one of the given expressions for every
+/// element independently.
+///
+/// This macro is to [`branch_array_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_array;
+///
+/// let coins: [char; 16] = branch_array!(16, 'H', 'T');
+/// println!("{}", coins.iter().collect::<String>());
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_array {
+	( $n:expr, $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_array_using!(
+			$crate::ThreadBranchRng,
+			$n,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Creates an array of `n` independent fair coin flips, using the given RNG.
+///
+/// This macro dose essentially the same as
+/// `branch_array_using!(rng, n, { false, true })`, but more efficiently: up to
+/// 8 flips are extracted from a single `rng.gen::<u8>()`, up to 32 from a
+/// single `u32`, and longer arrays from one `u64` per 64 flips. Thus, it is
+/// well suited to generate bit patterns for test vectors.
+///
+/// `n` must be a constant expression, the result is a `[bool; n]`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::random_bits_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let pattern: [bool; 8] = random_bits_using!(my_rng, 8);
+/// let byte = pattern.iter().rev().fold(0u8, |byte, &bit| byte << 1 | u8::from(bit));
+/// println!("{:08b}", byte);
+///
+/// let long = random_bits_using!(my_rng, 100);
+/// assert_eq!(long.len(), 100);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! random_bits_using {
+	( $rng:expr, $n:expr $(,)? ) => {
+		$crate::bits::bits::<_, { $n }>(&mut $rng)
+	};
+}
+
+
+/// Creates an array of `n` independent fair coin flips.
+///
+/// This macro is to [`random_bits_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::random_bits;
+///
+/// let flips: [bool; 16] = random_bits!(16);
+/// println!("{:?}", flips);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! random_bits {
+	( $n:expr $(,)? ) => {
+		$crate::random_bits_using!($crate::ThreadBranchRng, $n)
+	};
+}
+
+
+/// Creates an iterator of `n` values, each by branching into one of the given
+/// expressions independently, using the given RNG.
+///
+/// This macro samples with replacement: it dose essentially the same as
+/// [`branch_array_using`], i.e. it selects and evaluates a branch `n` times
+/// independently and uniformly, so the same branch may be selected multiple
+/// times, but instead of the array, it yields an iterator over its values.
+/// Thus, it can be directly collected into any collection, or chained with
+/// other iterator adapters. All branches are evaluated eagerly, before the
+/// iterator is returned, and without allocating.
+///
+/// `n` must be a constant expression, and all branches must have the same
+/// type.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_sample_n_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let samples: Vec<u32> = branch_sample_n_using!( my_rng, 5, {
+///     1,
+///     2,
+///     3,
+/// }).collect();
+/// assert_eq!(samples.len(), 5);
+/// assert!(samples.iter().all(|s| (1..=3).contains(s)));
+///
+/// let total: u32 = branch_sample_n_using!( my_rng, 100, { 0, 1 }).sum();
+/// assert!(total <= 100);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_sample_n_using {
+	( $rng:expr, $n:expr, { $( $branch:expr ),* $(,)? }) => {
+		::core::iter::IntoIterator::into_iter(
+			$crate::branch_array_using!($rng, $n, { $( $branch ),* })
+		)
+	};
+}
+
+
+/// Creates an iterator of `n` values, each by branching into one of the given
+/// expressions independently.
+///
+/// This macro is to [`branch_sample_n_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_sample_n;
+///
+/// let word: String = branch_sample_n!(8, 'a', 'b', 'c').collect();
+/// assert_eq!(word.len(), 8);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_sample_n {
+	( $n:expr, $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_sample_n_using!(
+			$crate::ThreadBranchRng,
+			$n,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Creates a `Vec` by branching into one of the given expressions `n` times
+/// independently, using the given RNG.
+///
+/// This macro dose essentially the same as [`branch_array_using`], but yields
+/// a `Vec<T>` of length `n`, where `n` may be any runtime `usize` expression.
+/// The `Vec` is allocated with the exact capacity up front, and for each
+/// element only the chosen branch is evaluated.
+///
+/// If `n` is zero, an empty `Vec` is returned without touching the RNG.
+///
+/// This macro requires the `alloc` feature, but not `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_vec_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut counter = 0;
+///
+/// let samples = branch_vec_using!( my_rng, 10_000, {
+///     0,
+///     { counter += 1; counter },
+///     -1,
+/// });
+/// assert_eq!(samples.len(), 10_000);
+/// assert!(samples.iter().all(|&s| -1 <= s && s <= counter));
+/// ```
+#[macro_export]
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "alloc")))]
+macro_rules! branch_vec_using {
+	( $rng:expr, $n:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let n: usize = $n;
+			let rng = &mut $rng;
+			let mut vec = $crate::alloc::vec::Vec::with_capacity(n);
+			for _ in 0..n {
+				vec.push($crate::branch_internal!(
+					[uniform] *rng,
+					{ $( $branch )* },
+				));
+			}
+			vec
+		}
+	};
+}
+
+
+/// Creates a `Vec` by branching into one of the given expressions `n` times
+/// independently.
+///
+/// This macro is to [`branch_vec_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_vec;
+///
+/// let dice = branch_vec!(5, 1, 2, 3, 4, 5, 6);
+/// assert_eq!(dice.len(), 5);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_vec {
+	( $n:expr, $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_vec_using!(
+			$crate::ThreadBranchRng,
+			$n,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions, but decides which one only
+/// once per call site, using the given RNG.
+///
+/// The first time this macro is evaluated, it randomly selects a branch using
+/// the given [`Rng`](rand::Rng) just like [`branch_using`]. This decision is
+/// then memoized for the lifetime of the program, so every subsequent
+/// evaluation of the same macro invocation will execute the same branch again.
+/// Notice that only the decision is memoized, the chosen expression is
+/// evaluated again each time.
+///
+/// The RNG is only used for the very first evaluation, afterwards it is not
+/// touched at all.
+///
+/// This is useful e.g. to pick a random backend at startup and sticking with
+/// it.
+///
+/// # Thread Safety
+///
+/// The decision is stored in a `static` [`OnceLock`](std::sync::OnceLock)
+/// specific to each invocation of this macro, thus it is shared by all
+/// threads. If several threads evaluate the same invocation for the first
+/// time concurrently, only one of them draws the decision (first writer wins),
+/// while the others block until it is available and then use the same
+/// decision.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_at_most_once_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let mut backends = Vec::new();
+/// for _ in 0..10 {
+///     backends.push(branch_at_most_once_using!( my_rng, {
+///         "vulkan",
+///         "metal",
+///         "opengl",
+///     }));
+/// }
+/// // All iterations took the same branch
+/// assert!(backends.iter().all(|b| *b == backends[0]));
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_at_most_once_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			static DECISION: $crate::std::sync::OnceLock<usize> =
+				$crate::std::sync::OnceLock::new();
+			let index = *DECISION.get_or_init(|| {
+				$crate::select::uniform(
+					&mut $rng,
+					$crate::branch_count!({ $( $branch ),* }),
+				)
+			});
+			$crate::branch_internal!(
+				[index] index,
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, but decides which one only
+/// once per call site.
+///
+/// This macro is to [`branch_at_most_once_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`].
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_at_most_once;
+///
+/// fn greeting() -> &'static str {
+///     branch_at_most_once!("Hello", "Hi", "Hey")
+/// }
+///
+/// // Every call takes the same branch
+/// assert_eq!(greeting(), greeting());
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_at_most_once {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_at_most_once_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Fills a fixed-capacity collection by branching into one of the given
+/// expressions for every element independently, using the given RNG.
+///
+/// This macro dose essentially the same as [`branch_vec_using`], but instead of
+/// allocating, it pushes the results of random branches into the given
+/// [`PushSink`] until it is full. Afterwards, it yields the number of
+/// elements that have been pushed. If the collection is full already, neither
+/// the RNG is touched, nor any branch is evaluated.
+///
+/// This is suitable for embedded targets without an allocator, e.g. with a
+/// `heapless::Vec` (requires the `heapless` feature) or an
+/// `arrayvec::ArrayVec` (requires the `arrayvec` feature).
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_fill_using;
+/// use random_branch::PushSink;
+///
+/// // Some fixed-capacity queue
+/// struct Queue([u8; 8], usize);
+/// impl PushSink<u8> for Queue {
+///     fn is_full(&self) -> bool {
+///         self.1 == self.0.len()
+///     }
+///     fn try_push(&mut self, value: u8) -> Result<(), u8> {
+///         if self.is_full() {
+///             return Err(value);
+///         }
+///         self.0[self.1] = value;
+///         self.1 += 1;
+///         Ok(())
+///     }
+/// }
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut queue = Queue([0; 8], 3);
+///
+/// let written = branch_fill_using!( my_rng, &mut queue, {
+///     1,
+///     2,
+///     3,
+/// });
+/// assert_eq!(written, 5);
+/// assert!(queue.is_full());
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_fill_using {
+	( $rng:expr, $sink:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let sink = $sink;
+			let rng = &mut $rng;
+			let mut written: usize = 0;
+			while !$crate::PushSink::is_full(&*sink) {
+				let value = $crate::branch_internal!(
+					[uniform] *rng,
+					{ $( $branch )* },
+				);
+				if $crate::PushSink::try_push(&mut *sink, value).is_err() {
+					break;
+				}
+				written += 1;
+			}
+			written
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, boxing the
+/// result as the given trait object.
+///
+/// This macro dose essentially the same as [`branch_using`], but each
+/// expression is boxed and converted into `Box<dyn Trait>`, where `dyn Trait`
+/// is given as the second argument. This allows the expressions to have
+/// different types, as long as they all implement that trait, e.g. returning
+/// different kinds of iterators.
+///
+/// This macro requires the `alloc` feature, but not `std`.
+///
+/// This macro turns something like this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// # use random_branch::branch_boxed_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// branch_boxed_using!( my_rng, dyn Iterator<Item = u8>, {
+///     vec![1_u8, 2, 3].into_iter(),
+///     4_u8..6,
+/// });
+/// ```
+///
+/// into something similar to this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// # use rand::Rng;
+///
+/// match my_rng.gen_range(0..2) {
+///     0 => Box::new(vec![1_u8, 2, 3].into_iter()) as Box<dyn Iterator<Item = u8>>,
+///     1 => Box::new(4_u8..6) as Box<dyn Iterator<Item = u8>>,
+///     _ => unreachable!(),
+/// };
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_boxed_using;
+/// use std::fmt::Display;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let thing: Box<dyn Display> = branch_boxed_using!( my_rng, dyn Display, {
+///     42,
+///     "fuliluf",
+///     'x',
+/// });
+/// println!("The thing is {}", thing);
+/// ```
+#[macro_export]
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "alloc")))]
+macro_rules! branch_boxed_using {
+	( $rng:expr, $trait:ty, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[uniform] $rng,
+				{ $( {
+					$crate::alloc::boxed::Box::new($branch)
+						as $crate::alloc::boxed::Box<$trait>
+				} )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, boxing the result as the given
+/// trait object.
+///
+/// This macro is to [`branch_boxed_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_boxed;
+///
+/// let numbers = branch_boxed!(
+///     dyn Iterator<Item = u32>,
+///     1_u32..4,
+///     vec![2_u32, 4, 6].into_iter(),
+///     std::iter::repeat(7_u32).take(3),
+/// );
+/// assert_eq!(numbers.count(), 3);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_boxed {
+	( $trait:ty, $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_boxed_using!(
+			$crate::ThreadBranchRng,
+			$trait,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Evaluates the given expression with the given probability using the given
+/// RNG, yielding an `Option`.
+///
+/// With probability `p`, the expression is evaluated and its value is returned
+/// as `Some`, otherwise the expression is not evaluated at all and `None` is
+/// returned.
+///
+/// The probability `p` is an `f64` which is passed to
+/// [`Rng::gen_bool`](rand::Rng::gen_bool), thus it must be within `0.0..=1.0`,
+/// otherwise this macro panics.
+///
+/// This macro turns something like this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// # use random_branch::maybe_using;
+/// # fn compute() -> u32 { 42 }
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// maybe_using!(my_rng, 0.3, compute());
+/// ```
+///
+/// into something similar to this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// # fn compute() -> u32 { 42 }
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// # use rand::Rng;
+///
+/// if my_rng.gen_bool(0.3) {
+///     Some(compute())
+/// } else {
+///     None
+/// };
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::maybe_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// assert_eq!(maybe_using!(my_rng, 1.0, 42), Some(42));
+/// assert_eq!(maybe_using!(my_rng, 0.0, unreachable!()), None::<u32>);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! maybe_using {
+	( $rng:expr, $p:expr, $branch:expr $(,)? ) => {
+		if $crate::rand::Rng::gen_bool(&mut $rng, $p) {
+			::core::option::Option::Some($branch)
+		} else {
+			::core::option::Option::None
+		}
+	};
+}
+
+
+/// Evaluates the given expression with the given probability, yielding an
+/// `Option`.
+///
+/// This macro is to [`maybe_using`] what [`branch`] is to [`branch_using`], it
+/// uses the [`ThreadBranchRng`] and thus requires `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::maybe;
+///
+/// fn compute() -> u32 {
+///     42
+/// }
+///
+/// let answer: Option<u32> = maybe!(0.3, compute());
+/// match answer {
+///     Some(answer) => println!("The answer is {}", answer),
+///     None => println!("There is no answer"),
+/// }
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! maybe {
+	( $p:expr, $branch:expr $(,)? ) => {
+		$crate::maybe_using!($crate::ThreadBranchRng, $p, $branch)
+	};
+}
+
+
+/// Executes the given expression with the given probability using the given
+/// RNG, doing nothing otherwise.
+///
+/// This macro dose essentially the same as [`maybe_using`], but it is a
+/// statement for side effects only: the value of the expression is discarded,
+/// just as with `expr;`, and the macro itself evaluates to `()`. This reads
+/// naturally for code that should only sometimes run, e.g. sampled logging.
+/// Like any statement, an expression of a `#[must_use]` type, such as
+/// `Result`, triggers the `unused_must_use` lint.
+///
+/// The probability `p` is an `f64` which is passed to
+/// [`Rng::gen_bool`](rand::Rng::gen_bool), thus it must be within `0.0..=1.0`,
+/// otherwise this macro panics.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::perhaps_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut log = Vec::new();
+///
+/// for request in 0..100 {
+///     perhaps_using!(my_rng, 0.1, log.push(request));
+/// }
+/// # assert!(log.len() < 100);
+///
+/// perhaps_using!(my_rng, 0.0, unreachable!());
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! perhaps_using {
+	( $rng:expr, $p:expr, $branch:expr $(,)? ) => {
+		if $crate::rand::Rng::gen_bool(&mut $rng, $p) {
+			$branch;
+		}
+	};
+}
+
+
+/// Executes the given expression with the given probability, doing nothing
+/// otherwise.
+///
+/// This macro is to [`perhaps_using`] what [`branch`] is to [`branch_using`],
+/// it uses the [`ThreadBranchRng`] and thus requires `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::perhaps;
+///
+/// for step in 0..10 {
+///     perhaps!(0.2, println!("Sampled step {}", step));
+/// }
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! perhaps {
+	( $p:expr, $branch:expr $(,)? ) => {
+		$crate::perhaps_using!($crate::ThreadBranchRng, $p, $branch)
+	};
+}
+
+
+/// Evaluates the given expression with the global default probability using
+/// the given RNG, yielding an `Option`.
+///
+/// This macro dose essentially the same as [`maybe_using`], but instead of
+/// taking the probability as argument, it reads the process-wide default
+/// injection rate, see [`set_default_injection_rate`]. This is intended for
+/// fault injection: all call sites share a single knob, which is `0.0` by
+/// default, so the expression is never evaluated unless a test raises the
+/// rate. As long as the rate is `0.0`, the RNG is not used at all.
+///
+/// This macro requires a target with 64-bit atomics.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::set_default_injection_rate;
+/// use random_branch::with_probability_default_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// fn send(my_rng: &mut Lcg64Xsh32) -> Result<(), &'static str> {
+///     if let Some(error) = with_probability_default_using!(*my_rng, "injected timeout") {
+///         return Err(error);
+///     }
+///     Ok(())
+/// }
+///
+/// // By default, nothing is injected
+/// assert_eq!(send(&mut my_rng), Ok(()));
+///
+/// // An integration test can raise the rate
+/// set_default_injection_rate(1.0);
+/// assert_eq!(send(&mut my_rng), Err("injected timeout"));
+/// ```
+#[macro_export]
+#[cfg(all(feature = "rand", target_has_atomic = "64"))]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(all(feature = "rand", target_has_atomic = "64"))))]
+macro_rules! with_probability_default_using {
+	( $rng:expr, $branch:expr $(,)? ) => {
+		{
+			let rate = $crate::default_injection_rate();
+			if rate > 0.0 && $crate::rand::Rng::gen_bool(&mut $rng, rate) {
+				::core::option::Option::Some($branch)
+			} else {
+				::core::option::Option::None
+			}
+		}
+	};
+}
+
+
+/// Evaluates the given expression with the global default probability,
+/// yielding an `Option`.
+///
+/// This macro is to [`with_probability_default_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::with_probability_default;
+///
+/// // The default rate is `0.0`
+/// assert_eq!(with_probability_default!(unreachable!()), None::<u32>);
+/// ```
+#[macro_export]
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(all(feature = "std", target_has_atomic = "64"))))]
+macro_rules! with_probability_default {
+	( $branch:expr $(,)? ) => {
+		$crate::with_probability_default_using!($crate::ThreadBranchRng, $branch)
+	};
+}
+
+
+/// Creates an infinite iterator, which branches into one of the given
+/// expressions for each item, using the given RNG.
+///
+/// This macro yields a [`BranchIter`], which on each call to `next` draws a
+/// fresh random branch just like [`branch_using`] and evaluates only that
+/// branch. The iterator is lazy, nothing is evaluated and the RNG is not used
+/// until `next` is called.
+///
+/// The iterator takes ownership of the given RNG expression. So, if you want
+/// to keep using your RNG after the iterator has been dropped, pass a mutable
+/// reference (i.e. `&mut my_rng`) instead.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_iter_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let colors: Vec<&str> = branch_iter_using!( &mut my_rng, {
+///     "red",
+///     "green",
+///     "blue",
+/// }).take(100).collect();
+/// assert_eq!(colors.len(), 100);
+///
+/// // `my_rng` is still usable
+/// let color = branch_iter_using!( &mut my_rng, { "red", "blue" }).next();
+/// assert!(color.is_some());
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_iter_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::BranchIter::new($rng, |rng| {
+			$crate::branch_internal!(
+				[uniform] *rng,
+				{ $( $branch )* },
+			)
+		})
+	};
+}
+
+
+/// Creates an infinite iterator, which branches into one of the given
+/// expressions for each item.
+///
+/// This macro is to [`branch_iter_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_iter;
+///
+/// let word: String = branch_iter!('a', 'b', 'c').take(8).collect();
+/// assert_eq!(word.len(), 8);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_iter {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_iter_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Evaluates every one of the given expressions exactly once, in declaration
+/// order.
+///
+/// Unlike [`branch_using`], this macro involves no randomness at all. Instead,
+/// it is meant for tests that need to cover every branch of a random choice,
+/// without relying on luck. The branches are given in the same braced form as
+/// for [`branch_using`], so a list of branches can be copied verbatim or
+/// forwarded by some user macro to either of them.
+///
+/// If a closure (or any other `FnMut`) is given after the branches, it is
+/// called with the value of each branch, in declaration order. Otherwise, the
+/// values are collected into an array, with one element per branch.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::for_each_branch;
+///
+/// fn assert_valid(greeting: &str) {
+///     assert!(greeting.ends_with('!'));
+/// }
+///
+/// for_each_branch!({
+///     "Hello!",
+///     "Hi!",
+///     "Hey!",
+/// }, |greeting| assert_valid(greeting));
+/// ```
+///
+/// Collecting the values into an array:
+///
+/// ```rust
+/// use random_branch::for_each_branch;
+/// let mut evaluated = Vec::new();
+///
+/// let values = for_each_branch!({
+///     { evaluated.push('a'); 1 },
+///     { evaluated.push('b'); 2 },
+///     { evaluated.push('c'); 3 },
+/// });
+/// assert_eq!(values, [1, 2, 3]);
+/// assert_eq!(evaluated, ['a', 'b', 'c']);
+/// ```
+#[macro_export]
+macro_rules! for_each_branch {
+	( { $( $branch:expr ),* $(,)? } $(,)? ) => {
+		::core::array::from_fn::<_, { $crate::branch_internal!(@count { $( $branch )* }) }, _>(
+			|index| $crate::branch_internal!(
+				[index] index,
+				{ $( $branch )* },
+			)
+		)
+	};
+	( { $( $branch:expr ),* $(,)? }, $f:expr $(,)? ) => {
+		{
+			let mut f = $f;
+			for index in 0 .. $crate::branch_internal!(@count { $( $branch )* }) {
+				f($crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				));
+			}
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG and
+/// distribution.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// choosing uniformly, the index of the branch is sampled from the given
+/// [`Distribution<usize>`](rand::distributions::Distribution). This allows
+/// plugging in arbitrary distributions, e.g. a
+/// [`WeightedIndex`](rand::distributions::WeightedIndex).
+///
+/// The distribution must only yield values in `0..n`, where `n` is the number
+/// of branches, i.e. the first branch has index `0`. With debug assertions
+/// enabled, this contract is checked and violating it panics. Without debug
+/// assertions, an out-of-range index will just panic as unreachable.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_dist_using;
+/// use rand::distributions::WeightedIndex;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let weights = WeightedIndex::new(&[1, 0, 3]).unwrap();
+///
+/// let num = branch_dist_using!( my_rng, weights, {
+///     1,
+///     2,
+///     3,
+/// });
+/// assert!(num == 1 || num == 3);
+/// ```
+///
+/// Out-of-range indices are rejected:
+///
+/// ```rust,should_panic
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_dist_using;
+/// use rand::distributions::Uniform;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// // Yields index 2 at some point, but there are only two branches
+/// for _ in 0..100 {
+///     branch_dist_using!( my_rng, Uniform::new(0, 3), {
+///         1,
+///         2,
+///     });
+/// }
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_dist_using {
+	( $rng:expr, $dist:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[index] $crate::rand::distributions::Distribution::<usize>::sample(
+					&$dist,
+					&mut $rng,
+				),
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the given distribution.
+///
+/// This macro is to [`branch_dist_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_dist;
+/// use rand::distributions::WeightedIndex;
+///
+/// let weights = WeightedIndex::new(&[10, 1]).unwrap();
+/// let answer = branch_dist!(weights, "yes", "no");
+/// assert!(answer == "yes" || answer == "no");
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_dist {
+	( $dist:expr, $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_dist_using!(
+			$crate::ThreadBranchRng,
+			$dist,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Creates a shuffle bag of the given expressions.
+///
+/// This macro yields a [`Deck`], from which branches can be drawn using
+/// [`Deck::draw`]. Each draw evaluates one of the given expressions, however,
+/// unlike [`branch_using`], every expression is evaluated exactly once per
+/// cycle, in a random order that is reshuffled for each cycle.
+///
+/// The expressions are stored within a closure, which borrows whatever the
+/// expressions use. Prefix the branches with `move` to create a `move`
+/// closure instead, e.g. to return the deck from a function.
+///
+/// This works without `std` and without an allocator.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_deck;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let mut playlist = branch_deck!({
+///     "Song A",
+///     "Song B",
+///     "Song C",
+/// });
+///
+/// let mut first_cycle = [
+///     playlist.draw(&mut my_rng),
+///     playlist.draw(&mut my_rng),
+///     playlist.draw(&mut my_rng),
+/// ];
+/// // Every song has been played exactly once
+/// first_cycle.sort();
+/// assert_eq!(first_cycle, ["Song A", "Song B", "Song C"]);
+/// ```
+///
+/// Using `move` to return the deck:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_deck;
+/// use random_branch::Deck;
+///
+/// fn dice(sides: u32) -> Deck<impl FnMut(usize) -> u32, 3> {
+///     branch_deck!(move {
+///         1,
+///         sides / 2,
+///         sides,
+///     })
+/// }
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut deck = dice(6);
+/// let sum: u32 = (0..3).map(|_| deck.draw(&mut my_rng)).sum();
+/// assert_eq!(sum, 10);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_deck {
+	( { $( $branch:expr ),* $(,)? } ) => {
+		$crate::Deck::<_, { $crate::branch_internal!(@count { $( $branch )* }) }>::new(
+			|index| $crate::branch_internal!(
+				[index] index,
+				{ $( $branch )* },
+			)
+		)
+	};
+	( move { $( $branch:expr ),* $(,)? } ) => {
+		$crate::Deck::<_, { $crate::branch_internal!(@count { $( $branch )* }) }>::new(
+			move |index| $crate::branch_internal!(
+				[index] index,
+				{ $( $branch )* },
+			)
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, but not
+/// into one of the recently selected ones.
+///
+/// This macro dose essentially the same as [`branch_using`], but it uses the
+/// given [`NoRepeat<K>`](NoRepeat) state to remember the last `K` selections,
+/// and selects uniformly only among the branches that are not among them. So,
+/// with `NoRepeat<1>` the same branch is never selected twice in a row.
+///
+/// The state must be stored by the caller, e.g. in some `struct`. It
+/// should only be used with the same list of branches, since it only remembers
+/// the indices.
+///
+/// `K` must be less than the number of branches, otherwise this macro fails to
+/// compile.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_norepeat_using;
+/// use random_branch::NoRepeat;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut state = NoRepeat::<2>::new();
+///
+/// let mut sounds = Vec::new();
+/// for _ in 0..100 {
+///     sounds.push(branch_norepeat_using!( state, my_rng, {
+///         "drip",
+///         "drop",
+///         "splash",
+///         "plop",
+///     }));
+/// }
+/// // The same sound never occurs within three consecutive sounds
+/// assert!(sounds.windows(3).all(|w| w[0] != w[1] && w[0] != w[2] && w[1] != w[2]));
+/// ```
+///
+/// Remembering as many selections as there are branches fails to compile:
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_norepeat_using;
+/// use random_branch::NoRepeat;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut state = NoRepeat::<2>::new();
+///
+/// branch_norepeat_using!( state, my_rng, {
+///     "drip",
+///     "drop",
+/// });
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_norepeat_using {
+	( $state:expr, $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[index] $crate::NoRepeat::select::<
+					_,
+					{ $crate::branch_internal!(@count { $( $branch )* }) },
+				>(&mut $state, &mut $rng),
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, yielding
+/// `None` if there are no expressions.
+///
+/// This macro dose essentially the same as [`branch_using`], but it wraps the
+/// value of the chosen branch in `Some`. Unlike [`branch_using`], it also
+/// accepts an empty list of branches, in which case it yields `None` without
+/// evaluating the RNG expression. This is useful for generated code, where
+/// the list of branches may legitimately be empty.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_opt_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let num = branch_opt_using!( my_rng, {
+///     1,
+///     2,
+/// });
+/// assert!(num == Some(1) || num == Some(2));
+///
+/// let nothing: Option<u32> = branch_opt_using!( my_rng, {});
+/// assert_eq!(nothing, None);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_opt_using {
+	( $rng:expr, { } ) => {
+		::core::option::Option::None
+	};
+	( $rng:expr, { $( $branch:expr ),+ $(,)? }) => {
+		::core::option::Option::Some($crate::branch_using!(
+			$rng,
+			{ $( $branch ),+ }
+		))
+	};
+}
+
+
+/// Branches into one of the given expressions, yielding `None` if there are
+/// no expressions.
+///
+/// This macro is to [`branch_opt_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_opt;
+///
+/// assert_eq!(branch_opt!(42), Some(42));
+/// assert_eq!(branch_opt!(), None::<u32>);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_opt {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_opt_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, making
+/// recently selected ones less likely.
+///
+/// This macro dose essentially the same as [`branch_using`], but the
+/// branches are selected according to the given
+/// [`AdaptiveWeights<N>`](AdaptiveWeights) state, where `N` must be the number
+/// of branches. The weight of a selected branch decays, so it is less likely
+/// to be selected again soon, and then recovers over subsequent selections.
+/// See [`AdaptiveWeights`] for the details.
+///
+/// The state must be stored by the caller, e.g. in some `struct`. It
+/// should only be used with the same list of branches, since it only stores a
+/// weight per index.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_adaptive_using;
+/// use random_branch::AdaptiveWeights;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut state = AdaptiveWeights::<3>::default();
+///
+/// for _ in 0..10 {
+///     let footstep = branch_adaptive_using!( state, my_rng, {
+///         "tap",
+///         "tip",
+///         "top",
+///     });
+///     println!("{}", footstep);
+/// }
+/// ```
+///
+/// Using a state with a wrong number of branches fails to compile:
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_adaptive_using;
+/// use random_branch::AdaptiveWeights;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut state = AdaptiveWeights::<3>::default();
+///
+/// branch_adaptive_using!( state, my_rng, {
+///     "tap",
+///     "tip",
+/// });
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_adaptive_using {
+	( $state:expr, $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[index] $crate::AdaptiveWeights::<
+					{ $crate::branch_internal!(@count { $( $branch )* }) },
+				>::select(&mut $state, &mut $rng),
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Counts the given expressions, as a constant expression.
+///
+/// This macro yields the number of branches as a `usize`, without evaluating
+/// any of them. It accepts the branches either as plain arguments, just like
+/// [`branch`], or in the braced form of [`branch_using`].
+///
+/// Since the result is a constant expression, it can be used e.g. as an array
+/// length or const-generic argument. See [`branch_with_count_using`] to also
+/// branch into one of the counted expressions.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_count;
+///
+/// const COUNT: usize = branch_count!({
+///     println!("First line."),
+///     println!("Second line?"),
+///     println!("Third line!"),
+/// });
+/// let buffer = [0u8; COUNT];
+/// assert_eq!(buffer.len(), 3);
+///
+/// assert_eq!(branch_count!(1, 2), 2);
+/// assert_eq!(branch_count!(), 0);
+/// ```
+#[macro_export]
+macro_rules! branch_count {
+	( { $( $branch:expr ),* $(,)? } ) => {
+		$crate::branch_internal!(@count { $( $branch )* })
+	};
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_internal!(@count { $( $branch )* })
+	};
+}
+
+
+/// Branches into the given expression selected by a constant index.
+///
+/// The index must be a constant expression of type `usize`, e.g. a `const`
+/// item or a const-generic parameter, and it is checked at compile time to be
+/// in range, i.e. less than the [`branch_count`] of the given branches. Only
+/// the selected branch is evaluated, and since no RNG is involved, this macro
+/// can be used in `const fn` and other const contexts, as a constant indexed
+/// dispatch.
+///
+/// ```rust,compile_fail
+/// use random_branch::branch_const_index;
+///
+/// const NAME: &str = branch_const_index!(3, { "zero", "one", "two" });
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_const_index;
+///
+/// const fn name<const I: usize>() -> &'static str {
+///     branch_const_index!(I, { "zero", "one", "two" })
+/// }
+///
+/// const ONE: &str = name::<1>();
+/// assert_eq!(ONE, "one");
+/// assert_eq!(name::<2>(), "two");
+/// ```
+#[macro_export]
+macro_rules! branch_const_index {
+	( $index:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			const {
+				::core::assert!(
+					$index < $crate::branch_count!({ $( $branch ),* }),
+					"The branch index is out of range.",
+				)
+			};
+			let mut position: usize = 0;
+			match () {
+				// See `branch_internal` for why the branches are in a nested
+				// `match`
+				#[allow(unused_parens)]
+				() => match () {
+					$( _ if { position += 1; $index == position - 1 } => $branch, )*
+					_ => ::core::unreachable!(),
+				},
+			}
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, yielding
+/// the number of branches too.
+///
+/// This macro dose essentially the same as [`branch_using`], but yields a
+/// tuple `(value, count)`, where `value` is the value of the chosen branch and
+/// `count` is the number of branches, as given by [`branch_count`].
+///
+/// Notice that the `count` of the tuple is not a constant expression. If you
+/// need the count e.g. as array length, use [`branch_count`] on the same list
+/// of branches.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_count;
+/// use random_branch::branch_with_count_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// // Statically sized by the fan-out of the branch below
+/// const COUNT: usize = branch_count!({ 'a', 'b', 'c' });
+/// let mut histogram = [0u8; COUNT];
+///
+/// let (value, count) = branch_with_count_using!( my_rng, {
+///     'a',
+///     'b',
+///     'c',
+/// });
+/// assert_eq!(count, COUNT);
+/// histogram[(value as u8 - b'a') as usize] += 1;
+/// assert_eq!(histogram.iter().sum::<u8>(), 1);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_with_count_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		(
+			$crate::branch_using!($rng, { $( $branch ),* }),
+			$crate::branch_count!({ $( $branch ),* }),
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions, yielding the number of
+/// branches too.
+///
+/// This macro is to [`branch_with_count_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_with_count;
+///
+/// let (value, count) = branch_with_count!(1, 2, 3, 4);
+/// assert_eq!(count, 4);
+/// assert!(1 <= value && value <= count);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_with_count {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_with_count_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, but never
+/// into the same one twice in a row.
+///
+/// This macro dose essentially the same as [`branch_using`], but each
+/// invocation remembers the branch it selected last, and excludes it from the
+/// next selection, choosing uniformly among the remaining branches. This
+/// prevents e.g. the same line of generated text from occurring twice in a
+/// row.
+///
+/// Unlike [`branch_norepeat_using`], the state is managed by the macro
+/// itself: the last selection is stored in a thread-local `Cell<usize>`,
+/// specific to each invocation of this macro (i.e. per call site) and to each
+/// thread. Thus, this macro requires `std`.
+///
+/// If there is just a single branch, it is executed every time anyway.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_no_repeat_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let mut lines = Vec::new();
+/// for _ in 0..100 {
+///     lines.push(branch_no_repeat_using!( my_rng, {
+///         "The sun rises.",
+///         "The birds sing.",
+///         "The wind blows.",
+///     }));
+/// }
+/// assert!(lines.windows(2).all(|w| w[0] != w[1]));
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_no_repeat_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::std::thread_local! {
+				static LAST: ::core::cell::Cell<usize> = const {
+					::core::cell::Cell::new(usize::MAX)
+				};
+			}
+			$crate::branch_internal!(
+				[index] LAST.with(|last| $crate::norepeat::select_excluding_last(
+					last,
+					&mut $rng,
+					$crate::branch_count!({ $( $branch ),* }),
+				)),
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, but never into the same one
+/// twice in a row.
+///
+/// This macro is to [`branch_no_repeat_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`].
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_no_repeat;
+///
+/// fn greet() -> &'static str {
+///     branch_no_repeat!("Hello", "Hi")
+/// }
+///
+/// // With two branches, they simply alternate
+/// let first = greet();
+/// assert_ne!(greet(), first);
+/// assert_eq!(greet(), first);
+///
+/// // A single branch is executed every time
+/// assert_eq!(branch_no_repeat!(1), 1);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_no_repeat {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_no_repeat_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into the same position of several lists of expressions using the
+/// given RNG.
+///
+/// This macro draws a single random index, just like [`branch_using`] does,
+/// but then evaluates the expression at that index in each of the given lists,
+/// yielding a tuple of their values. This is useful to select matching items,
+/// e.g. a question and its answer.
+///
+/// All lists must have the same length, otherwise this macro fails to compile
+/// with a type mismatch between arrays of both lengths, e.g. "expected an
+/// array with a size of 3, found one with a size of 2".
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_zip_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let (question, answer) = branch_zip_using!( my_rng, {
+///     "What is 6 times 7?",
+///     "What is the capital of France?",
+/// }, {
+///     "42",
+///     "Paris",
+/// });
+/// println!("Q: {}\nA: {}", question, answer);
+/// ```
+///
+/// Lists of different lengths fail to compile:
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_zip_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let (question, answer) = branch_zip_using!( my_rng, {
+///     "What is 6 times 7?",
+///     "What is the capital of France?",
+/// }, {
+///     "42",
+/// });
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_zip_using {
+	( $rng:expr,
+		{ $( $first:expr ),* $(,)? }
+		$( , { $( $rest:expr ),* $(,)? } )+
+		$(,)?
+	) => {
+		{
+			const COUNT: usize = $crate::branch_count!({ $( $first ),* });
+			$(
+				// All lists must have the same length
+				let _: [(); COUNT] = [(); $crate::branch_count!({ $( $rest ),* })];
+			)+
+			let selected: usize = $crate::branch_internal!(@select [uniform] $rng, COUNT);
+			(
+				$crate::branch_internal!(
+					[index] selected,
+					{ $( $first )* },
+				),
+				$(
+					$crate::branch_internal!(
+						[index] selected,
+						{ $( $rest )* },
+					),
+				)+
+			)
+		}
+	};
+}
+
+
+/// Branches into the same position of several lists of expressions.
+///
+/// This macro is to [`branch_zip_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_zip;
+///
+/// let (name, symbol, number) = branch_zip!(
+///     { "Hydrogen", "Helium", "Lithium" },
+///     { "H", "He", "Li" },
+///     { 1, 2, 3 },
+/// );
+/// println!("{} ({}) has atomic number {}", name, symbol, number);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_zip {
+	( $( { $( $branch:expr ),* $(,)? } ),+ $(,)? ) => {
+		$crate::branch_zip_using!(
+			$crate::ThreadBranchRng,
+			$( { $( $branch ),* } ),+
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, with a
+/// probability proportional to the given weights.
+///
+/// This macro dose essentially the same as [`branch_using`], but each branch
+/// is prefixed by a weight `w => expr`, where `w` is any expression of a type
+/// implementing `Into<u32>`, e.g. `u32`, `u16`, `u8`, or your own weight
+/// type. The weights are evaluated at runtime, before a branch is selected,
+/// and each branch is selected with a probability of its weight divided by
+/// the sum of all weights. Thus, a branch with weight zero is never selected.
+///
+/// This applies to the weights of all weighted macros of this crate. An
+/// untyped integer literal is taken as `u32`:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_weighted_using;
+///
+/// #[derive(Debug, Clone, Copy)]
+/// enum Weight {
+///     High = 10,
+///     Low = 1,
+/// }
+///
+/// impl From<Weight> for u32 {
+///     fn from(weight: Weight) -> u32 {
+///         weight as u32
+///     }
+/// }
+///
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let small: u8 = 2;
+///
+/// let item = branch_weighted_using!( my_rng, {
+///     Weight::High => "often",
+///     Weight::Low => "seldom",
+///     small => "sometimes",
+///     0 => "never",
+/// });
+/// assert_ne!(item, "never");
+/// ```
+///
+/// # Panics
+///
+/// Panics if all weights are zero. Use [`try_branch_weighted_using`] to get
+/// an error instead.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_weighted_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let rare = 1;
+///
+/// let item = branch_weighted_using!( my_rng, {
+///     10 => "common",
+///     3 => "uncommon",
+///     rare => "rare",
+///     0 => "never",
+/// });
+/// assert_ne!(item, "never");
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_weighted_using {
+	( $rng:expr, { $( $weight:expr => $branch:expr ),* $(,)? }) => {
+		match $crate::try_branch_weighted_using!($rng, { $( $weight => $branch ),* }) {
+			::core::result::Result::Ok(value) => value,
+			::core::result::Result::Err(_) => $crate::weighted::all_weights_zero(),
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, with a probability
+/// proportional to the given weights.
+///
+/// This macro is to [`branch_weighted_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_weighted;
+///
+/// let coin = branch_weighted!(
+///     49 => "heads",
+///     49 => "tails",
+///     2 => "edge",
+/// );
+/// println!("It's {}", coin);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_weighted {
+	( $( $weight:expr => $branch:expr ),* $(,)? ) => {
+		$crate::branch_weighted_using!(
+			$crate::ThreadBranchRng,
+			{ $( $weight => $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, with a
+/// probability proportional to the given weights, rejecting zero weight
+/// literals at compile time.
+///
+/// This macro dose essentially the same as [`branch_weighted_using`], but if
+/// any weight is an integer literal that is zero, such as `0` or `0_u32`, it
+/// fails to compile, since a branch with a constant zero weight can never be
+/// selected, which is almost always a bug. Weights that are not literals,
+/// e.g. variables or function calls, are exempt from this check, they may
+/// still be zero at runtime.
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_weighted_strict_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let coin = branch_weighted_strict_using!( my_rng, {
+///     1 => "heads",
+///     0 => "tails",
+/// });
+/// ```
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_weighted_strict_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let coin = branch_weighted_strict_using!( my_rng, {
+///     1 => "heads",
+///     0x0_u32 => "tails",
+/// });
+/// ```
+///
+/// # Panics
+///
+/// Panics if all weights are zero at runtime.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_weighted_strict_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let edge_weight = 0;
+///
+/// let coin = branch_weighted_strict_using!( my_rng, {
+///     49 => "heads",
+///     49_u32 => "tails",
+///     edge_weight => "edge",
+/// });
+/// assert_ne!(coin, "edge");
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_weighted_strict_using {
+	( @check ) => {};
+	( @check 0 => $branch:expr $( , $( $rest:tt )* )? ) => {
+		compile_error!("A literal weight must not be zero.");
+	};
+	( @check $weight:literal => $branch:expr $( , $( $rest:tt )* )? ) => {
+		const _: () = ::core::assert!($weight != 0, "A literal weight must not be zero.");
+		$crate::branch_weighted_strict_using!(@check $( $( $rest )* )?);
+	};
+	( @check $weight:expr => $branch:expr $( , $( $rest:tt )* )? ) => {
+		$crate::branch_weighted_strict_using!(@check $( $( $rest )* )?);
+	};
+	( $rng:expr, { $( $arms:tt )* }) => {
+		{
+			$crate::branch_weighted_strict_using!(@check $( $arms )*);
+			$crate::branch_weighted_using!($rng, { $( $arms )* })
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, with a probability
+/// proportional to the given weights, rejecting zero weight literals at
+/// compile time.
+///
+/// This macro is to [`branch_weighted_strict_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_weighted_strict;
+///
+/// let coin = branch_weighted_strict!(
+///     49 => "heads",
+///     49 => "tails",
+///     2 => "edge",
+/// );
+/// println!("It's {}", coin);
+/// ```
+///
+/// ```rust,compile_fail
+/// use random_branch::branch_weighted_strict;
+///
+/// let coin = branch_weighted_strict!(
+///     1 => "heads",
+///     0 => "tails",
+/// );
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_weighted_strict {
+	( $( $arms:tt )* ) => {
+		$crate::branch_weighted_strict_using!(
+			$crate::ThreadBranchRng,
+			{ $( $arms )* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, with a
+/// probability proportional to the given literal weights, which must sum to
+/// the given total.
+///
+/// This macro dose essentially the same as [`branch_weighted_using`], but it
+/// fails to compile if the weights don't sum to the total, which catches typos
+/// in probability tables, e.g. percentages that don't add up to `100`. Thus,
+/// all weights must be integer literals, any other weight is rejected at
+/// compile time as well. The total may be any constant expression.
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_weighted_sum_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let coin = branch_weighted_sum_using!( my_rng, 100, {
+///     60 => "heads",
+///     45 => "tails",
+/// });
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_weighted_sum_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let coin = branch_weighted_sum_using!( my_rng, 100, {
+///     49 => "heads",
+///     49 => "tails",
+///     2 => "edge",
+/// });
+/// println!("It's {}", coin);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_weighted_sum_using {
+	( $rng:expr, $total:expr, { $( $weight:literal => $branch:expr ),* $(,)? }) => {
+		{
+			const _: () = ::core::assert!(
+				0 $( + $weight as u64 )* == $total as u64,
+				"The weights don't sum to the stated total.",
+			);
+			$crate::branch_weighted_using!($rng, { $( $weight => $branch ),* })
+		}
+	};
+	( $rng:expr, $total:expr, { $( $arms:tt )* }) => {
+		compile_error!("All weights must be integer literals to check their sum.")
+	};
+}
+
+
+/// Branches into one of the given expressions, with a probability
+/// proportional to the given literal weights, which must sum to the given
+/// total.
+///
+/// This macro is to [`branch_weighted_sum_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_weighted_sum;
+///
+/// let reward = branch_weighted_sum!(100, {
+///     90 => "coin",
+///     9 => "gem",
+///     1 => "crown",
+/// });
+/// println!("You found a {}", reward);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_weighted_sum {
+	( $total:expr, { $( $arms:tt )* }) => {
+		$crate::branch_weighted_sum_using!(
+			$crate::ThreadBranchRng,
+			$total,
+			{ $( $arms )* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, with a
+/// probability proportional to the given weights, or fails if all weights are
+/// zero.
+///
+/// This macro dose essentially the same as [`branch_weighted_using`], but
+/// instead of panicking if all weights are zero, it returns
+/// `Err(BranchError::AllWeightsZero)`, without evaluating any branch.
+/// Otherwise, it returns the value of the selected branch as `Ok`. This makes
+/// weighted branching safe for untrusted weights.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::try_branch_weighted_using;
+/// use random_branch::BranchError;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let weights = [0, 0];
+///
+/// let item = try_branch_weighted_using!( my_rng, {
+///     weights[0] => "first",
+///     weights[1] => "second",
+/// });
+/// assert_eq!(item, Err(BranchError::AllWeightsZero));
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! try_branch_weighted_using {
+	( $rng:expr, { $( $weight:expr => $branch:expr ),* $(,)? }) => {
+		{
+			let weights: &[u32] = &[ $( $crate::branch_internal!(@weight $weight) ),* ];
+			match if $crate::select::DETERMINISTIC {
+				$crate::weighted::first(weights)
+			} else {
+				$crate::weighted::select(weights, &mut $rng)
+			} {
+				::core::result::Result::Ok(selected) => ::core::result::Result::Ok(
+					$crate::branch_internal!(
+						[index] selected,
+						{ $( $branch )* },
+					)
+				),
+				::core::result::Result::Err(err) => ::core::result::Result::Err(
+					$crate::BranchError::from(err)
+				),
+			}
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, with a probability
+/// proportional to the given weights, or fails if all weights are zero.
+///
+/// This macro is to [`try_branch_weighted_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::try_branch_weighted;
+///
+/// fn pick(a: u32, b: u32) -> Result<char, Box<dyn std::error::Error>> {
+///     Ok(try_branch_weighted!(a => 'a', b => 'b')?)
+/// }
+///
+/// assert_eq!(pick(1, 0).unwrap(), 'a');
+/// assert!(pick(0, 0).is_err());
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! try_branch_weighted {
+	( $( $weight:expr => $branch:expr ),* $(,)? ) => {
+		$crate::try_branch_weighted_using!(
+			$crate::ThreadBranchRng,
+			{ $( $weight => $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, retrying
+/// until the value satisfies the given predicate.
+///
+/// This macro repeatedly does the same as [`branch_using`], i.e. it draws a
+/// random branch (with replacement) and evaluates it, until the given
+/// predicate returns `true` for its value, which is then returned as `Some`.
+/// If the predicate rejects `max_attempts` values in a row, `None` is
+/// returned. All rejected values are dropped.
+///
+/// The predicate is called with a reference to each value, and it may borrow
+/// from the surrounding scope. Notice that the branches are evaluated within
+/// a closure, so `return`, `break`, `continue`, and `?` are not available
+/// within them.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_until_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let occupied = vec![(0, 0), (1, 1)];
+///
+/// let position = branch_until_using!( my_rng, 100, |pos| !occupied.contains(pos), {
+///     (0, 0),
+///     (0, 1),
+///     (1, 1),
+/// });
+/// # #[cfg(not(feature = "deterministic"))]
+/// assert_eq!(position, Some((0, 1)));
+///
+/// let position = branch_until_using!( my_rng, 100, |pos| !occupied.contains(pos), {
+///     (0, 0),
+///     (1, 1),
+/// });
+/// assert_eq!(position, None);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_until_using {
+	( $rng:expr, $max_attempts:expr, $predicate:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let rng = &mut $rng;
+			$crate::until::retry(
+				$max_attempts,
+				|| $crate::branch_internal!(
+					[uniform] *rng,
+					{ $( $branch )* },
+				),
+				$predicate,
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, retrying until the value
+/// satisfies the given predicate.
+///
+/// This macro is to [`branch_until_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_until;
+///
+/// let odd = branch_until!(1000, |n| n % 2 == 1, 1, 2, 3, 4);
+/// assert!(odd == Some(1) || odd == Some(3));
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_until {
+	( $max_attempts:expr, $predicate:expr, $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_until_using!(
+			$crate::ThreadBranchRng,
+			$max_attempts,
+			$predicate,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Tries the given fallible expressions in a random order using the given RNG,
+/// until one succeeds.
+///
+/// Each expression must evaluate to a `Result<T, E>`. This macro shuffles the
+/// branches using the given [`Rng`](rand::Rng) and evaluates them one at a
+/// time, i.e. without replacement. The first `Ok` is returned, and the
+/// remaining branches are not evaluated at all. If all branches return an
+/// `Err`, the error of the last evaluated branch is returned.
+///
+/// This is useful e.g. to try several mirrors in a random order.
+///
+/// This works without `std` and without an allocator. Notice that the
+/// branches are evaluated within a loop, so `break` and `continue` are not
+/// available within them.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_fallback_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// fn fetch(mirror: &str) -> Result<String, String> {
+///     if mirror.ends_with(".org") {
+///         Ok(format!("Content from {}", mirror))
+///     } else {
+///         Err(format!("{} is down", mirror))
+///     }
+/// }
+///
+/// let content = branch_fallback_using!( my_rng, {
+///     fetch("mirror.example.com"),
+///     fetch("mirror.example.org"),
+///     fetch("mirror.example.net"),
+/// });
+/// assert_eq!(content, Ok("Content from mirror.example.org".to_string()));
+///
+/// let content = branch_fallback_using!( my_rng, {
+///     fetch("mirror.example.com"),
+///     fetch("mirror.example.net"),
+/// });
+/// assert!(content.is_err());
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_fallback_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let mut order: [usize; $crate::branch_count!({ $( $branch ),* })] =
+				::core::array::from_fn(|index| index);
+			if !$crate::select::DETERMINISTIC {
+				$crate::rand::seq::SliceRandom::shuffle(&mut order[..], &mut $rng);
+			}
+
+			let mut last = ::core::option::Option::None;
+			for index in order {
+				let result = $crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				);
+				let done = ::core::result::Result::is_ok(&result);
+				last = ::core::option::Option::Some(result);
+				if done {
+					break;
+				}
+			}
+			match last {
+				::core::option::Option::Some(result) => result,
+				::core::option::Option::None => unreachable!(),
+			}
+		}
+	};
+}
+
+
+/// Tries the given fallible expressions in a random order, until one
+/// succeeds.
+///
+/// This macro is to [`branch_fallback_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_fallback;
+///
+/// let number: Result<u32, _> = branch_fallback!(
+///     "one".parse(),
+///     "2".parse(),
+///     "three".parse(),
+/// );
+/// assert_eq!(number, Ok(2));
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_fallback {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_fallback_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, drawing
+/// again if the branch declines.
+///
+/// Each expression must evaluate to a [`Pick<T>`](Pick). This macro draws a
+/// random branch, just like [`branch_using`], and evaluates it. If it yields
+/// [`Pick::Take`], its value is returned as `Some`. However, if it yields
+/// [`Pick::Pass`], another branch is drawn uniformly among the remaining
+/// ones, i.e. a branch that passed is never retried within the same
+/// invocation. If all branches pass, `None` is returned. Thus, each branch is
+/// evaluated at most once.
+///
+/// This is useful for branches that can only decide whether they apply after
+/// doing some work.
+///
+/// This works without `std` and without an allocator. Notice that the
+/// branches are evaluated within a loop, so `break` and `continue` are not
+/// available within them.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_retry_using;
+/// use random_branch::Pick;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let outdoors = false;
+///
+/// let event = branch_retry_using!( my_rng, {
+///     if outdoors {
+///         Pick::Take("ambush")
+///     } else {
+///         Pick::Pass
+///     },
+///     Pick::Take("treasure"),
+/// });
+/// assert_eq!(event, Some("treasure"));
+///
+/// let event: Option<&str> = branch_retry_using!( my_rng, {
+///     Pick::Pass,
+///     Pick::Pass,
+/// });
+/// assert_eq!(event, None);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_retry_using {
+	( $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			const COUNT: usize = $crate::branch_count!({ $( $branch ),* });
+			let mut remaining: [usize; COUNT] = ::core::array::from_fn(|index| index);
+			let mut left = COUNT;
+			let mut result = ::core::option::Option::None;
+			while left > 0 {
+				let index = $crate::retry::take_random(&mut remaining, &mut left, &mut $rng);
+				let pick = $crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				);
+				if let $crate::Pick::Take(value) = pick {
+					result = ::core::option::Option::Some(value);
+					break;
+				}
+			}
+			result
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, drawing again if the branch
+/// declines.
+///
+/// This macro is to [`branch_retry_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_retry;
+/// use random_branch::Pick;
+///
+/// let inventory = ["sword"];
+/// let item = branch_retry!(
+///     match inventory.contains(&"shield") {
+///         true => Pick::Take("shield"),
+///         false => Pick::Pass,
+///     },
+///     match inventory.contains(&"sword") {
+///         true => Pick::Take("sword"),
+///         false => Pick::Pass,
+///     },
+/// );
+/// assert_eq!(item, Some("sword"));
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_retry {
+	( $( $branch:expr ),* $(,)? ) => {
+		$crate::branch_retry_using!(
+			$crate::ThreadBranchRng,
+			{ $( $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given labeled expressions using the given RNG,
+/// and returns the label of the chosen one along its value.
+///
+/// This macro dose essentially the same as [`branch_using`], but each branch
+/// is preceded by a string literal label and a `=>`. The macro evaluates to a
+/// tuple of the label of the chosen branch and the value of its expression,
+/// i.e. `(&'static str, T)`. This is useful to log or record which branch was
+/// taken in a human readable form.
+///
+/// The labels must be string literals, anything else is rejected at compile
+/// time:
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_named_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let (label, ()) = branch_named_using!( my_rng, {
+///     1 => (),
+///     2 => (),
+/// });
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_named_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let (label, (dx, dy)) = branch_named_using!( my_rng, {
+///     "north" => (0, 1),
+///     "south" => (0, -1),
+///     "east" => (1, 0),
+///     "west" => (-1, 0),
+/// });
+/// println!("Going {}", label);
+/// match label {
+///     "north" => assert_eq!((dx, dy), (0, 1)),
+///     "south" => assert_eq!((dx, dy), (0, -1)),
+///     "east" => assert_eq!((dx, dy), (1, 0)),
+///     "west" => assert_eq!((dx, dy), (-1, 0)),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_named_using {
+	( $rng:expr, { $( $label:literal => $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[uniform] $rng,
+				{ $( {
+					{
+						let label: &'static str = $label;
+						(label, $branch)
+					}
+				} )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given labeled expressions, and returns the label
+/// of the chosen one along its value.
+///
+/// This macro is to [`branch_named_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_named;
+///
+/// let (label, value) = branch_named!(
+///     "small" => 1,
+///     "large" => 1000,
+/// );
+/// assert_eq!(value, if label == "small" { 1 } else { 1000 });
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_named {
+	( $( $label:literal => $branch:expr ),* $(,)? ) => {
+		$crate::branch_named_using!(
+			$crate::ThreadBranchRng,
+			{ $( $label => $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions using the given RNG, spreading
+/// successive selections evenly according to the given state.
+///
+/// This macro dose essentially the same as [`branch_using`], but the
+/// selection is stratified over a stream of invocations by the given
+/// [`StratifiedPicker`], so that the frequency of each branch closely tracks
+/// its expected share, instead of clumping as independent draws would. Notice
+/// that this means that successive invocations are not independent of each
+/// other. See [`StratifiedPicker`] for the details.
+///
+/// Optionally, each branch may be preceded by a `u32` weight and a `=>`,
+/// just like in [`branch_weighted_using`], to give the branches
+/// proportional shares instead of equal ones.
+///
+/// The state must be stored by the caller, e.g. in some `struct`. It
+/// should only be used with the same list of branches, since it only stores a
+/// credit per index.
+///
+/// # Panics
+///
+/// Panics if all weights are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_stratified_using;
+/// use random_branch::StratifiedPicker;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut state = StratifiedPicker::<3>::new();
+///
+/// let mut counts = [0; 3];
+/// for _ in 0..30 {
+///     let index = branch_stratified_using!( state, my_rng, {
+///         0,
+///         1,
+///         2,
+///     });
+///     counts[index] += 1;
+/// }
+/// assert_eq!(counts, [10, 10, 10]);
+/// ```
+///
+/// With weights:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_stratified_using;
+/// use random_branch::StratifiedPicker;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let mut state = StratifiedPicker::<2>::new();
+///
+/// let mut hits = 0;
+/// for _ in 0..100 {
+///     hits += branch_stratified_using!( state, my_rng, {
+///         1 => 1,
+///         3 => 0,
+///     });
+/// }
+/// assert_eq!(hits, 25);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_stratified_using {
+	( $state:expr, $rng:expr, { $( $weight:expr => $branch:expr ),+ $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[index] $crate::StratifiedPicker::<
+					{ $crate::branch_internal!(@count { $( $branch )* }) },
+				>::select_weighted(&mut $state, &[ $( $crate::branch_internal!(@weight $weight) ),* ], &mut $rng),
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $state:expr, $rng:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			$crate::branch_internal!(
+				[index] $crate::StratifiedPicker::<
+					{ $crate::branch_internal!(@count { $( $branch )* }) },
+				>::select(&mut $state, &mut $rng),
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Generates a random sentence from the given phrase lists using the given
+/// RNG.
+///
+/// Each braced group is a list of alternative phrases, and for each group one
+/// phrase is selected randomly and independently, just like with
+/// [`branch_using`]. Optionally, the phrases of a group may be preceded by a
+/// `u32` weight and a `=>`, just like in [`branch_weighted_using`]. The
+/// selected phrases are joined by a space into a `String`.
+///
+/// The phrases can be any expressions implementing `AsRef<str>`, as long as
+/// all phrases of a group have the same type, so they can also be nested
+/// invocations of this macro. For text whose structure is only
+/// known at runtime, see [`text::Grammar`](crate::text::Grammar).
+///
+/// This macro turns something like this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::random_sentence_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let sentence = random_sentence_using!( my_rng,
+///     { 3 => "The", 1 => "A" }
+///     { "cat", "dog" }
+///     { "sleeps.", "runs." }
+/// );
+/// ```
+///
+/// into something similar to this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+/// use random_branch::branch_weighted_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let mut sentence = String::new();
+/// sentence.push_str(branch_weighted_using!(my_rng, { 3 => "The", 1 => "A" }));
+/// sentence.push(' ');
+/// sentence.push_str(branch_using!(my_rng, { "cat", "dog" }));
+/// sentence.push(' ');
+/// sentence.push_str(branch_using!(my_rng, { "sleeps.", "runs." }));
+/// ```
+///
+/// # Panics
+///
+/// Panics if all weights of a group are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::random_sentence_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let sentence = random_sentence_using!( my_rng,
+///     { "I", "You", "We" }
+///     { 5 => "like", 1 => "love" }
+///     {
+///         String::from("cake"),
+///         random_sentence_using!( my_rng, { "sweet", "sour" } { "candy" }),
+///     }
+/// );
+/// println!("{}", sentence);
+/// assert!(sentence.split(' ').count() >= 3);
+/// ```
+#[macro_export]
+#[cfg(feature = "text")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "text")))]
+macro_rules! random_sentence_using {
+	( @phrase $rng:expr, { $( $weight:expr => $phrase:expr ),+ $(,)? }) => {
+		$crate::branch_weighted_using!($rng, { $( $weight => $phrase ),+ })
+	};
+	( @phrase $rng:expr, { $( $phrase:expr ),+ $(,)? }) => {
+		$crate::branch_using!($rng, { $( $phrase ),+ })
+	};
+	( $rng:expr, $( { $( $group:tt )* } )+ ) => {
+		{
+			let mut sentence = $crate::alloc::string::String::new();
+			$(
+				if !sentence.is_empty() {
+					sentence.push(' ');
+				}
+				sentence.push_str(::core::convert::AsRef::<str>::as_ref(
+					&$crate::random_sentence_using!(@phrase $rng, { $( $group )* })
+				));
+			)+
+			sentence
+		}
+	};
+}
+
+
+/// Generates a random sentence from the given phrase lists.
+///
+/// This macro is to [`random_sentence_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::random_sentence;
+///
+/// let sentence = random_sentence!(
+///     { "Good", "Nice" }
+///     { 9 => "morning", 1 => "evening" }
+/// );
+/// println!("{}", sentence);
+/// ```
+#[macro_export]
+#[cfg(all(feature = "text", feature = "std"))]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(all(feature = "text", feature = "std"))))]
+macro_rules! random_sentence {
+	( $( { $( $group:tt )* } )+ ) => {
+		$crate::random_sentence_using!(
+			$crate::ThreadBranchRng,
+			$( { $( $group )* } )+
+		)
+	};
+}
+
+
+/// Creates a `Vec` by branching into the given expressions with exact
+/// proportions, in a random order, using the given RNG.
+///
+/// This macro evaluates exactly `n` branches and collects their values into a
+/// `Vec`, similar to [`branch_vec_using`]. However, instead of selecting each
+/// branch independently, each branch is evaluated a fixed number of times,
+/// proportional to its `u32` weight, and only the order is random. So e.g.
+/// with the weights `5`, `3` and `2` and an `n` of `100`, the branches are
+/// evaluated exactly `50`, `30` and `20` times.
+///
+/// If the weights don't divide `n` evenly, the counts are rounded with the
+/// largest remainder method: every branch is evaluated the integer part of its
+/// exact share times, and the remaining evaluations go to the branches with
+/// the largest fractional parts, preferring the earlier branches on ties.
+/// Thus, every count differs by less than one from its exact share, and the
+/// counts always add up to `n`.
+///
+/// # Panics
+///
+/// Panics if all weights are zero, unless `n` is zero.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_balanced_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let variants = branch_balanced_using!( my_rng, 100, {
+///     5 => 'a',
+///     3 => 'b',
+///     2 => 'c',
+/// });
+/// assert_eq!(variants.len(), 100);
+/// assert_eq!(variants.iter().filter(|&&v| v == 'a').count(), 50);
+/// assert_eq!(variants.iter().filter(|&&v| v == 'b').count(), 30);
+/// assert_eq!(variants.iter().filter(|&&v| v == 'c').count(), 20);
+/// ```
+#[macro_export]
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "alloc")))]
+macro_rules! branch_balanced_using {
+	( $rng:expr, $n:expr, { $( $weight:expr => $branch:expr ),* $(,)? }) => {
+		{
+			let order = $crate::balanced::shuffled(&[ $( $crate::branch_internal!(@weight $weight) ),* ], $n, &mut $rng);
+			let mut vec = $crate::alloc::vec::Vec::with_capacity(order.len());
+			for index in order {
+				vec.push($crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				));
+			}
+			vec
+		}
+	};
+}
+
+
+/// Creates a `Vec` by branching into the given expressions with exact
+/// proportions, in a random order.
+///
+/// This macro is to [`branch_balanced_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_balanced;
+///
+/// let groups = branch_balanced!(10,
+///     1 => "control",
+///     1 => "treatment",
+/// );
+/// assert_eq!(groups.iter().filter(|&&g| g == "control").count(), 5);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_balanced {
+	( $n:expr, $( $weight:expr => $branch:expr ),* $(,)? ) => {
+		$crate::branch_balanced_using!(
+			$crate::ThreadBranchRng,
+			$n,
+			{ $( $weight => $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions for each item of the given
+/// iterable, using the given RNG.
+///
+/// This macro iterates over `items`, binds each item to the given pattern,
+/// and then executes one of the given expressions, which can use the bound
+/// item, just like [`branch_using`]. The branch is selected independently for
+/// each item. The values of the branches are discarded, so they may even
+/// have different types.
+///
+/// The RNG is borrowed by the macro for the whole loop, and the branches are
+/// evaluated in the loop body, not in a closure, so they can freely borrow
+/// other variables and use `break`, `continue`, `return` or `?`.
+///
+/// This macro turns something like this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_for_each_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let names = ["Alice", "Bob"];
+///
+/// branch_for_each_using!( my_rng, names, |name| {
+///     println!("Hello, {}!", name),
+///     println!("Hi, {}!", name),
+/// });
+/// ```
+///
+/// into something similar to this:
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let names = ["Alice", "Bob"];
+///
+/// for name in names {
+///     branch_using!( my_rng, {
+///         println!("Hello, {}!", name),
+///         println!("Hi, {}!", name),
+///     });
+/// }
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_for_each_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let words = vec![String::from("tree"), String::from("house")];
+/// let mut text = String::new();
+///
+/// branch_for_each_using!( my_rng, &words, |word| {
+///     text.push_str(word),
+///     text.push_str(&word.to_uppercase()),
+/// });
+/// assert_eq!(text.to_lowercase(), "treehouse");
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_for_each_using {
+	( $rng:expr, $items:expr, | $item:pat_param | { $( $branch:expr ),* $(,)? }) => {
+		{
+			let rng = &mut $rng;
+			for $item in $items {
+				$crate::branch_internal!(
+					[uniform] *rng,
+					{ $( { let _ = $branch; } )* },
+				)
+			}
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions for each item of the given
+/// iterable.
+///
+/// This macro is to [`branch_for_each_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_for_each;
+///
+/// let mut sum = 0;
+/// branch_for_each!(1..=3, |x| {
+///     sum += x,
+///     sum += 10 * x,
+/// });
+/// assert_eq!(sum % 10 + sum / 10 % 10, 6);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_for_each {
+	( $items:expr, | $item:pat_param | { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_for_each_using!(
+			$crate::ThreadBranchRng,
+			$items,
+			| $item | { $( $branch ),* }
+		)
+	};
+}
+
+
+/// Lazily maps each item of the given iterable by branching into one of the
+/// given expressions, using the given RNG.
+///
+/// This macro is the collecting twin of [`branch_for_each_using`]: it yields
+/// an iterator, which binds each item to the given pattern, and then yields
+/// the value of one of the given expressions, which can use the bound item,
+/// just like [`branch_using`]. The branch is selected independently for each
+/// item.
+///
+/// The iterator is lazy, so the branches are only evaluated when it is
+/// advanced, e.g. by `collect`. Since the branches are evaluated within a
+/// closure, which mutably borrows the RNG until the iterator is dropped,
+/// `return` and `?` are not available within them.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_map_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// let numbers = [1, 2, 3, 4];
+///
+/// let mapped: Vec<i32> = branch_map_using!( my_rng, numbers, |n| {
+///     n,
+///     -n,
+/// }).collect();
+/// assert_eq!(mapped.iter().map(|n| n.abs()).collect::<Vec<_>>(), numbers);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_map_using {
+	( $rng:expr, $items:expr, | $item:pat_param | { $( $branch:expr ),* $(,)? }) => {
+		::core::iter::Iterator::map(
+			::core::iter::IntoIterator::into_iter($items),
+			|$item| $crate::branch_internal!(
+				[uniform] $rng,
+				{ $( $branch )* },
+			),
+		)
+	};
+}
+
+
+/// Lazily maps each item of the given iterable by branching into one of the
+/// given expressions.
+///
+/// This macro is to [`branch_map_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_map;
+///
+/// let greetings: Vec<String> = branch_map!(["Alice", "Bob"], |name| {
+///     format!("Hello, {}!", name),
+///     format!("Hi, {}!", name),
+/// }).collect();
+/// assert_eq!(greetings.len(), 2);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_map {
+	( $items:expr, | $item:pat_param | { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_map_using!(
+			$crate::ThreadBranchRng,
+			$items,
+			| $item | { $( $branch ),* }
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions, deterministically selected by
+/// the given seed.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// taking an RNG, it creates a [`SeededRng`] from the given `u64` seed, and
+/// selects the branch via [`SeededRng::select`]. So the same seed and the
+/// same number of branches always select the same branch, which is stable
+/// across releases and platforms, see [`SeededRng`] for the details. This is
+/// useful e.g. for reproducible examples and docs.
+///
+/// To draw a reproducible sequence of branches from a single seed, use
+/// [`branch_seeded_with`] instead.
+///
+/// This macro works without `std`, but requires the `pcg` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_seeded;
+///
+/// let choice = branch_seeded!(42, {
+///     "rock",
+///     "paper",
+///     "scissors",
+/// });
+/// assert_eq!(choice, "scissors");
+/// ```
+#[macro_export]
+#[cfg(feature = "pcg")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "pcg")))]
+macro_rules! branch_seeded {
+	( $seed:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_seeded_with!($crate::SeededRng::new($seed), { $( $branch ),* })
+	};
+}
+
+
+/// Branches into one of the given expressions, deterministically selected by
+/// the given [`SeededRng`] state.
+///
+/// This macro is the state variant of [`branch_seeded`]: it selects the
+/// branch via [`SeededRng::select`] of the given state, which advances it.
+/// Thus, a loop draws a sequence of branches, which is fully determined by
+/// the seed of the state, and stable across releases and platforms.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_seeded_with;
+/// use random_branch::SeededRng;
+///
+/// let mut state = SeededRng::new(42);
+/// let mut moves = [""; 5];
+/// for m in &mut moves {
+///     *m = branch_seeded_with!(state, {
+///         "rock",
+///         "paper",
+///         "scissors",
+///     });
+/// }
+/// assert_eq!(moves, ["scissors", "scissors", "paper", "rock", "rock"]);
+/// ```
+#[macro_export]
+#[cfg(feature = "pcg")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "pcg")))]
+macro_rules! branch_seeded_with {
+	( $state:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_internal!(
+			[index] $crate::SeededRng::select(
+				&mut $state,
+				$crate::branch_internal!(@count { $( $branch )* }),
+			),
+			{ $( $branch )* },
+		)
+	};
+}
+
+
+/// Branches into one of the expressions of each of the given groups, using a
+/// single draw of the given RNG.
+///
+/// This macro dose essentially the same as calling [`branch_using`] once per
+/// group, and yields a tuple of the values of the selected branches, one per
+/// group. However, instead of drawing a random number per group, it draws a
+/// single `u64`, selects one of all combinations uniformly from it, and then
+/// splits that into the branch index of each group. This reduces the number
+/// of RNG calls in tight loops. The selections of the groups are still
+/// uniform and independent of each other.
+///
+/// In order to be unbiased, the selection occasionally needs to draw another
+/// `u64`, but this is very rare unless the number of combinations is close to
+/// `2^64`.
+///
+/// The number of combinations, i.e. the product of the number of branches of
+/// all groups, must fit into 64 bits, otherwise it fails to compile:
+///
+/// ```rust,compile_fail
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_batch_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// // 2^65 combinations
+/// let bits = branch_batch_using!( my_rng,
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 }, { 0, 1 },
+///     { 0, 1 }, { 0, 1 },
+/// );
+/// ```
+///
+/// The groups are evaluated in order.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_batch_using;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let (suit, rank) = branch_batch_using!( my_rng,
+///     { '♠', '♥', '♦', '♣' },
+///     { "A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K" },
+/// );
+/// println!("{}{}", rank, suit);
+/// ```
+#[macro_export]
+#[cfg(feature = "rand")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "rand")))]
+macro_rules! branch_batch_using {
+	( $rng:expr, $( { $( $branch:expr ),* $(,)? } ),+ $(,)? ) => {
+		{
+			const COUNTS: [usize; $crate::branch_internal!(@count { $( [ $( $branch )* ] )+ })] = [
+				$( $crate::branch_internal!(@count { $( $branch )* }) ),+
+			];
+			const TOTAL: u64 = $crate::batch::total(&COUNTS);
+			let mut indices = ::core::iter::IntoIterator::into_iter(
+				$crate::batch::select(&mut $rng, TOTAL, &COUNTS)
+			);
+			( $(
+				$crate::branch_internal!(
+					[index] ::core::option::Option::unwrap(
+						::core::iter::Iterator::next(&mut indices)
+					),
+					{ $( $branch )* },
+				),
+			)+ )
+		}
+	};
+}
+
+
+/// Branches into one of the expressions of each of the given groups, using a
+/// single draw.
+///
+/// This macro is to [`branch_batch_using`] what [`branch`] is to
+/// [`branch_using`], it uses the [`ThreadBranchRng`] and thus requires
+/// `std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_batch;
+///
+/// let (x, y, z) = branch_batch!({ -1, 1 }, { -1, 1 }, { -1, 1 });
+/// assert_eq!((x * y * z as i32).abs(), 1);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_batch {
+	( $( { $( $branch:expr ),* $(,)? } ),+ $(,)? ) => {
+		$crate::branch_batch_using!(
+			$crate::ThreadBranchRng,
+			$( { $( $branch ),* } ),+
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions by the given index, without any
+/// RNG.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// drawing the branch index from an RNG, it takes it from the given `usize`
+/// expression, e.g. a hash, a counter, or some fuzzer input. The index is
+/// reduced modulo the number of branches, so any index selects a branch, e.g.
+/// with three branches, the index `4` selects the second branch. Only the
+/// selected branch is evaluated.
+///
+/// See [`branch_on_checked`] for a variant that asserts that the index is in
+/// range instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_on;
+///
+/// let names: Vec<&str> = (0..5)
+///     .map(|i| branch_on!(i, { "zero", "one", "two" }))
+///     .collect();
+/// assert_eq!(names, ["zero", "one", "two", "zero", "one"]);
+/// ```
+#[macro_export]
+macro_rules! branch_on {
+	( $index:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let index: usize = $index;
+			$crate::branch_internal!(
+				[index] index % $crate::branch_internal!(@count { $( $branch )* }),
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions by the given index, which must
+/// be in range.
+///
+/// This macro dose essentially the same as [`branch_on`], but instead of
+/// reducing the index modulo the number of branches, it panics if the index
+/// is out of range.
+///
+/// # Panics
+///
+/// Panics if the index is not less than the number of branches.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_on_checked;
+///
+/// assert_eq!(branch_on_checked!(2, { "zero", "one", "two" }), "two");
+/// ```
+///
+/// ```rust,should_panic
+/// use random_branch::branch_on_checked;
+///
+/// branch_on_checked!(3, { "zero", "one", "two" });
+/// ```
+#[macro_export]
+macro_rules! branch_on_checked {
+	( $index:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let index: usize = $index;
+			let count = $crate::branch_internal!(@count { $( $branch )* });
+			::core::assert!(
+				index < count,
+				"The branch index {} is out of range for {} branches",
+				index,
+				count,
+			);
+			$crate::branch_internal!(
+				[index] index,
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions by the given index, or fails if
+/// it is out of range.
+///
+/// This macro dose essentially the same as [`branch_on_checked`], but instead
+/// of panicking, it returns `Err(BranchError::IndexOutOfRange)` if the index
+/// is not less than the number of branches, without evaluating any branch.
+/// Otherwise, it returns the value of the selected branch as `Ok`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::try_branch_on;
+/// use random_branch::BranchError;
+///
+/// assert_eq!(try_branch_on!(1, { "zero", "one" }), Ok("one"));
+/// assert_eq!(try_branch_on!(2, { "zero", "one" }), Err(BranchError::IndexOutOfRange));
+/// ```
+#[macro_export]
+macro_rules! try_branch_on {
+	( $index:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let index: usize = $index;
+			if index < $crate::branch_internal!(@count { $( $branch )* }) {
+				::core::result::Result::Ok(
+					$crate::branch_internal!(
+						[index] index,
+						{ $( $branch )* },
+					)
+				)
+			} else {
+				::core::result::Result::Err($crate::BranchError::IndexOutOfRange)
+			}
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, deterministically selected by
+/// the hash of the given key.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// drawing from an RNG, the branch is selected by the hash of the given key,
+/// so the same key always selects the same branch. Different keys are spread
+/// uniformly over the branches, e.g. to assign users to the groups of an A/B
+/// test by their id. Optionally, each branch may be preceded by a `u32`
+/// weight and a `=>`, just like in [`branch_weighted_using`], to spread the
+/// keys proportionally to the weights instead.
+///
+/// The key can be any [`StableKey`], such as strings, byte slices and
+/// integers, or references to them. It is hashed with [`key_hash`], which is
+/// stable across versions of this crate, versions of Rust, and platforms, so
+/// assignments never change silently. The branch index is the upper 64 bits
+/// of the 128-bit product of the hash and the number of branches, or with
+/// weights, of their total, which is then mapped to the branch whose
+/// cumulative weights cover it.
+///
+/// # Panics
+///
+/// Panics if all weights are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_keyed;
+///
+/// let group = branch_keyed!("user-42", {
+///     "control",
+///     "variant a",
+///     "variant b",
+/// });
+/// assert_eq!(group, "variant a");
+///
+/// // The same key always gets the same group
+/// for _ in 0..10 {
+///     assert_eq!(branch_keyed!("user-42", { "control", "variant a", "variant b" }), group);
+/// }
+///
+/// // With weights, 90% of the keys are in the control group
+/// let user_id: u64 = 1234;
+/// let group = branch_keyed!(user_id, {
+///     90 => "control",
+///     10 => "variant",
+/// });
+/// ```
+#[macro_export]
+macro_rules! branch_keyed {
+	( $key:expr, { $( $weight:expr => $branch:expr ),+ $(,)? }) => {
+		$crate::branch_internal!(
+			[index] match $crate::keyed::weighted_index(&$key, &[ $( $crate::branch_internal!(@weight $weight) ),* ]) {
+				::core::result::Result::Ok(index) => index,
+				::core::result::Result::Err(_) => $crate::weighted::all_weights_zero(),
+			},
+			{ $( $branch )* },
+		)
+	};
+	( $key:expr, { $( $branch:expr ),* $(,)? }) => {
+		$crate::branch_internal!(
+			[index] $crate::keyed::index(
+				&$key,
+				$crate::branch_internal!(@count { $( $branch )* }),
+			),
+			{ $( $branch )* },
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions, selected by the bytes of the
+/// given cursor.
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// drawing the branch index from an RNG, it takes it from the given
+/// [`ByteCursor`] (or a `&mut ByteCursor`), e.g. wrapping the input of a
+/// fuzzer. This way, the fuzzer decides which branches are taken, so its
+/// coverage feedback can steer the exploration of the branches.
+///
+/// Each invocation consumes the minimal number of bytes needed for its
+/// number of branches, see [`ByteCursor`] for the exact mapping. Once the
+/// bytes run out, the first branch is selected. The cursor is only borrowed
+/// for the selection itself, so the branches may use it too, e.g. for nested
+/// invocations.
+///
+/// This macro does not use any RNG and thus works in `no_std`.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_from_bytes;
+/// use random_branch::ByteCursor;
+///
+/// fn generate(cursor: &mut ByteCursor) -> String {
+///     branch_from_bytes!(cursor, {
+///         String::from("x"),
+///         format!("({} + {})", generate(cursor), generate(cursor)),
+///         format!("-{}", generate(cursor)),
+///     })
+/// }
+///
+/// // E.g. in a fuzz target: `fuzz_target!(|data: &[u8]| { .. })`
+/// let data = [1, 2, 0, 0];
+/// assert_eq!(generate(&mut ByteCursor::new(&data)), "(-x + x)");
+/// ```
+#[macro_export]
+macro_rules! branch_from_bytes {
+	( $cursor:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let index: usize = $cursor.select(
+				$crate::branch_internal!(@count { $( $branch )* }),
+			);
+			$crate::branch_internal!(
+				[index] index,
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the operating system's
+/// cryptographically secure RNG.
+///
+/// This macro dose essentially the same as [`branch`], but instead of the
+/// thread-local RNG, it always draws from [`rand::rngs::OsRng`], which is
+/// backed by the operating system's CSPRNG (e.g. `getrandom` on Linux). Use it
+/// for security-sensitive decisions, e.g. randomized defenses, where the
+/// branch must not be predictable. It also signals this intent to readers.
+///
+/// Unlike [`branch`], this macro ignores any RNG set via
+/// [`set_thread_branch_rng`](crate::set_thread_branch_rng) and the `env-seed`
+/// feature, since those are meant for reproducible tests, which would
+/// defeat the purpose of this macro. For the same reason, it bypasses all
+/// hooks of the other macros: the `deterministic` feature, forced branches
+/// and the exploration of the `test-override` feature, and the replay and
+/// recording of the `recorder` feature. The only hook that applies is
+#[cfg_attr(feature = "test-override", doc = "[`with_secure_rng`](crate::test_override::with_secure_rng),")]
+#[cfg_attr(not(feature = "test-override"), doc = "`with_secure_rng`,")]
+/// which replaces the OS RNG on the current thread.
+///
+/// # Performance
+///
+/// Each invocation issues a system call to get fresh randomness, which costs
+/// in the order of a few hundred nanoseconds, while [`branch`] just takes a
+/// few nanoseconds from a user-space generator. So, only use this macro where
+/// the quality of the randomness actually matters. In turn, no state is kept
+/// in user space between invocations: each one fetches a fresh `u64` (rarely
+/// more, to reject values that would bias the selection), which is not kept
+/// beyond the selection.
+///
+/// # Panics
+///
+/// Panics if the operating system fails to provide random bytes, see
+/// [`try_branch_secure`] for a fallible alternative.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_secure;
+///
+/// let delay_ms = branch_secure!(0, 10, 20, 30);
+/// assert!(delay_ms <= 30);
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! branch_secure {
+	( $( $branch:expr ),* $(,)? ) => {
+		{
+			$crate::branch_internal!(
+				[secure] (),
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions using the operating system's
+/// cryptographically secure RNG, or fails if it is unavailable.
+///
+/// This macro dose essentially the same as [`branch_secure`], but instead of
+/// panicking, it evaluates to a [`rand::Error`] if the operating system fails
+/// to provide random bytes, e.g. on platforms without a usable entropy source
+/// or if it is not seeded yet, without evaluating any branch. Otherwise, it
+/// evaluates to `Ok` with the value of the selected branch. The same
+/// performance considerations apply.
+///
+/// With the `test-override` feature, the source can be replaced via
+#[cfg_attr(feature = "test-override", doc = "[`with_secure_rng`](crate::test_override::with_secure_rng),")]
+#[cfg_attr(not(feature = "test-override"), doc = "`with_secure_rng`,")]
+/// e.g. by a failing RNG to test the error path.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::try_branch_secure;
+///
+/// fn decoy() -> Result<&'static str, rand::Error> {
+///     try_branch_secure!("real", "decoy A", "decoy B")
+/// }
+/// # assert!(decoy().is_ok());
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! try_branch_secure {
+	( $( $branch:expr ),* $(,)? ) => {
+		{
+			match $crate::secure::try_select($crate::branch_count!({ $( $branch ),* })) {
+				::core::result::Result::Ok(selected) => ::core::result::Result::Ok(
+					$crate::branch_internal!(
+						[index] selected,
+						{ $( $branch )* },
+					)
+				),
+				::core::result::Result::Err(err) => ::core::result::Result::Err::<_, $crate::rand::Error>(err),
+			}
+		}
+	};
+}
+
+
+/// Branches into one of the given expressions, selected by the fuzzer input
+/// of an [`arbitrary::Unstructured`].
+///
+/// This macro dose essentially the same as [`branch_using`], but instead of
+/// drawing the branch index from an RNG, it takes it from the given
+/// `&mut Unstructured` via [`Unstructured::int_in_range`], so it fits into
+/// structure-aware fuzzing, e.g. within implementations of
+/// [`arbitrary::Arbitrary`]. The same input always selects the same
+/// branches.
+///
+/// The whole invocation evaluates to an [`arbitrary::Result`] of the value of
+/// the selected branch, so it is usually followed by `?`. It is an error if
+/// the input is already exhausted, so that e.g. recursive generators stop
+/// cleanly, instead of always taking the first branch like `int_in_range`
+/// does. A branch itself may use `?` too, since the branches are not wrapped
+/// in a closure.
+///
+/// This macro requires the `arbitrary` feature.
+///
+/// [`Unstructured::int_in_range`]: arbitrary::Unstructured::int_in_range
+///
+/// # Examples
+///
+/// ```rust
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use random_branch::branch_arbitrary;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Op {
+///     Add(u8),
+///     Del,
+///     Query,
+/// }
+///
+/// impl<'a> Arbitrary<'a> for Op {
+///     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+///         branch_arbitrary!(u, {
+///             Op::Add(u.arbitrary()?),
+///             Op::Del,
+///             Op::Query,
+///         })
+///     }
+/// }
+///
+/// let mut u = Unstructured::new(&[0, 42, 2]);
+/// assert_eq!(Op::arbitrary(&mut u), Ok(Op::Add(42)));
+/// assert_eq!(Op::arbitrary(&mut u), Ok(Op::Query));
+/// assert!(Op::arbitrary(&mut u).is_err());
+/// ```
+#[macro_export]
+#[cfg(feature = "arbitrary")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "arbitrary")))]
+macro_rules! branch_arbitrary {
+	( $u:expr, { $( $branch:expr ),* $(,)? }) => {
+		match $crate::unstructured::select(
+			$u,
+			$crate::branch_internal!(@count { $( $branch )* }),
+		) {
+			::core::result::Result::Ok(index) => ::core::result::Result::Ok(
+				$crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				)
+			),
+			::core::result::Result::Err(error) => ::core::result::Result::Err(error),
+		}
+	};
+}
+
+
+/// Creates a `proptest` strategy, which yields one of the given expressions.
+///
+/// This macro turns the same list of branches as [`branch_using`] into an
+/// `impl Strategy<Value = T>` of the [`proptest`] crate, so that a property
+/// test can explore the same branches as the code under test, instead of
+/// keeping a `prop_oneof!` mirror in sync by hand.
+///
+/// Each generated value selects a branch uniformly and evaluates it, so the
+/// branches are evaluated once per generated value, and they must not move
+/// out of captured variables. When a property fails, the strategy shrinks
+/// toward earlier branches, so the first branch is the simplest value.
+///
+/// This macro requires the `proptest` feature.
+///
+/// [`proptest`]: https://docs.rs/proptest
+///
+/// # Composing Strategies
+///
+/// The branches are plain values, not strategies. To generate values within
+/// a branch, let each branch yield a boxed strategy, and flatten the result
+/// via `prop_flat_map`. This way, a failing case first shrinks within the
+/// selected branch and then toward earlier branches:
+///
+/// ```rust
+/// use proptest::prelude::*;
+/// use random_branch::branch_strategy;
+///
+/// #[derive(Debug, Clone)]
+/// enum Op {
+///     Read(u32),
+///     Write(u32),
+///     Flush,
+/// }
+///
+/// fn op() -> impl Strategy<Value = Op> {
+///     branch_strategy!({
+///         any::<u32>().prop_map(Op::Read).boxed(),
+///         any::<u32>().prop_map(Op::Write).boxed(),
+///         Just(Op::Flush).boxed(),
+///     })
+///     .prop_flat_map(|op| op)
+/// }
+///
+/// proptest!(|(ops in prop::collection::vec(op(), 0..10))| {
+///     prop_assert!(ops.len() < 10);
+/// });
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// use proptest::prelude::*;
+/// use random_branch::branch_strategy;
+///
+/// proptest!(|(name in branch_strategy!({ "alpha", "beta", "gamma" }))| {
+///     prop_assert!(name.len() >= 4);
+/// });
+/// ```
+#[macro_export]
+#[cfg(feature = "proptest")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "proptest")))]
+macro_rules! branch_strategy {
+	({ $( $branch:expr ),* $(,)? }) => {
+		$crate::proptest::strategy::Strategy::prop_map(
+			0..$crate::branch_internal!(@count { $( $branch )* }),
+			move |index: usize| {
+				$crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				)
+			},
+		)
+	};
+}
+
+
+/// Branches into one of the given expressions, selected by a `quickcheck`
+/// generator.
+///
+/// This macro dose essentially the same as [`branch_using`] and
+/// [`branch_weighted_using`], but instead of an RNG, it takes a
+/// `&mut quickcheck::Gen`, which isn't a [`rand::Rng`] since `quickcheck`
+/// 1.0, so it can be used within implementations of `Arbitrary::arbitrary`.
+/// The branch is selected via `Gen::choose`, either uniformly, or with a
+/// probability proportional to the given weights.
+///
+/// For recursive structures, a depth hint can be given via `depth = ..`: once
+/// the depth reaches the generator's `size()`, the first branch is always
+/// selected, so it should be a non-recursive one, which keeps the generated
+/// structures bounded. A weighted branch is only selected if its weight is
+/// non-zero, even then.
+///
+/// This macro requires the `quickcheck` feature.
+///
+/// # Panics
+///
+/// Panics if weights are given and all of them are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use quickcheck::{Arbitrary, Gen};
+/// use random_branch::branch_qc;
+///
+/// #[derive(Debug, Clone)]
+/// enum Tree {
+///     Leaf(u8),
+///     Node(Box<Tree>, Box<Tree>),
+/// }
+///
+/// fn tree(g: &mut Gen, depth: usize) -> Tree {
+///     branch_qc!(g, depth = depth, {
+///         Tree::Leaf(u8::arbitrary(g)),
+///         Tree::Node(Box::new(tree(g, depth + 1)), Box::new(tree(g, depth + 1))),
+///     })
+/// }
+///
+/// impl Arbitrary for Tree {
+///     fn arbitrary(g: &mut Gen) -> Self {
+///         tree(g, 0)
+///     }
+/// }
+///
+/// let coin = branch_qc!(&mut Gen::new(10), { 9 => "heads", 1 => "tails" });
+/// # assert!(coin == "heads" || coin == "tails");
+/// let _ = Tree::arbitrary(&mut Gen::new(5));
+/// ```
+#[macro_export]
+#[cfg(feature = "quickcheck")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "quickcheck")))]
+macro_rules! branch_qc {
+	( $g:expr, depth = $depth:expr, { $first_weight:expr => $first:expr $( , $weight:expr => $branch:expr )* $(,)? }) => {
+		{
+			let g: &mut $crate::qc::Gen = $g;
+			let weights = [
+				$crate::branch_internal!(@weight $first_weight)
+				$( , $crate::branch_internal!(@weight $weight) )*
+			];
+			let selected = if $crate::qc::exhausted(g, $depth) && weights[0] > 0 {
+				0
+			} else {
+				$crate::qc::select_weighted(g, &weights)
+			};
+			$crate::branch_internal!(
+				[index] selected,
+				{ $first $( $branch )* },
+			)
+		}
+	};
+	( $g:expr, depth = $depth:expr, { $first:expr $( , $branch:expr )* $(,)? }) => {
+		{
+			let g: &mut $crate::qc::Gen = $g;
+			let selected = if $crate::qc::exhausted(g, $depth) {
+				0
+			} else {
+				$crate::qc::select(g, $crate::branch_internal!(@count { $first $( $branch )* }))
+			};
+			$crate::branch_internal!(
+				[index] selected,
+				{ $first $( $branch )* },
+			)
+		}
+	};
+	( $g:expr, { $( $weight:expr => $branch:expr ),* $(,)? }) => {
+		{
+			let selected = $crate::qc::select_weighted($g, &[ $( $crate::branch_internal!(@weight $weight) ),* ]);
+			$crate::branch_internal!(
+				[index] selected,
+				{ $( $branch )* },
+			)
+		}
+	};
+	( $g:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let selected = $crate::qc::select(
+				$g,
+				$crate::branch_internal!(@count { $( $branch )* }),
+			);
+			$crate::branch_internal!(
+				[index] selected,
+				{ $( $branch )* },
+			)
+		}
+	};
+}
+
+
+/// Creates a `bolero` value generator, which yields one of the given
+/// expressions.
+///
+/// This macro turns the same list of branches as [`branch_using`] or
+/// [`branch_weighted_using`] into a generator implementing
+/// `bolero_generator::ValueGenerator`, so it can be passed to
+/// `check!().with_generator(..)`. The branch is selected by the driver, not
+/// by an RNG, so the generator works with all of `bolero`'s drivers,
+/// including the fuzzing, the exhaustive and the Kani driver:
+/// * without weights, the branch is selected via `Driver::gen_variant`,
+/// * with weights, a position is drawn via `Driver::gen_u64` in the range
+///   of the sum of all weights, and mapped to the branch covering it, so an
+///   exhaustive driver enumerates each branch as often as its weight.
+///
+/// The selected branch is evaluated each time a value is generated, and the
+/// value must be `'static`.
+///
+/// This macro requires the `bolero` feature.
+///
+/// # Panics
+///
+/// Panics if weights are given and all of them are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use bolero::check;
+/// use random_branch::branch_generator;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// enum Op {
+///     Read,
+///     Write,
+///     Flush,
+/// }
+///
+/// check!()
+///     .with_generator(branch_generator!({ Op::Read, Op::Write, Op::Flush }))
+///     .for_each(|op| assert!(matches!(op, Op::Read | Op::Write | Op::Flush)))
+/// # ;
+/// ```
+#[macro_export]
+#[cfg(feature = "bolero")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "bolero")))]
+macro_rules! branch_generator {
+	({ $( $weight:expr => $branch:expr ),* $(,)? }) => {
+		$crate::generator::BranchGenerator::<_, { $crate::branch_internal!(@count { $( $branch )* }) }>::weighted(
+			[ $( $crate::branch_internal!(@weight $weight) ),* ],
+			move |index: usize| {
+				$crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				)
+			},
+		)
+	};
+	({ $( $branch:expr ),* $(,)? }) => {
+		$crate::generator::BranchGenerator::<_, { $crate::branch_internal!(@count { $( $branch )* }) }>::uniform(
+			move |index: usize| {
+				$crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				)
+			},
+		)
+	};
+}
+
+
+/// Runs the given expressions as random branches and asserts that they are
+/// taken uniformly.
+///
+/// This macro runs `iterations` times the same as [`branch_using`] does,
+/// tallying which branch was taken each time, and then checks the counts via
+/// [`testing::assert_uniform`] with the
+/// [`testing::DEFAULT_SIGNIFICANCE`]. The values of the branches are
+/// discarded. Returns the counts, for further checks.
+///
+/// This macro requires `std`.
+///
+/// # Panics
+///
+/// Panics with a table of the observed and expected counts if the counts are
+/// unlikely for a uniform distribution.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::assert_branch_uniform;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+/// # #[cfg(not(feature = "deterministic"))] {
+/// let mut log = Vec::new();
+///
+/// let counts = assert_branch_uniform!(my_rng, 3000, {
+///     log.push('a'),
+///     log.push('b'),
+///     log.push('c'),
+/// });
+/// assert_eq!(counts.iter().sum::<u64>(), 3000);
+/// assert_eq!(log.len(), 3000);
+/// # }
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! assert_branch_uniform {
+	( $rng:expr, $iterations:expr, { $( $branch:expr ),* $(,)? }) => {
+		{
+			let count = $crate::branch_internal!(@count { $( $branch )* });
+			let mut counts = $crate::std::vec![0_u64; count];
+			for _ in 0..$iterations {
+				let index = $crate::select::uniform(&mut $rng, count);
+				let _ = $crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				);
+				counts[index] += 1;
+			}
+			$crate::testing::assert_uniform(&counts, $crate::testing::DEFAULT_SIGNIFICANCE);
+			counts
+		}
+	};
+}
+
+
+/// Runs the given expressions as weighted random branches and asserts that
+/// they are taken proportionally to their weights.
+///
+/// This macro dose essentially the same as [`assert_branch_uniform`], but
+/// selects the branches like [`branch_weighted_using`] does, and checks the
+/// counts via [`testing::assert_weighted`] against the weights.
+///
+/// This macro requires `std`.
+///
+/// # Panics
+///
+/// Panics with a table of the observed and expected counts if the counts are
+/// unlikely for the given weights, or if all weights are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::assert_branch_weighted;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// # #[cfg(not(feature = "deterministic"))] {
+/// let counts = assert_branch_weighted!(my_rng, 4000, {
+///     3 => "common",
+///     1 => "rare",
+///     0 => "never",
+/// });
+/// assert_eq!(counts[2], 0);
+/// # }
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+macro_rules! assert_branch_weighted {
+	( $rng:expr, $iterations:expr, { $( $weight:expr => $branch:expr ),* $(,)? }) => {
+		{
+			let weights: &[u32] = &[ $( $crate::branch_internal!(@weight $weight) ),* ];
+			let mut counts = $crate::std::vec![0_u64; weights.len()];
+			for _ in 0..$iterations {
+				let index = match $crate::weighted::select(weights, &mut $rng) {
+					::core::result::Result::Ok(index) => index,
+					::core::result::Result::Err(_) => $crate::weighted::all_weights_zero(),
+				};
+				let _ = $crate::branch_internal!(
+					[index] index,
+					{ $( $branch )* },
+				);
+				counts[index] += 1;
+			}
+			let ratios: $crate::std::vec::Vec<f64> =
+				weights.iter().map(|&w| f64::from(w)).collect();
+			$crate::testing::assert_weighted(&counts, &ratios, $crate::testing::DEFAULT_SIGNIFICANCE);
+			counts
+		}
+	};
+}
+
+
+/// Internal branching macro
+///
+/// The strategy selects how the branch index is drawn from the RNG:
+/// * `uniform` uses `select::uniform`, which is uniform for any count
+/// * `fast` masks a single `u32` if the count is a power of two, and falls
+///   back to `uniform` otherwise
+/// * `index` uses the given expression as index instead of an RNG
+///
+/// The expansion does not recurse per branch, so the number of branches is
+/// not limited by the `recursion_limit`. Instead, the branches are counted as
+/// the length of a constant array, and each branch becomes a match arm whose
+/// guard compares the selected index with a running counter.
+///
+/// Each branch must be a single token tree. Public macros forward their
+/// `expr` fragments as is, which are opaque single token trees, instead of
+/// wrapping them in a block, since a block around e.g. `(a)` or `{ a }` would
+/// trigger the `unused_parens` and `unused_braces` lints in the user's crate.
+///
+/// Each expansion also declares a `static` with the coverage counters of its
+/// call site, which is zero-sized and unused without the `coverage` feature.
+/// With the `tracing`, `log`, or `defmt` feature, it logs the selection with
+/// the optional label.
 ///
 /// Syntax:
 /// ```text
-/// branch_internal!([RNG], [BRANCHES]+)
+/// branch_internal!([STRATEGY] [RNG], [BRANCHES]+ [LABEL]?)
+/// branch_internal!(@cfg_scan [STRATEGY] [RNG], [] [] [BRANCHES]*)
+/// branch_internal!(@cfg [STRATEGY] [RNG], [] [BRANCHES]* default: [BRANCH])
+/// branch_internal!(@count [BRANCHES]*)
+/// branch_internal!(@weight [WEIGHT])
 /// ```
 #[doc(hidden)]
 #[macro_export]
 macro_rules! branch_internal {
-	// Entry pattern
-	( $rng:expr, { $( $branches:tt )* }, ) => {
-		$crate::branch_internal!(@parseRule $rng, 0, {}, { $( $branches )* },)
+	// Invalid, no branches
+	( [$strategy:ident] $rng:expr, {  }, $( $label:literal )? ) => {
+		compile_error!("You must provide at least one choice.")
 	};
+	// Assembles all branches into a big match
+	( [$strategy:ident] $rng:expr, { $( $branch:tt )* }, $( $label:literal )? ) => {{
+		const COUNT: usize = $crate::branch_internal!(@count { $( $branch )* });
+		$crate::branch_internal!(@limit COUNT);
+		let selected: usize = $crate::branch_internal!(@select [$strategy] $rng, COUNT);
+		static SITE: $crate::select::Site<COUNT> =
+			$crate::select::Site::new(::core::file!(), ::core::line!(), ::core::column!())
+				$( .with_label($label) )?;
+		SITE.hit(selected);
+		$crate::branch_defmt!(selected, COUNT $( , $label )?);
+		let mut position: usize = 0;
+		match () {
+			// The level of `unused_parens` is taken from the enclosing node of
+			// the `match` with the branches, so e.g. `branch!((a), b)` is fine
+			#[allow(unused_parens)]
+			() => match () {
+				$( _ if { position += 1; selected == position - 1 } => $branch, )*
+				_ => unreachable!()
+			},
+		}
+	}};
 
-	// Invalid, base case
-	(@parseRule $rng:expr, $cnt:expr,
-		{  },
-		{  },
-	) => {
-		compile_error!("You must provide at least one choice.")
+	// Looks for a `default:` branch in branches that didn't match the plain
+	// rules of the public macros, the second bracket notes any attribute
+	(@cfg_scan [$strategy:ident] $rng:expr, [ $( $seen:tt )* ] [ $( $attr:tt )? ] default : $( $rest:tt )* ) => {
+		$crate::branch_internal!(@cfg [$strategy] $rng, [] $( $seen )* default : $( $rest )*)
 	};
-	// Prepares one branch at a time
-	(@parseRule $rng:expr, $cnt:expr,
-		{ $( $stuff:tt )* },
-		{ $branch:tt $( $rest:tt )* },
-	) => {
-		{
-			$crate::branch_internal!(@parseRule $rng, $cnt + 1,
-				{ $( $stuff )* { $cnt => $branch } },
-				{ $( $rest )* },
-			)
+	(@cfg_scan [$strategy:ident] $rng:expr, [ $( $seen:tt )* ] [ $( $attr:tt )? ] # $( $rest:tt )* ) => {
+		$crate::branch_internal!(@cfg_scan [$strategy] $rng, [ $( $seen )* # ] [#] $( $rest )*)
+	};
+	(@cfg_scan [$strategy:ident] $rng:expr, [ $( $seen:tt )* ] [ $( $attr:tt )? ] $next:tt $( $rest:tt )* ) => {
+		$crate::branch_internal!(@cfg_scan [$strategy] $rng, [ $( $seen )* $next ] [ $( $attr )? ] $( $rest )*)
+	};
+	(@cfg_scan [$strategy:ident] $rng:expr, [ $( $seen:tt )* ] [#] ) => {
+		compile_error!("Branches with `#[cfg(..)]` attributes need a final `default:` branch.")
+	};
+	// Neither attributes nor `default:`, so the branches are malformed, which
+	// this rule reports at the offending token
+	(@cfg_scan [$strategy:ident] $rng:expr, [ $( $seen:tt )* ] [] ) => {
+		$crate::branch_internal!(@plain $( $seen )*)
+	};
+	(@plain $( $branch:expr ),* $(,)? ) => {
+		compile_error!("The branches must be separated by commas or semicolons.")
+	};
+
+	// Munches branches with an optional `#[cfg(..)]` until the `default`
+	// branch, which is only taken if all other branches are configured out
+	(@cfg [$strategy:ident] $rng:expr, [ $( [$cond:meta] $branch:tt )* ] default : $default:expr $(,)? ) => {
+		$crate::branch_internal!(@cfg_expand [$strategy] $rng, {
+			$( [$cond] $branch )*
+			[not(any( $( $cond ),* ))] $default
+		})
+	};
+	(@cfg [$strategy:ident] $rng:expr, [ $( $done:tt )* ] default : $default:expr, $( $rest:tt )+ ) => {
+		compile_error!("The `default:` branch must be the last one.")
+	};
+	(@cfg [$strategy:ident] $rng:expr, [ $( $done:tt )* ] #[cfg($cond:meta)] $branch:expr, $( $rest:tt )* ) => {
+		$crate::branch_internal!(@cfg [$strategy] $rng, [ $( $done )* [$cond] $branch ] $( $rest )*)
+	};
+	(@cfg [$strategy:ident] $rng:expr, [ $( $done:tt )* ] $branch:expr, $( $rest:tt )* ) => {
+		$crate::branch_internal!(@cfg [$strategy] $rng, [ $( $done )* [all()] $branch ] $( $rest )*)
+	};
+	// Like the main rule, but each branch is only present if its `cfg` holds
+	(@cfg_expand [$strategy:ident] $rng:expr, { $( [$cond:meta] $branch:tt )* }) => {{
+		const COUNT: usize = {
+			let mut count: usize = 0;
+			$( #[cfg($cond)] { count += 1; } )*
+			count
+		};
+		$crate::branch_internal!(@limit COUNT);
+		let selected: usize = $crate::branch_internal!(@select [$strategy] $rng, COUNT);
+		static SITE: $crate::select::Site<COUNT> =
+			$crate::select::Site::new(::core::file!(), ::core::line!(), ::core::column!());
+		SITE.hit(selected);
+		$crate::branch_defmt!(selected, COUNT);
+		let mut position: usize = 0;
+		match () {
+			#[allow(unused_parens)]
+			() => match () {
+				$( #[cfg($cond)] _ if { position += 1; selected == position - 1 } => $branch, )*
+				_ => unreachable!()
+			},
 		}
+	}};
+
+	// Uniform index in `0..cnt`
+	(@select [uniform] $rng:expr, $cnt:expr) => {
+		if $crate::select::DETERMINISTIC { $crate::select::first($cnt) } else { $crate::select::uniform(&mut $rng, $cnt) }
 	};
-	// Assembles all branches into a big match
-	(@parseRule $rng:expr, $cnt:expr,
-		{ $( { $cc:expr => $branch:tt } )* },
-		{ },
-	) => {{
-		match $crate::rand::Rng::gen_range(&mut $rng, 0 .. ($cnt)) {
-			$( n if n == $cc => $branch )*
-			_ => unreachable!()
+	// Uses the given index as is
+	(@select [index] $index:expr, $cnt:expr) => {{
+		let index: usize = $index;
+		debug_assert!(index < $cnt, "The branch index is out of range.");
+		index
+	}};
+	// Masks a single `u32` if `cnt` is a power of two, otherwise `uniform`
+	(@select [fast] $rng:expr, $cnt:expr) => {
+		if $crate::select::DETERMINISTIC { $crate::select::first($cnt) } else { $crate::select::fast(&mut $rng, $cnt) }
+	};
+	// Uniform index in `0..cnt` from the OS RNG, the RNG expression is unused
+	(@select [secure] $rng:expr, $cnt:expr) => {
+		$crate::secure::select($cnt)
+	};
+	// Uniform index in `0..cnt` from the backend of `branch`, the RNG
+	// expression is unused
+	(@select [backend] $rng:expr, $cnt:expr) => {
+		if $crate::select::DETERMINISTIC { $crate::select::first($cnt) } else { $crate::select::backend($cnt) }
+	};
+	// Uniform index in `0..cnt` from a `fastrand` generator
+	(@select [fastrand] $rng:expr, $cnt:expr) => {
+		if $crate::select::DETERMINISTIC { $crate::select::first($cnt) } else { $crate::select::fastrand_uniform(&mut $rng, $cnt) }
+	};
+	// Uniform index in `0..cnt` from a `nanorand::Rng`
+	(@select [nanorand] $rng:expr, $cnt:expr) => {
+		if $crate::select::DETERMINISTIC { $crate::select::first($cnt) } else { $crate::select::nanorand_uniform(&mut $rng, $cnt) }
+	};
+
+	// Converts a weight into `u32`, taking `u32` as is and anything else via
+	// `Into<u32>`
+	(@weight $weight:expr) => {{
+		#[allow(unused_imports)]
+		use $crate::weighted::{ExactWeight as _, IntoWeight as _};
+		(&&$crate::weighted::Weight::new($weight)).get()
+	}};
+
+	// Fails to compile if there are more branches than the target supports
+	(@limit $cnt:ident) => {
+		const _: () = ::core::assert!(
+			$cnt <= $crate::select::MAX_BRANCHES,
+			"There are too many branches for this target, see the limits of `branch_using`.",
+		);
+	};
+
+	// Counts the branches as a constant expression
+	(@count { $( $branch:tt )* }) => {
+		<[()]>::len(&[ $( $crate::branch_internal!(@unit $branch) ),* ])
+	};
+	(@unit $branch:tt) => {
+		()
+	};
+}
+
+/// Logs selecting the branch `index` out of the constant `count` with
+/// `defmt`, if enabled, at the call site of the other macros.
+///
+/// Thus, the format string of each call site is interned on its own, and the
+/// file and line of the call site are known from its location information.
+/// Like the format string, the label is interned, and the index and count are
+/// sent as the smallest integer type that fits the count, to keep the frame
+/// small.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(feature = "defmt")]
+macro_rules! branch_defmt {
+	( $index:expr, $count:expr ) => {{
+		// The user crate need not depend on `defmt` itself
+		use $crate::defmt;
+		let index: usize = $index;
+		if $count <= u8::MAX as usize {
+			defmt::trace!("selected branch {=u8} of {=u8}", index as u8, $count as u8);
+		} else if $count <= u16::MAX as usize {
+			defmt::trace!("selected branch {=u16} of {=u16}", index as u16, $count as u16);
+		} else {
+			defmt::trace!("selected branch {=usize} of {=usize}", index, $count);
+		}
+	}};
+	( $index:expr, $count:expr, $label:literal ) => {{
+		use $crate::defmt;
+		let index: usize = $index;
+		let label = defmt::intern!($label);
+		if $count <= u8::MAX as usize {
+			defmt::trace!("selected branch {=u8} of {=u8} ({=istr})", index as u8, $count as u8, label);
+		} else if $count <= u16::MAX as usize {
+			defmt::trace!("selected branch {=u16} of {=u16} ({=istr})", index as u16, $count as u16, label);
+		} else {
+			defmt::trace!("selected branch {=usize} of {=usize} ({=istr})", index, $count, label);
 		}
 	}};
 }
 
-#[cfg(test)]
+/// Does nothing without the `defmt` feature.
+#[doc(hidden)]
+#[macro_export]
+#[cfg(not(feature = "defmt"))]
+macro_rules! branch_defmt {
+	( $index:expr, $count:expr $( , $label:literal )? ) => {};
+}
+
+// Most unit tests use the macros of `rand`, and with `defmt` they would lack
+// a global logger to link
+#[cfg(all(test, feature = "rand", not(feature = "defmt")))]
 mod tests {
 	// We actually use mostly doc-tests, which are better suited for macro tests
+
+	use super::*;
+	use rand::RngCore;
+	use rand_pcg::Lcg64Xsh32;
+
+	/// Yields the number of times it has been advanced so far.
+	#[derive(Debug, Default)]
+	struct CountingRng(u64);
+
+	impl RngCore for CountingRng {
+		fn next_u32(&mut self) -> u32 {
+			self.next_u64() as u32
+		}
+
+		fn next_u64(&mut self) -> u64 {
+			self.0 += 1;
+			self.0
+		}
+
+		fn fill_bytes(&mut self, dest: &mut [u8]) {
+			for chunk in dest.chunks_mut(8) {
+				let bytes = self.next_u64().to_le_bytes();
+				chunk.copy_from_slice(&bytes[..chunk.len()]);
+			}
+		}
+
+		fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+			self.fill_bytes(dest);
+			Ok(())
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn fast_power_of_two_is_uniform() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [0_u32; 8];
+		for _ in 0..8000 {
+			let i: usize = branch_fast_using!(rng, { 0, 1, 2, 3, 4, 5, 6, 7 });
+			counts[i] += 1;
+		}
+		for &c in &counts {
+			assert!(800 < c && c < 1200, "{:?}", counts);
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn fast_falls_back_for_other_counts() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [0_u32; 3];
+		for _ in 0..3000 {
+			let i: usize = branch_fast_using!(rng, { 0, 1, 2 });
+			counts[i] += 1;
+		}
+		for &c in &counts {
+			assert!(800 < c && c < 1200, "{:?}", counts);
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn array_elements_are_uniform() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [[0_u32; 3]; 4];
+		for _ in 0..3000 {
+			let arr: [usize; 4] = branch_array_using!(rng, 4, { 0, 1, 2 });
+			for (pos, &i) in arr.iter().enumerate() {
+				counts[pos][i] += 1;
+			}
+		}
+		for pos in &counts {
+			for &c in pos {
+				assert!(800 < c && c < 1200, "{:?}", counts);
+			}
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn array_advances_rng_n_times() {
+		let mut rng = CountingRng::default();
+		let arr: [u8; 64] = branch_array_using!(rng, 64, { 1, 2, 3 });
+		assert_eq!(rng.0, 64);
+		assert!(arr.iter().all(|&x| (1..=3).contains(&x)));
+
+		let mut rng = CountingRng::default();
+		let arr: [u8; 0] = branch_array_using!(rng, 0, { 1, 2, 3 });
+		assert_eq!(rng.0, 0);
+		assert!(arr.is_empty());
+	}
+
+	#[test]
+	fn maybe_is_lazy_with_given_probability() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut evaluated = 0_u32;
+		let mut some = 0_u32;
+		for _ in 0..10_000 {
+			if maybe_using!(rng, 0.3, evaluated += 1).is_some() {
+				some += 1;
+			}
+		}
+		assert_eq!(evaluated, some);
+		assert!(2700 < some && some < 3300, "{}", some);
+	}
+
+	#[test]
+	fn perhaps_runs_with_given_probability() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut evaluated = 0_u32;
+		for _ in 0..10_000 {
+			perhaps_using!(rng, 0.3, evaluated += 1);
+		}
+		assert!(2700 < evaluated && evaluated < 3300, "{}", evaluated);
+
+		// The value is discarded
+		let mut reset = || {
+			let old = evaluated;
+			evaluated = 0;
+			old
+		};
+		let () = perhaps_using!(rng, 1.0, reset());
+		assert_eq!(evaluated, 0);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn iter_is_lazy() {
+		let mut rng = CountingRng::default();
+		let v: [u8; 0] = [];
+		assert!(branch_iter_using!(&mut rng, { 1, 2, 3 }).take(0).eq(v));
+		assert_eq!(rng.0, 0);
+
+		assert_eq!(branch_iter_using!(&mut rng, { 1, 2, 3 }).take(5).count(), 5);
+		assert_eq!(rng.0, 5);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn iter_is_uniform() {
+		let mut counts = [0_u32; 3];
+		for i in branch_iter_using!(Lcg64Xsh32::new(42, 0), { 0, 1, 2 }).take(9000) {
+			counts[i] += 1;
+		}
+		for &c in &counts {
+			assert!(2700 < c && c < 3300, "{:?}", counts);
+		}
+	}
+
+	#[test]
+	fn iter_is_fused() {
+		fn assert_fused<I: core::iter::FusedIterator>(_: &I) {}
+		assert_fused(&branch_iter_using!(Lcg64Xsh32::new(42, 0), { 0, 1, 2 }));
+	}
+
+	#[test]
+	fn for_each_branch_visits_all_in_order() {
+		let mut counts = [0_u32; 3];
+		let mut seen = [None; 3];
+		let mut next = 0;
+		for_each_branch!({
+			{ counts[0] += 1; 'a' },
+			{ counts[1] += 1; 'b' },
+			{ counts[2] += 1; 'c' },
+		}, |c| {
+			seen[next] = Some(c);
+			next += 1;
+		});
+		assert_eq!(counts, [1, 1, 1]);
+		assert_eq!(seen, [Some('a'), Some('b'), Some('c')]);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn deck_draws_each_branch_once_per_cycle() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut deck = branch_deck!({ 0, 1, 2, 3, 4 });
+		let mut cycles = [[0_usize; 5]; 20];
+		for cycle in cycles.iter_mut() {
+			assert_eq!(deck.remaining(), 0);
+			for slot in cycle.iter_mut() {
+				*slot = deck.draw(&mut rng);
+			}
+			let mut sorted = *cycle;
+			sorted.sort_unstable();
+			assert_eq!(sorted, [0, 1, 2, 3, 4]);
+		}
+		assert!(cycles.windows(2).any(|w| w[0] != w[1]), "{:?}", cycles);
+	}
+
+	#[test]
+	fn norepeat_never_repeats_within_window() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut state = NoRepeat::<3>::new();
+		let mut last = [usize::MAX; 3];
+		for _ in 0..10_000 {
+			let i: usize = branch_norepeat_using!(state, rng, { 0, 1, 2, 3, 4 });
+			assert!(!last.contains(&i), "{} in {:?}", i, last);
+			last = [last[1], last[2], i];
+		}
+	}
+
+	#[test]
+	fn norepeat_is_uniform_among_allowed() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut state = NoRepeat::<1>::new();
+		// counts[previous][next]
+		let mut counts = [[0_u32; 3]; 3];
+		let mut prev: usize = branch_norepeat_using!(state, rng, { 0, 1, 2 });
+		for _ in 0..12_000 {
+			let i: usize = branch_norepeat_using!(state, rng, { 0, 1, 2 });
+			counts[prev][i] += 1;
+			prev = i;
+		}
+		for (p, row) in counts.iter().enumerate() {
+			for (i, &c) in row.iter().enumerate() {
+				if p == i {
+					assert_eq!(c, 0);
+				} else {
+					assert!(1700 < c && c < 2300, "{:?}", counts);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn adaptive_reduces_streaks() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut state = AdaptiveWeights::<3>::default();
+		let mut adaptive_repeats = 0;
+		let mut uniform_repeats = 0;
+		let mut prev_adaptive = usize::MAX;
+		let mut prev_uniform = usize::MAX;
+		for _ in 0..10_000 {
+			let a: usize = branch_adaptive_using!(state, rng, { 0, 1, 2 });
+			let u: usize = branch_using!(rng, { 0, 1, 2 });
+			adaptive_repeats += (a == prev_adaptive) as u32;
+			uniform_repeats += (u == prev_uniform) as u32;
+			prev_adaptive = a;
+			prev_uniform = u;
+		}
+		assert!(
+			adaptive_repeats * 2 < uniform_repeats,
+			"{} vs {}",
+			adaptive_repeats,
+			uniform_repeats,
+		);
+	}
+
+	#[test]
+	fn adaptive_keeps_frequencies_equal() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut state = AdaptiveWeights::<3>::default();
+		let mut counts = [0_u32; 3];
+		for _ in 0..9000 {
+			let i: usize = branch_adaptive_using!(state, rng, { 0, 1, 2 });
+			counts[i] += 1;
+		}
+		for &c in &counts {
+			assert!(2850 < c && c < 3150, "{:?}", counts);
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn zip_keeps_pairs() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut seen = [false; 3];
+		for _ in 0..1000 {
+			let (q, a, i): (&str, u32, usize) = branch_zip_using!(rng,
+				{ "one", "two", "three" },
+				{ 1, 2, 3 },
+				{ 0, 1, 2 },
+			);
+			assert_eq!(["one", "two", "three"][i], q);
+			assert_eq!(i as u32 + 1, a);
+			seen[i] = true;
+		}
+		assert_eq!(seen, [true; 3]);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn weighted_is_proportional() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [0_u32; 4];
+		for _ in 0..10_000 {
+			let i: usize = branch_weighted_using!(rng, { 1 => 0, 0 => 1, 3 => 2, 6 => 3 });
+			counts[i] += 1;
+		}
+		assert_eq!(counts[1], 0);
+		assert!(800 < counts[0] && counts[0] < 1200, "{:?}", counts);
+		assert!(2700 < counts[2] && counts[2] < 3300, "{:?}", counts);
+		assert!(5600 < counts[3] && counts[3] < 6400, "{:?}", counts);
+	}
+
+	#[test]
+	#[should_panic(expected = "All weights are zero.")]
+	fn weighted_panics_if_all_zero() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let zero = 0;
+		let _: u8 = branch_weighted_using!(rng, { zero => 1, zero => 2 });
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn until_never_returns_rejected_values() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let forbidden = 2;
+		let mut attempts = 0;
+		for _ in 0..1000 {
+			let value = branch_until_using!(rng, 10, |&v| { attempts += 1; v != forbidden }, {
+				1,
+				2,
+				3,
+			});
+			assert!(value == Some(1) || value == Some(3), "{:?}", value);
+		}
+		assert!(attempts > 1000);
+	}
+
+	#[test]
+	fn until_gives_up_after_max_attempts() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut attempts = 0;
+		let value = branch_until_using!(rng, 7, |_| { attempts += 1; false }, { 1, 2, 3 });
+		assert_eq!(value, None);
+		assert_eq!(attempts, 7);
+	}
+
+	#[test]
+	fn fallback_stops_at_first_success() {
+		for seed in 0..100 {
+			let mut rng = Lcg64Xsh32::new(seed, 0);
+			let mut evaluated = [0_u32; 4];
+			let result: Result<usize, usize> = branch_fallback_using!(rng, {
+				{ evaluated[0] += 1; Err(0) },
+				{ evaluated[1] += 1; Ok(1) },
+				{ evaluated[2] += 1; Err(2) },
+				{ evaluated[3] += 1; Ok(3) },
+			});
+			let ok = result.unwrap();
+			assert_eq!(evaluated[ok], 1);
+			assert_eq!(evaluated[1] + evaluated[3], 1);
+			assert!(evaluated.iter().all(|&e| e <= 1));
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn fallback_returns_last_error() {
+		let mut first_tried = [0_u32; 3];
+		for seed in 0..300 {
+			let mut rng = Lcg64Xsh32::new(seed, 0);
+			let mut order = [usize::MAX; 3];
+			let mut next = 0;
+			let mut fail = |i: usize| -> Result<(), usize> {
+				order[next] = i;
+				next += 1;
+				Err(i)
+			};
+			let result = branch_fallback_using!(rng, { fail(0), fail(1), fail(2) });
+			assert_eq!(next, 3);
+			assert_eq!(result, Err(order[2]));
+			first_tried[order[0]] += 1;
+		}
+		for &c in &first_tried {
+			assert!(70 < c && c < 130, "{:?}", first_tried);
+		}
+	}
+
+	#[test]
+	fn retry_never_retries_passed_branches() {
+		for seed in 0..100 {
+			let mut rng = Lcg64Xsh32::new(seed, 0);
+			let mut evaluated = [0_u32; 4];
+			let value = branch_retry_using!(rng, {
+				{ evaluated[0] += 1; Pick::Pass },
+				{ evaluated[1] += 1; Pick::Pass },
+				{ evaluated[2] += 1; Pick::Take(2) },
+				{ evaluated[3] += 1; Pick::Pass },
+			});
+			assert_eq!(value, Some(2));
+			assert_eq!(evaluated[2], 1);
+			assert!(evaluated.iter().all(|&e| e <= 1), "{:?}", evaluated);
+		}
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn retry_all_pass() {
+		let mut rng = CountingRng::default();
+		let mut evaluated = [0_u32; 3];
+		let value: Option<()> = branch_retry_using!(rng, {
+			{ evaluated[0] += 1; Pick::Pass },
+			{ evaluated[1] += 1; Pick::Pass },
+			{ evaluated[2] += 1; Pick::Pass },
+		});
+		assert_eq!(value, None);
+		assert_eq!(evaluated, [1, 1, 1]);
+		assert_eq!(rng.0, 3);
+	}
+
+	#[test]
+	fn named_label_matches_executed_branch() {
+		for seed in 0..100 {
+			let mut rng = Lcg64Xsh32::new(seed, 0);
+			let executed;
+			let (label, value) = branch_named_using!(rng, {
+				"first" => { executed = 0; 'a' },
+				"second" => { executed = 1; 'b' },
+				"third" => { executed = 2; 'c' },
+			});
+			let expected = match executed {
+				0 => ("first", 'a'),
+				1 => ("second", 'b'),
+				2 => ("third", 'c'),
+				_ => unreachable!(),
+			};
+			assert_eq!((label, value), expected);
+		}
+	}
+
+	#[test]
+	fn stratified_windows_are_balanced() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut state = StratifiedPicker::<4>::new();
+		let picks: [usize; 400] = core::array::from_fn(|_| {
+			branch_stratified_using!(state, rng, { 0, 1, 2, 3 })
+		});
+
+		for k in 1..=5 {
+			for window in picks.windows(4 * k) {
+				for branch in 0..4 {
+					let count = window.iter().filter(|&&i| i == branch).count();
+					assert!(count.abs_diff(k) <= 1, "{} in {:?}", branch, window);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn stratified_weighted_tracks_shares() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		for weights in [[1_u32, 2, 3], [5, 0, 1], [7, 11, 13], [1, 1, 1000]] {
+			let total: u32 = weights.iter().sum();
+			let mut state = StratifiedPicker::<3>::new();
+			let mut counts = [0_u32; 3];
+			for n in 1..=(10 * total) {
+				let index = branch_stratified_using!(state, rng, {
+					weights[0] => 0,
+					weights[1] => 1,
+					weights[2] => 2,
+				});
+				counts[index] += 1;
+				for (&count, &w) in counts.iter().zip(&weights) {
+					let expected = f64::from(n) * f64::from(w) / f64::from(total);
+					assert!((f64::from(count) - expected).abs() < 1.0, "{:?} {:?}", weights, counts);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn stratified_seeds_differ() {
+		let run = |seed| {
+			let mut rng = Lcg64Xsh32::new(seed, 0);
+			let mut state = StratifiedPicker::<3>::new();
+			core::array::from_fn::<usize, 30, _>(|_| {
+				branch_stratified_using!(state, rng, { 0, 1, 2 })
+			})
+		};
+		assert_eq!(run(1), run(1));
+		assert_ne!(run(1), run(2));
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn from_fn_uses_closure() {
+		let mut calls = 0_u64;
+		let value = branch_using!(from_fn(|| { calls += 1; 0 }), { 'a', 'b', 'c' });
+		assert_eq!(value, 'a');
+		assert!(calls >= 1);
+
+		let mut rng = from_fn(|| 0x0123_4567_89ab_cdef);
+		let mut bytes = [0_u8; 10];
+		rng.fill_bytes(&mut bytes);
+		assert_eq!(bytes, [0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01, 0xef, 0xcd]);
+		assert_eq!(rng.next_u32(), 0x89ab_cdef);
+	}
+
+	#[test]
+	fn for_each_empty_iterator() {
+		let mut rng = CountingRng::default();
+		let mut evaluated = false;
+		branch_for_each_using!(rng, core::iter::empty::<u8>(), |_| {
+			evaluated = true,
+			evaluated = true,
+		});
+		assert!(!evaluated);
+		assert_eq!(rng.0, 0);
+
+		let mut mapped = branch_map_using!(rng, [0_u8; 0], |x| { x, x + 1 });
+		assert_eq!(mapped.next(), None);
+		assert_eq!(rng.0, 0);
+	}
+
+	#[test]
+	fn for_each_borrowed_items() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let items = [[1_u32; 4], [2; 4], [3; 4]];
+		let mut sum = 0;
+		branch_for_each_using!(rng, &items, |&[first, ..]| {
+			sum += first,
+			sum += 10 * first,
+		});
+		assert_eq!(sum % 10 + sum / 10 % 10, 6);
+
+		let mut lengths = branch_map_using!(rng, items.iter(), |item| {
+			item.len(),
+			item[1..].len() + 1,
+		});
+		assert!(lengths.all(|len| len == 4));
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn for_each_selects_independently() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut picks = [0_usize; 3000];
+		branch_for_each_using!(rng, picks.iter_mut(), |pick| {
+			*pick = 0,
+			*pick = 1,
+			*pick = 2,
+		});
+
+		let mut counts = [0_u32; 3];
+		let mut repeats = 0;
+		for (i, &pick) in picks.iter().enumerate() {
+			counts[pick] += 1;
+			if i > 0 && picks[i - 1] == pick {
+				repeats += 1;
+			}
+		}
+		assert!(counts.iter().all(|&c| 900 < c && c < 1100), "{:?}", counts);
+		// Independent picks repeat the previous one a third of the time
+		assert!(900 < repeats && repeats < 1100, "{}", repeats);
+
+		let mut mapped = [0_usize; 3000];
+		for (slot, value) in mapped.iter_mut().zip(branch_map_using!(rng, 0..3000, |_| { 0, 1, 2 })) {
+			*slot = value;
+		}
+		assert_ne!(mapped, picks);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn batch_draws_once() {
+		let mut rng = CountingRng::default();
+		let (a, b, c) = branch_batch_using!(rng, { 0, 1 }, { 0, 1, 2 }, { 0, 1, 2, 3, 4 });
+		assert!(a < 2 && b < 3 && c < 5);
+		assert_eq!(rng.0, 1);
+
+		let (single,) = branch_batch_using!(rng, { 'x' });
+		assert_eq!(single, 'x');
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn batch_is_uniform_and_independent() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [[0_u32; 3]; 2];
+		for _ in 0..6000 {
+			let (a, b) = branch_batch_using!(rng, { 0, 1 }, { 0, 1, 2 });
+			counts[a][b] += 1;
+		}
+		for c in counts.iter().flatten() {
+			assert!(900 < *c && *c < 1100, "{:?}", counts);
+		}
+	}
+
+	#[test]
+	fn default_injection_rate() {
+		// This is the only test touching the global rate
+		let mut rng = CountingRng::default();
+		assert_eq!(crate::default_injection_rate(), 0.0);
+		for _ in 0..10 {
+			assert_eq!(with_probability_default_using!(rng, 1), None);
+		}
+		assert_eq!(rng.0, 0);
+
+		set_default_injection_rate(1.0);
+		assert_eq!(with_probability_default_using!(rng, 1), Some(1));
+
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		set_default_injection_rate(0.25);
+		let hits = (0..4000).filter(|_| with_probability_default_using!(rng, ()).is_some()).count();
+		assert!(900 < hits && hits < 1100, "{}", hits);
+
+		set_default_injection_rate(0.0);
+	}
+
+	#[test]
+	#[should_panic(expected = "The injection rate must be within 0.0..=1.0")]
+	fn invalid_injection_rate() {
+		set_default_injection_rate(f64::NAN);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn sample_n_is_independent_with_replacement() {
+		let mut rng = CountingRng::default();
+		let mut samples = branch_sample_n_using!(rng, 4, { 'a', 'b' });
+		assert_eq!(rng.0, 4);
+		assert_eq!(samples.len(), 4);
+		assert!(samples.all(|s| s == 'a' || s == 'b'));
+
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [0_u32; 3];
+		for index in branch_sample_n_using!(rng, 3000, { 0_usize, 1, 2 }) {
+			counts[index] += 1;
+		}
+		assert!(counts.iter().all(|&c| 900 < c && c < 1100), "{:?}", counts);
+	}
+
+	#[test]
+	fn branch_on_wraps_around() {
+		let mut evaluated = [0_u32; 3];
+		for i in 0..9 {
+			let value = branch_on!(i, {
+				{ evaluated[0] += 1; 'a' },
+				{ evaluated[1] += 1; 'b' },
+				{ evaluated[2] += 1; 'c' },
+			});
+			assert_eq!(value, ['a', 'b', 'c'][i % 3]);
+		}
+		assert_eq!(evaluated, [3, 3, 3]);
+		assert_eq!(branch_on!(usize::MAX, { 0, 1 }), 1);
+	}
+
+	#[test]
+	#[should_panic(expected = "The branch index 3 is out of range for 3 branches")]
+	fn branch_on_checked_out_of_range() {
+		branch_on_checked!(3, { 'a', 'b', 'c' });
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn branch_on_matches_branch_using() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		for _ in 0..100 {
+			let index = choose_index(&mut rng.clone(), 5);
+			let on = branch_on!(index, { 'a', 'b', 'c', 'd', 'e' });
+			let checked = branch_on_checked!(index, { 'a', 'b', 'c', 'd', 'e' });
+			let using = branch_using!(rng, { 'a', 'b', 'c', 'd', 'e' });
+			assert_eq!(on, using);
+			assert_eq!(checked, using);
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn secure_ignores_thread_override() {
+		set_thread_branch_rng(std::boxed::Box::new(from_fn(|| 0)));
+		let overridden: [u32; 64] = core::array::from_fn(|_| branch!(0, 1));
+		let secure: [u32; 64] = core::array::from_fn(|_| branch_secure!(0, 1));
+		reset_thread_branch_rng();
+
+		assert!(overridden.iter().all(|&x| x == 0));
+		assert!(secure.contains(&0) && secure.contains(&1));
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn secure_is_uniform() {
+		use crate::testing::assert_uniform;
+		use crate::testing::DEFAULT_SIGNIFICANCE;
+
+		let mut counts = [0; 3];
+		let mut try_counts = [0; 5];
+		for _ in 0..3000 {
+			counts[branch_secure!(0, 1, 2)] += 1;
+			try_counts[try_branch_secure!(0, 1, 2, 3, 4).unwrap()] += 1;
+		}
+		assert_uniform(&counts, DEFAULT_SIGNIFICANCE);
+		assert_uniform(&try_counts, DEFAULT_SIGNIFICANCE);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn semicolon_separated() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut counts = [0_u32; 3];
+		for _ in 0..300 {
+			branch_using!(rng, {
+				counts[0] += 1;
+				counts[1] += 1;
+				{ let i = 2; counts[i] += 1 };
+			});
+		}
+		assert_eq!(counts.iter().sum::<u32>(), 300);
+		assert!(counts.iter().all(|&c| c > 0), "{:?}", counts);
+
+		// Without a trailing semicolon and with a value
+		let x: u8 = branch_using!(rng, { 1; 2 });
+		assert!(x == 1 || x == 2);
+
+		// A single branch, whichever form
+		assert_eq!(branch_using!(rng, { 7; }), 7);
+		assert_eq!(branch_using!(rng, { 7, }), 7);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn semicolon_separated_thread_rng() {
+		let mut counts = [0_u32; 2];
+		for _ in 0..100 {
+			branch! {
+				counts[0] += 1;
+				counts[1] += 1;
+			}
+		}
+		assert_eq!(counts[0] + counts[1], 100);
+
+		let x: u8 = branch!(1, 2);
+		assert!(x == 1 || x == 2);
+	}
+
+	#[test]
+	fn branch_errors() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+
+		let value = try_branch_weighted_using!(rng, { 0 => 'a', 0 => 'b' });
+		assert_eq!(value, Err(BranchError::AllWeightsZero));
+		let value = try_branch_weighted_using!(rng, { 0 => 'a', 1 => 'b' });
+		assert_eq!(value, Ok('b'));
+
+		assert_eq!(try_branch_on!(3, { 'a', 'b', 'c' }), Err(BranchError::IndexOutOfRange));
+		assert_eq!(try_branch_on!(2, { 'a', 'b', 'c' }), Ok('c'));
+
+		assert_eq!(BranchError::from(AllWeightsZero), BranchError::AllWeightsZero);
+	}
+
+	#[test]
+	#[cfg(not(any(feature = "coverage", feature = "tracing", feature = "log")))]
+	fn coverage_is_zero_sized_without_feature() {
+		assert_eq!(core::mem::size_of::<select::Site<3>>(), 0);
+	}
+
+	#[test]
+	fn weights_into_u32() {
+		/// Deliberately neither `Copy` nor `Clone`.
+		struct Weight(u32);
+
+		impl From<Weight> for u32 {
+			fn from(weight: Weight) -> u32 {
+				weight.0
+			}
+		}
+
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let never: u16 = 0;
+		for _ in 0..100 {
+			let value = branch_weighted_using!(rng, {
+				Weight(0) => 'a',
+				never => 'b',
+				true => 'c',
+				2_u8 => 'd',
+			});
+			assert!(value == 'c' || value == 'd');
+		}
+
+		let value = try_branch_weighted_using!(rng, { Weight(0) => 'a', false => 'b' });
+		assert_eq!(value, Err(BranchError::AllWeightsZero));
+	}
+
+	#[test]
+	fn branch_const_index_in_const_fn() {
+		const fn square_or_cube<const I: usize>(x: u32) -> u32 {
+			branch_const_index!(I, { x * x, x * x * x })
+		}
+
+		const SQUARE: u32 = square_or_cube::<0>(3);
+		assert_eq!(SQUARE, 9);
+		assert_eq!(square_or_cube::<1>(3), 27);
+
+		let mut evaluated = [false; 3];
+		branch_const_index!(1, {
+			evaluated[0] = true,
+			evaluated[1] = true,
+			evaluated[2] = true,
+		});
+		assert_eq!(evaluated, [false, true, false]);
+	}
+
+	#[test]
+	#[cfg(not(feature = "log"))]
+	fn no_log_records_without_feature() {
+		use core::sync::atomic::AtomicUsize;
+		use core::sync::atomic::Ordering;
+
+		static RECORDS: AtomicUsize = AtomicUsize::new(0);
+
+		struct Counter;
+		impl log::Log for Counter {
+			fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+				true
+			}
+			fn log(&self, record: &log::Record<'_>) {
+				if record.target() == "random_branch" {
+					RECORDS.fetch_add(1, Ordering::Relaxed);
+				}
+			}
+			fn flush(&self) {}
+		}
+
+		log::set_logger(&Counter).unwrap();
+		log::set_max_level(log::LevelFilter::Trace);
+
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		branch_using!(rng, "ignored", { 1, 2, 3 });
+		assert_eq!(RECORDS.load(Ordering::Relaxed), 0);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn random_bits_are_deterministic() {
+		use rand::Rng;
+
+		let a: [bool; 100] = random_bits_using!(Lcg64Xsh32::new(42, 0), 100);
+		let b: [bool; 100] = random_bits_using!(Lcg64Xsh32::new(42, 0), 100);
+		let c: [bool; 100] = random_bits_using!(Lcg64Xsh32::new(43, 0), 100);
+		assert_eq!(a, b);
+		assert_ne!(a, c);
+		assert!(a.contains(&true) && a.contains(&false));
+
+		// Short patterns are the bits of a single byte
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let byte: u8 = Lcg64Xsh32::new(42, 0).gen();
+		let bits = random_bits_using!(rng, 8);
+		for (i, bit) in bits.iter().enumerate() {
+			assert_eq!(*bit, byte >> i & 1 == 1);
+		}
+		let bits = random_bits_using!(rng, 3);
+		assert_eq!(bits.len(), 3);
+	}
+
+	#[test]
+	fn branch_using_rng_field_with_mutable_self_branches() {
+		struct Game {
+			rng: Lcg64Xsh32,
+			score: u32,
+			turns: u32,
+		}
+
+		impl Game {
+			fn bonus(&mut self) -> u32 {
+				self.score += 10;
+				branch_using!(self.rng, { 1, 2 })
+			}
+
+			fn turn(&mut self) -> u32 {
+				self.turns += 1;
+				branch_using!(self.rng, {
+					{ self.score += 1; 0 },
+					{ self.score += 2; self.turns },
+					self.bonus(),
+					branch_using!(self.rng, { self.score, self.turns }),
+				})
+			}
+		}
+
+		let mut game = Game {
+			rng: Lcg64Xsh32::new(0, 0),
+			score: 0,
+			turns: 0,
+		};
+		for _ in 0..100 {
+			game.turn();
+		}
+		assert_eq!(game.turns, 100);
+		assert!(game.score > 0);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn shuffle_using_seeded_permutation() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut values = [1, 2, 3, 4, 5, 6, 7, 8];
+		shuffle_using!(rng, &mut values);
+		assert_eq!(values, [5, 3, 7, 1, 8, 4, 6, 2]);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn branch_subset_using_stays_in_range() {
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		let mut seen = [false; 5];
+		for _ in 0..200 {
+			let index = branch_subset_using!(rng, 1..=3, { 0, 1, 2, 3, 4 });
+			seen[index] = true;
+		}
+		assert_eq!(seen, [false, true, true, true, false]);
+
+		for _ in 0..20 {
+			assert_eq!(branch_subset_using!(rng, 2.., { 0, 1, 2 }), 2);
+			assert_eq!(branch_subset_using!(rng, ..1, { 0, 1, 2 }), 0);
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "The branch range 1..4 must be non-empty and within 0..3")]
+	fn branch_subset_using_out_of_range() {
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		branch_subset_using!(rng, 1..=3, { 0, 1, 2 });
+	}
+
+	#[test]
+	#[should_panic(expected = "The branch range 2..2 must be non-empty and within 0..3")]
+	fn branch_subset_using_empty_range() {
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		branch_subset_using!(rng, 2..2, { 0, 1, 2 });
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn tuple_arms_are_single_branches() {
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		let mut seen = [false; 2];
+		for _ in 0..50 {
+			let (a, b): (i32, i32) = branch_using!(rng, { (1, 2), (3, 4) });
+			assert_eq!(a + 1, b);
+			seen[a as usize / 2] = true;
+		}
+		assert_eq!(seen, [true; 2]);
+
+		assert_eq!(branch_count!((1, 2), (3, 4), (5, 6)), 3);
+		let (x, y) = branch_using!(rng, { (1, 2); (3, 4) });
+		assert_eq!(x + 1, y);
+	}
+
+	#[test]
+	fn max_branches_fit_tables() {
+		use crate::select::MAX_BRANCHES;
+
+		let bytes = MAX_BRANCHES.checked_mul(core::mem::size_of::<usize>());
+		assert!(bytes.is_some_and(|bytes| bytes <= isize::MAX as usize));
+		#[cfg(target_pointer_width = "16")]
+		assert_eq!(MAX_BRANCHES, 16383);
+		#[cfg(target_pointer_width = "64")]
+		assert_eq!(MAX_BRANCHES, (1 << 60) - 1);
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	#[cfg(feature = "std")]
+	fn branch_owned_moves_string_into_one_arm() {
+		use std::string::String;
+		use std::vec::Vec;
+
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		let mut consumers: [Vec<String>; 3] = Default::default();
+		for _ in 0..60 {
+			let resource = String::from("resource");
+			branch_owned_using!(rng, resource, {
+				consumers[0].push(resource),
+				consumers[1].push(resource),
+				consumers[2].push(resource),
+			});
+		}
+		assert_eq!(consumers.iter().map(Vec::len).sum::<usize>(), 60);
+		assert!(consumers.iter().all(|c| !c.is_empty() && c.iter().all(|s| s == "resource")));
+
+		let len = branch_owned!(s = String::from("abc"), { s.len(), s.into_bytes().len() });
+		assert_eq!(len, 3);
+	}
 }
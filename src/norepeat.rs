@@ -0,0 +1,115 @@
+//! The state of [`branch_norepeat_using`](crate::branch_norepeat_using).
+
+use core::cell::Cell;
+
+use rand::Rng;
+
+use crate::choose_index;
+
+
+/// Remembers the last `K` selected branches, to avoid repeating them.
+///
+/// This is the state used by
+/// [`branch_norepeat_using`](crate::branch_norepeat_using). Each selection
+/// chooses uniformly among those branches that are not among the last `K`
+/// selections, and then remembers the selected branch, forgetting the oldest
+/// one. Thus, with `K = 1` the same branch is never selected twice in a row.
+///
+/// The last selections are stored in a fixed-size ring buffer, so this
+/// requires neither `std` nor an allocator.
+///
+/// `K` must be less than the number of branches, otherwise there would be
+/// nothing left to choose from. This is checked at compile-time, when
+/// selecting with `N` branches.
+#[derive(Debug, Clone)]
+pub struct NoRepeat<const K: usize> {
+	recent: [usize; K],
+	len: usize,
+	next: usize,
+}
+
+impl<const K: usize> NoRepeat<K> {
+	/// Creates a state without any previous selections.
+	pub const fn new() -> Self {
+		Self {
+			recent: [0; K],
+			len: 0,
+			next: 0,
+		}
+	}
+
+	/// Returns the remembered selections, in no particular order.
+	pub fn recent(&self) -> &[usize] {
+		&self.recent[..self.len]
+	}
+
+	/// Forgets all previous selections.
+	pub fn clear(&mut self) {
+		self.len = 0;
+		self.next = 0;
+	}
+
+	/// Selects the index of one of `N` branches, that is not among the last
+	/// `K` selections.
+	///
+	/// The selection is uniform among the allowed branches.
+	pub fn select<R: Rng + ?Sized, const N: usize>(&mut self, rng: &mut R) -> usize {
+		const {
+			assert!(K < N, "The number of remembered selections must be less than the number of branches.");
+		}
+
+		let recent = self.recent();
+		let mut remaining = choose_index(rng, N - recent.len());
+		let mut index = 0;
+		loop {
+			if !recent.contains(&index) {
+				if remaining == 0 {
+					break;
+				}
+				remaining -= 1;
+			}
+			index += 1;
+		}
+
+		if K > 0 {
+			self.recent[self.next] = index;
+			self.next = (self.next + 1) % K;
+			self.len = (self.len + 1).min(K);
+		}
+		index
+	}
+}
+
+impl<const K: usize> Default for NoRepeat<K> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+
+/// Selects the index of one of `count` branches, that is not `last`, and
+/// stores it as the new `last`.
+///
+/// The selection is uniform among the allowed branches. If there is just a
+/// single branch, it is selected anyway. If `last` is not a valid index, e.g.
+/// `usize::MAX` initially, all branches are allowed.
+#[doc(hidden)]
+pub fn select_excluding_last<R: Rng + ?Sized>(
+	last: &Cell<usize>,
+	rng: &mut R,
+	count: usize,
+) -> usize {
+	let previous = last.get();
+	let index = if count > 1 && previous < count {
+		let index = choose_index(rng, count - 1);
+		if index >= previous {
+			index + 1
+		} else {
+			index
+		}
+	} else {
+		choose_index(rng, count)
+	};
+	last.set(index);
+	index
+}
@@ -0,0 +1,62 @@
+//! The index selection of [`branch_qc`](crate::branch_qc).
+
+pub use quickcheck::Gen;
+
+
+/// All byte values, to draw uniform bytes via [`Gen::choose`].
+const BYTES: [u8; 256] = {
+	let mut bytes = [0; 256];
+	let mut i = 0;
+	while i < 256 {
+		bytes[i] = i as u8;
+		i += 1;
+	}
+	bytes
+};
+
+
+/// Draws a uniformly distributed `u64` from the generator.
+fn next_u64(g: &mut Gen) -> u64 {
+	(0..8).fold(0, |acc, _| (acc << 8) | u64::from(*g.choose(&BYTES).unwrap()))
+}
+
+/// Draws a uniformly distributed `u64` in `0..n`.
+fn below(g: &mut Gen, n: u64) -> u64 {
+	// Lemire's multiply-and-reject method
+	let threshold = n.wrapping_neg() % n;
+	loop {
+		let product = u128::from(next_u64(g)) * u128::from(n);
+		if product as u64 >= threshold {
+			return (product >> 64) as u64;
+		}
+	}
+}
+
+
+/// Selects a uniformly distributed index in `0..count`.
+#[track_caller]
+pub fn select(g: &mut Gen, count: usize) -> usize {
+	assert!(count > 0, "You must provide at least one choice.");
+	below(g, count as u64) as usize
+}
+
+/// Selects an index with a probability proportional to its weight.
+#[track_caller]
+pub fn select_weighted(g: &mut Gen, weights: &[u32]) -> usize {
+	let total: u64 = weights.iter().map(|&w| u64::from(w)).sum();
+	assert!(total > 0, "All weights are zero.");
+
+	let mut remaining = below(g, total);
+	let mut index = 0;
+	while remaining >= u64::from(weights[index]) {
+		remaining -= u64::from(weights[index]);
+		index += 1;
+	}
+	index
+}
+
+/// Returns whether the given recursion depth reached the size of the
+/// generator.
+pub fn exhausted(g: &Gen, depth: usize) -> bool {
+	depth >= g.size()
+}
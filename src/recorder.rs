@@ -0,0 +1,550 @@
+//! Recording and replaying the branches selected by [`branch`](crate::branch)
+//! and friends.
+
+use core::cell::RefCell;
+#[cfg(feature = "serde")]
+use core::convert::TryFrom;
+use core::fmt;
+use core::panic::Location;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::vec::Vec;
+
+
+std::thread_local! {
+	/// The decisions recorded so far on the current thread, if recording.
+	static RECORDING: RefCell<Option<Vec<Decision>>> = const { RefCell::new(None) };
+
+	/// The decisions to replay on the current thread, if replaying.
+	static REPLAYING: RefCell<Option<Replayed>> = const { RefCell::new(None) };
+}
+
+
+/// The state of an installed [`Replay`].
+#[derive(Debug)]
+struct Replayed {
+	/// The decisions to replay.
+	decisions: Vec<Decision>,
+	/// The position of the next decision.
+	position: usize,
+	/// Whether to take the first branch instead of panicking if exhausted or
+	/// diverged.
+	lenient: bool,
+}
+
+
+/// A single selection of a branch, made by a macro.
+///
+/// The call site of the macro is identified by its file, line and column, as
+/// reported by [`Location`]. These don't depend on the compilation, so they
+/// stay valid across recompiles, other builds or versions of the compiler,
+/// as long as the source code is not changed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decision {
+	/// The source file of the macro invocation.
+	pub file: Cow<'static, str>,
+	/// The line of the macro invocation.
+	pub line: u32,
+	/// The column of the macro invocation.
+	pub column: u32,
+	/// The number of branches of the macro invocation.
+	pub count: usize,
+	/// The index of the selected branch.
+	pub index: usize,
+}
+
+impl Decision {
+	/// Returns whether this decision was made at the given call site among
+	/// the given number of branches.
+	fn matches(&self, location: &Location<'_>, count: usize) -> bool {
+		self.file == location.file()
+			&& self.line == location.line()
+			&& self.column == location.column()
+			&& self.count == count
+	}
+}
+
+impl fmt::Display for Decision {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"branch {} of {} at {}:{}:{}",
+			self.index, self.count, self.file, self.line, self.column,
+		)
+	}
+}
+
+
+/// Records the branches selected on the current thread, while installed.
+///
+/// While a recorder is installed, every macro that selects a single branch
+/// uniformly, such as [`branch`](crate::branch) or
+/// [`branch_using`](crate::branch_using), appends a [`Decision`] to it, which
+/// identifies the call site by its file, line and column. The recorded
+/// decisions can then be turned into a [`Replay`] via
+/// [`finish`](Self::finish), e.g. to persist the path taken by a failed soak
+/// test, and replay it later.
+///
+/// Installing a recorder replaces any previous recorder of the current
+/// thread, which is restored when the new recorder is finished or dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch;
+/// use random_branch::recorder::Recorder;
+///
+/// fn roll() -> u32 {
+///     branch!(1, 2, 3, 4, 5, 6) + branch!(1, 2, 3, 4, 5, 6)
+/// }
+///
+/// let recorder = Recorder::install();
+/// let first = roll();
+/// let replay = recorder.finish();
+/// assert_eq!(replay.decisions().len(), 2);
+///
+/// let replaying = replay.install();
+/// assert_eq!(roll(), first);
+/// replaying.finish();
+/// ```
+#[derive(Debug)]
+pub struct Recorder {
+	previous: Option<Option<Vec<Decision>>>,
+}
+
+impl Recorder {
+	/// Starts recording on the current thread.
+	#[must_use = "Recording stops when the recorder is dropped"]
+	pub fn install() -> Self {
+		let previous = RECORDING.with(|r| r.borrow_mut().replace(Vec::new()));
+		Self {
+			previous: Some(previous),
+		}
+	}
+
+	/// Stops recording and returns the recorded decisions.
+	pub fn finish(mut self) -> Replay {
+		Replay {
+			decisions: self.restore().unwrap_or_default(),
+		}
+	}
+
+	/// Restores the previous recorder, and returns the own decisions.
+	fn restore(&mut self) -> Option<Vec<Decision>> {
+		let previous = self.previous.take()?;
+		RECORDING.with(|r| core::mem::replace(&mut *r.borrow_mut(), previous))
+	}
+}
+
+impl Drop for Recorder {
+	fn drop(&mut self) {
+		self.restore();
+	}
+}
+
+
+/// A recorded sequence of [`Decision`]s, which can be replayed.
+///
+/// A replay is created by a [`Recorder`], and installing it via
+/// [`install`](Self::install) makes the macros of the current thread select
+/// the recorded branches instead of drawing from their RNG.
+///
+/// With the `serde` feature, a replay can be serialized, e.g. to attach it to
+/// a bug report, and deserialized in another process. The serialized form is
+/// a struct with the [`FORMAT_VERSION`](Self::FORMAT_VERSION) as `version`
+/// and the `decisions`. Unknown fields are ignored, but a newer version is
+/// rejected. Deserializing also [validates](Self::validate) the decisions, so
+/// a corrupted or inconsistent trace is reported as an error instead of
+/// failing in the middle of replaying it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ReplayRepr", into = "ReplayRepr"))]
+pub struct Replay {
+	decisions: Vec<Decision>,
+}
+
+impl Replay {
+	/// The version of the serialized form of a replay.
+	pub const FORMAT_VERSION: u32 = 1;
+
+	/// Checks that the decisions are consistent.
+	///
+	/// Since the number of branches of a call site is fixed, all decisions of
+	/// the same call site must have the same count, and each index must be
+	/// less than its count.
+	pub fn validate(&self) -> Result<(), InvalidReplay> {
+		let mut counts = BTreeMap::new();
+		for (position, decision) in self.decisions.iter().enumerate() {
+			if decision.index >= decision.count {
+				return Err(InvalidReplay::IndexOutOfRange {
+					position,
+					decision: decision.clone(),
+				});
+			}
+			let site = (&decision.file, decision.line, decision.column);
+			let count = *counts.entry(site).or_insert(decision.count);
+			if count != decision.count {
+				return Err(InvalidReplay::InconsistentCount {
+					position,
+					decision: decision.clone(),
+					count,
+				});
+			}
+		}
+		Ok(())
+	}
+
+	/// Returns the recorded decisions in order.
+	pub fn decisions(&self) -> &[Decision] {
+		&self.decisions
+	}
+
+	/// Returns the recorded decisions in order.
+	pub fn into_decisions(self) -> Vec<Decision> {
+		self.decisions
+	}
+
+	/// Starts replaying the decisions on the current thread.
+	///
+	/// While the returned guard is alive, every macro that would append a
+	/// decision to a [`Recorder`], instead takes the next recorded decision,
+	/// and selects its branch. The replay is strict: if a macro is invoked at
+	/// a different call site or with a different number of branches than the
+	/// next decision, or if all decisions have been taken already, the macro
+	/// panics with a message describing the mismatch. Thus, a replay only
+	/// works as long as the code under test, and its other inputs, are the
+	/// same as when it was recorded.
+	///
+	/// Installing a replay replaces any previous replay of the current
+	/// thread, which is restored when the new one is finished or dropped.
+	#[must_use = "Replaying stops when the guard is dropped"]
+	pub fn install(self) -> Replaying {
+		self.install_with(false)
+	}
+
+	/// Starts replaying the decisions leniently on the current thread.
+	///
+	/// This dose essentially the same as [`install`](Self::install), but
+	/// instead of panicking, a macro takes its first branch if all decisions
+	/// have been taken already. Further, if a macro doesn't match the next
+	/// decision, the replay ends early, so this and all following macros take
+	/// their first branch too. Thus, any prefix of a replay can be replayed,
+	/// as if the missing decisions had selected the first branch, which is
+	/// how the candidates of [`minimize`](Self::minimize) are meant to be
+	/// replayed. Also, [`Replaying::finish`] doesn't panic for a lenient
+	/// replay.
+	#[must_use = "Replaying stops when the guard is dropped"]
+	pub fn install_lenient(self) -> Replaying {
+		self.install_with(true)
+	}
+
+	/// Starts replaying the decisions on the current thread.
+	fn install_with(self, lenient: bool) -> Replaying {
+		let replayed = Replayed {
+			decisions: self.decisions,
+			position: 0,
+			lenient,
+		};
+		let previous = REPLAYING.with(|r| r.borrow_mut().replace(replayed));
+		Replaying {
+			previous: Some(previous),
+		}
+	}
+
+	/// Reduces a failing replay to a smaller one that still fails.
+	///
+	/// Given a replay of a failure, e.g. recorded in a long soak run, this
+	/// searches for a replay with fewer decisions, and fewer decisions that
+	/// don't select the first branch, for which `fails` still returns `true`.
+	/// Usually, only a handful of specific decisions cause the failure, which
+	/// are isolated this way. `fails` should run the code under test with the
+	/// given candidate installed via [`install_lenient`](Self::install_lenient),
+	/// so that the missing decisions select the first branch, and return
+	/// whether it failed, e.g. by catching a panic. Notice, that the failure
+	/// may be caused by another bug with a smaller replay, so `fails` should
+	/// check for the specific failure, if possible.
+	///
+	/// The reduction is greedy and deterministic: first, it searches for the
+	/// shortest failing prefix, assuming that longer prefixes fail too, then
+	/// it tries to reset ever smaller chunks of the remaining decisions to
+	/// their first branch, in the manner of delta debugging, and finally it
+	/// strips all trailing decisions of the first branch. It calls `fails` at
+	/// most `max_runs` times, and returns the smallest failing replay found
+	/// so far, which is `self` if none of the candidates failed.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// # #[cfg(not(feature = "deterministic"))] {
+	/// use random_branch::branch;
+	/// use random_branch::recorder::Recorder;
+	/// use random_branch::recorder::Replay;
+	///
+	/// /// Fails if the 10th and the 20th roll are both a six.
+	/// fn soak() -> bool {
+	///     let rolls: Vec<u32> = (0..100).map(|_| branch!(1, 2, 3, 4, 5, 6)).collect();
+	///     rolls[10] == 6 && rolls[20] == 6
+	/// }
+	///
+	/// // Record soak runs until one fails
+	/// let replay = loop {
+	///     let recorder = Recorder::install();
+	///     let failed = soak();
+	///     let replay = recorder.finish();
+	///     if failed {
+	///         break replay;
+	///     }
+	/// };
+	///
+	/// let minimal = replay.minimize(1000, |candidate| {
+	///     let _replaying = candidate.clone().install_lenient();
+	///     soak()
+	/// });
+	/// let indices: Vec<usize> = minimal.decisions().iter().map(|d| d.index).collect();
+	/// assert_eq!(indices.len(), 21);
+	/// assert_eq!(indices.iter().filter(|&&index| index != 0).count(), 2);
+	/// # }
+	/// ```
+	pub fn minimize(&self, max_runs: usize, mut fails: impl FnMut(&Replay) -> bool) -> Replay {
+		let mut runs = 0;
+		let mut try_candidate = |candidate: &Replay| {
+			if runs >= max_runs {
+				return false;
+			}
+			runs += 1;
+			fails(candidate)
+		};
+		let mut best = self.clone();
+
+		// Search the shortest failing prefix
+		let mut chunk = best.decisions.len() / 2;
+		while chunk > 0 {
+			let candidate = Replay::from(best.decisions[..best.decisions.len() - chunk].to_vec());
+			if try_candidate(&candidate) {
+				best = candidate;
+				chunk = chunk.min(best.decisions.len() / 2);
+			} else {
+				chunk /= 2;
+			}
+		}
+
+		// Reset chunks of the remaining decisions to their first branch
+		let mut chunk = (best.decisions.iter().filter(|d| d.index != 0).count() / 2).max(1);
+		loop {
+			let positions: Vec<usize> = (0..best.decisions.len())
+				.filter(|&position| best.decisions[position].index != 0)
+				.collect();
+			if positions.is_empty() {
+				break;
+			}
+			let mut reduced = false;
+			for group in positions.chunks(chunk) {
+				let mut candidate = best.clone();
+				for &position in group {
+					candidate.decisions[position].index = 0;
+				}
+				if try_candidate(&candidate) {
+					best = candidate;
+					reduced = true;
+				}
+			}
+			if !reduced {
+				if chunk == 1 {
+					break;
+				}
+				chunk /= 2;
+			}
+		}
+
+		// Missing decisions select the first branch anyway
+		while best.decisions.last().is_some_and(|d| d.index == 0) {
+			best.decisions.pop();
+		}
+		best
+	}
+}
+
+impl From<Vec<Decision>> for Replay {
+	fn from(decisions: Vec<Decision>) -> Self {
+		Self {
+			decisions,
+		}
+	}
+}
+
+
+/// The error of an invalid [`Replay`], see [`Replay::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidReplay {
+	/// The replay was serialized in a newer, unsupported format.
+	UnsupportedVersion(u32),
+	/// The index of a decision is not less than its count.
+	IndexOutOfRange {
+		/// The position of the decision within the replay.
+		position: usize,
+		/// The invalid decision.
+		decision: Decision,
+	},
+	/// A decision has a different count than an earlier decision of the same
+	/// call site.
+	InconsistentCount {
+		/// The position of the decision within the replay.
+		position: usize,
+		/// The inconsistent decision.
+		decision: Decision,
+		/// The count of the earlier decisions of the same call site.
+		count: usize,
+	},
+}
+
+impl fmt::Display for InvalidReplay {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::UnsupportedVersion(version) => write!(
+				f,
+				"unsupported replay format version {}, expected at most {}",
+				version,
+				Replay::FORMAT_VERSION,
+			),
+			Self::IndexOutOfRange { position, decision } => write!(
+				f,
+				"decision {} is out of range: {}",
+				position, decision,
+			),
+			Self::InconsistentCount { position, decision, count } => write!(
+				f,
+				"decision {} has an inconsistent count: {}, but earlier decisions of this call site have {} branches",
+				position, decision, count,
+			),
+		}
+	}
+}
+
+impl std::error::Error for InvalidReplay {}
+
+
+/// The serialized form of a [`Replay`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ReplayRepr {
+	version: u32,
+	decisions: Vec<Decision>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ReplayRepr> for Replay {
+	type Error = InvalidReplay;
+
+	fn try_from(repr: ReplayRepr) -> Result<Self, InvalidReplay> {
+		if repr.version > Self::FORMAT_VERSION {
+			return Err(InvalidReplay::UnsupportedVersion(repr.version));
+		}
+		let replay = Self {
+			decisions: repr.decisions,
+		};
+		replay.validate()?;
+		Ok(replay)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl From<Replay> for ReplayRepr {
+	fn from(replay: Replay) -> Self {
+		Self {
+			version: Replay::FORMAT_VERSION,
+			decisions: replay.decisions,
+		}
+	}
+}
+
+
+/// The guard of an installed [`Replay`], see [`Replay::install`].
+#[derive(Debug)]
+pub struct Replaying {
+	previous: Option<Option<Replayed>>,
+}
+
+impl Replaying {
+	/// Stops replaying.
+	///
+	/// # Panics
+	///
+	/// Panics if not all decisions have been replayed, unless the replay is
+	/// lenient.
+	#[track_caller]
+	pub fn finish(mut self) {
+		if let Some(replayed) = self.restore() {
+			match replayed.decisions.get(replayed.position) {
+				Some(next) if !replayed.lenient => panic!(
+					"The replay has {} decisions left, the next is {}",
+					replayed.decisions.len() - replayed.position,
+					next,
+				),
+				_ => {},
+			}
+		}
+	}
+
+	/// Restores the previous replay, and returns the own state.
+	fn restore(&mut self) -> Option<Replayed> {
+		let previous = self.previous.take()?;
+		REPLAYING.with(|r| core::mem::replace(&mut *r.borrow_mut(), previous))
+	}
+}
+
+impl Drop for Replaying {
+	fn drop(&mut self) {
+		self.restore();
+	}
+}
+
+
+/// Takes the next decision of the replay, if one is installed.
+#[track_caller]
+pub(crate) fn replay(count: usize) -> Option<usize> {
+	let location = Location::caller();
+	REPLAYING.with(|r| {
+		let mut r = r.borrow_mut();
+		let replayed = r.as_mut()?;
+		let position = replayed.position;
+		let decision = match replayed.decisions.get(position) {
+			Some(decision) => decision,
+			None if replayed.lenient => return Some(0),
+			None => panic!(
+				"The replay is exhausted after {} decisions, but there is another selection among {} branches at {}",
+				position, count, location,
+			),
+		};
+		if !decision.matches(location, count) {
+			if replayed.lenient {
+				replayed.position = replayed.decisions.len();
+				return Some(0);
+			}
+			panic!(
+				"The replay diverged at decision {}: expected {}, but got a selection among {} branches at {}",
+				position, decision, count, location,
+			);
+		}
+		replayed.position += 1;
+		Some(decision.index)
+	})
+}
+
+/// Appends a decision to the recorder, if one is installed.
+#[track_caller]
+pub(crate) fn record(count: usize, index: usize) {
+	let location = Location::caller();
+	RECORDING.with(|r| {
+		if let Some(decisions) = r.borrow_mut().as_mut() {
+			decisions.push(Decision {
+				file: Cow::Borrowed(location.file()),
+				line: location.line(),
+				column: location.column(),
+				count,
+				index,
+			});
+		}
+	})
+}
@@ -0,0 +1,37 @@
+//! Runtime support for [`shuffle_branches_resilient_using`](crate::shuffle_branches_resilient_using).
+
+use core::any::Any;
+use core::panic::AssertUnwindSafe;
+use std::boxed::Box;
+use std::panic::catch_unwind;
+use std::vec::Vec;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+
+/// The panic payloads of all failed branches, tagged by their branch index.
+pub type Failures = Vec<(usize, Box<dyn Any + Send + 'static>)>;
+
+
+/// Runs `run` once for every index in `0..count` in a random order, catching
+/// any panic, and returns the payloads of all panicked indices in the order
+/// they were run.
+pub fn run_shuffled<R: Rng + ?Sized>(
+	rng: &mut R,
+	count: usize,
+	mut run: impl FnMut(usize),
+) -> Failures {
+	let mut order: Vec<usize> = (0..count).collect();
+	if !crate::select::DETERMINISTIC {
+		order.shuffle(rng);
+	}
+
+	let mut failures = Vec::new();
+	for index in order {
+		if let Err(payload) = catch_unwind(AssertUnwindSafe(|| run(index))) {
+			failures.push((index, payload));
+		}
+	}
+	failures
+}
@@ -0,0 +1,40 @@
+//! The branch result of [`branch_retry_using`](crate::branch_retry_using).
+
+use rand::Rng;
+
+use crate::choose_index;
+
+
+/// The result of a branch that may decline to be selected.
+///
+/// This is the type that each branch of
+/// [`branch_retry_using`](crate::branch_retry_using) must evaluate to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pick<T> {
+	/// The branch applies, and yields this value.
+	Take(T),
+	/// The branch does not apply, so another one should be selected.
+	Pass,
+}
+
+
+/// Removes and returns a uniformly chosen index of the first `left` elements
+/// of `remaining`.
+///
+/// The chosen element is swapped to the end of the first `left` elements, and
+/// `left` is decremented, so it won't be chosen again.
+///
+/// With the `deterministic` feature, the first of the `left` elements is
+/// taken instead, keeping the others in order.
+pub fn take_random<R: Rng + ?Sized>(remaining: &mut [usize], left: &mut usize, rng: &mut R) -> usize {
+	if crate::select::DETERMINISTIC {
+		remaining[..*left].rotate_left(1);
+		*left -= 1;
+		return remaining[*left];
+	}
+
+	let i = choose_index(rng, *left);
+	*left -= 1;
+	remaining.swap(i, *left);
+	remaining[*left]
+}
@@ -0,0 +1,103 @@
+//! The selection of [`branch_secure`](crate::branch_secure) and
+//! [`try_branch_secure`](crate::try_branch_secure).
+
+use rand::RngCore;
+
+
+/// Selects a uniformly distributed index in `0..count` from the OS RNG, or
+/// from the source set via
+#[cfg_attr(feature = "test-override", doc = "[`with_secure_rng`](crate::test_override::with_secure_rng).")]
+#[cfg_attr(not(feature = "test-override"), doc = "`with_secure_rng`.")]
+///
+/// Unlike the other macros, this selection bypasses all hooks, i.e. the
+/// `deterministic` feature, forced and explored branches, and the replay of
+/// the `recorder`, since any crate in the build may enable those features.
+/// The thread-local source of `with_secure_rng` is the only replacement.
+///
+/// Returns the error of the source if it fails.
+pub fn try_select(count: usize) -> Result<usize, rand::Error> {
+	#[cfg(feature = "test-override")]
+	if let Some(result) = crate::test_override::secure_rng(|rng| uniform(rng, count)) {
+		return result;
+	}
+	uniform(&mut rand::rngs::OsRng, count)
+}
+
+/// Selects a uniformly distributed index in `0..count` from the OS RNG.
+///
+/// # Panics
+///
+/// Panics if the OS RNG fails.
+#[track_caller]
+pub fn select(count: usize) -> usize {
+	match try_select(count) {
+		Ok(index) => index,
+		Err(err) => panic!("The OS RNG failed: {}", err),
+	}
+}
+
+/// Draws a uniformly distributed index in `0..count` via `try_fill_bytes`.
+///
+/// Each attempt fetches a fresh `u64`, which is rejected if it falls into the
+/// incomplete last interval of `count` values, so the result is exactly
+/// uniform, and nothing is kept between calls.
+fn uniform<R: RngCore + ?Sized>(rng: &mut R, count: usize) -> Result<usize, rand::Error> {
+	assert!(count > 0, "You must provide at least one choice.");
+	let count = count as u64;
+	// `2^64 % count`, the number of values to reject
+	let rejected = (u64::MAX % count + 1) % count;
+	loop {
+		let mut bytes = [0; 8];
+		rng.try_fill_bytes(&mut bytes)?;
+		let value = u64::from_le_bytes(bytes);
+		if value <= u64::MAX - rejected {
+			return Ok((value % count) as usize);
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Yields the given values, then fails.
+	struct Script<'a>(&'a [u64]);
+
+	impl RngCore for Script<'_> {
+		fn next_u32(&mut self) -> u32 {
+			panic!("Only try_fill_bytes is scripted.")
+		}
+
+		fn next_u64(&mut self) -> u64 {
+			panic!("Only try_fill_bytes is scripted.")
+		}
+
+		fn fill_bytes(&mut self, _dest: &mut [u8]) {
+			panic!("Only try_fill_bytes is scripted.")
+		}
+
+		fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+			let (first, rest) = self.0.split_first().ok_or_else(|| rand::Error::new("exhausted"))?;
+			dest.copy_from_slice(&first.to_le_bytes());
+			self.0 = rest;
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn rejects_incomplete_interval() {
+		// 2^64 = 3 * 6148914691236517205 + 1, so only `u64::MAX` is rejected
+		assert_eq!(uniform(&mut Script(&[u64::MAX, 5]), 3).unwrap(), 2);
+		assert_eq!(uniform(&mut Script(&[u64::MAX - 1]), 3).unwrap(), 2);
+		// Powers of two reject nothing
+		assert_eq!(uniform(&mut Script(&[u64::MAX]), 4).unwrap(), 3);
+		assert_eq!(uniform(&mut Script(&[u64::MAX]), 1).unwrap(), 0);
+	}
+
+	#[test]
+	fn propagates_errors() {
+		assert!(uniform(&mut Script(&[]), 3).is_err());
+		assert!(uniform(&mut Script(&[u64::MAX]), 3).is_err());
+	}
+}
@@ -0,0 +1,118 @@
+//! The generator of [`branch_seeded`](crate::branch_seeded).
+
+use rand::RngCore;
+use rand::SeedableRng;
+use rand_pcg::Lcg64Xsh32;
+
+
+/// A cheap, portable RNG for reproducible branches.
+///
+/// This is the generator used by [`branch_seeded`](crate::branch_seeded),
+/// and it can be used as a state to draw a whole reproducible sequence of
+/// branches via [`branch_seeded_with`](crate::branch_seeded_with).
+///
+/// The generator is [`Lcg64Xsh32`] (also known as `Pcg32`) from the
+/// `rand_pcg` crate, seeded via [`SeedableRng::seed_from_u64`]. The branch
+/// index is derived by [`select`](Self::select) from a single `u64` (or a few
+/// on rare rejections) with Lemire's unbiased multiply-and-reject method,
+/// which doesn't depend on the implementation details of [`rand`]'s range
+/// sampling, nor on the pointer width of the target. Thus, the branches
+/// selected for a given seed and branch count are stable across releases of
+/// this crate and across platforms, and changing them is considered a
+/// breaking change.
+///
+/// This type also implements [`RngCore`], so it can be passed to all other
+/// macros too, however, only [`select`](Self::select) gives the above
+/// stability guarantee.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "pcg")))]
+pub struct SeededRng(Lcg64Xsh32);
+
+impl SeededRng {
+	/// Creates the generator from the given seed.
+	pub fn new(seed: u64) -> Self {
+		Self(Lcg64Xsh32::seed_from_u64(seed))
+	}
+
+	/// Selects an index in `0..count` uniformly, in a portable way.
+	///
+	/// # Panics
+	///
+	/// Panics if `count` is zero.
+	#[track_caller]
+	pub fn select(&mut self, count: usize) -> usize {
+		assert!(count > 0, "You must provide at least one choice.");
+
+		let n = count as u64;
+		let threshold = n.wrapping_neg() % n;
+		loop {
+			let product = u128::from(self.0.next_u64()) * u128::from(n);
+			if product as u64 >= threshold {
+				return (product >> 64) as usize;
+			}
+		}
+	}
+}
+
+impl RngCore for SeededRng {
+	fn next_u32(&mut self) -> u32 {
+		self.0.next_u32()
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0.next_u64()
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		self.0.fill_bytes(dest)
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.0.try_fill_bytes(dest)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn golden_indices() {
+		let golden: [(u64, usize, usize); 12] = [
+			(0, 2, 1), (0, 3, 2), (0, 7, 6), (0, 100, 98),
+			(1, 2, 0), (1, 3, 1), (1, 7, 3), (1, 100, 49),
+			(42, 2, 1), (42, 3, 2), (42, 7, 5), (42, 100, 77),
+		];
+		for &(seed, count, index) in &golden {
+			assert_eq!(SeededRng::new(seed).select(count), index, "seed {} count {}", seed, count);
+		}
+	}
+
+	#[test]
+	fn golden_sequence() {
+		let mut rng = SeededRng::new(42);
+		let sequence: [usize; 10] = core::array::from_fn(|_| rng.select(3));
+		assert_eq!(sequence, [2, 2, 1, 0, 0, 1, 1, 2, 0, 0]);
+	}
+
+	#[test]
+	fn seeds_give_different_sequences() {
+		let run = |seed| {
+			let mut rng = SeededRng::new(seed);
+			core::array::from_fn::<usize, 20, _>(|_| rng.select(3))
+		};
+		assert_eq!(run(7), run(7));
+		for seed in 0..10 {
+			assert_ne!(run(seed), run(seed + 1));
+		}
+	}
+
+	#[test]
+	fn single_choice() {
+		let mut rng = SeededRng::new(42);
+		for _ in 0..10 {
+			assert_eq!(rng.select(1), 0);
+		}
+	}
+}
@@ -0,0 +1,267 @@
+//! The index selection of [`branch_using`](crate::branch_using) and friends.
+//!
+//! All macros that select a single branch with a fresh draw go through this
+//! module, so that the optional hooks, such as the `test_override` module,
+//! see every selection.
+//! Without any of those features, these functions are trivial wrappers, which
+//! are inlined away.
+
+#[cfg(any(feature = "rand", feature = "fastrand", feature = "nanorand"))]
+use core::convert::Infallible;
+#[cfg(feature = "rand")]
+use core::ops::Bound;
+#[cfg(feature = "rand")]
+use core::ops::RangeBounds;
+
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+#[cfg(feature = "rand")]
+use crate::choose_index;
+
+#[cfg(feature = "coverage")]
+pub use crate::coverage::Site;
+
+
+/// Whether the `deterministic` feature is enabled, in which case the macros
+/// select the first branch without evaluating their RNG expression, unless
+/// the branch is forced, explored, or replayed.
+///
+/// The macros test this constant instead of the feature, since a `cfg` in a
+/// macro would be evaluated in the crate using it.
+pub const DETERMINISTIC: bool = cfg!(feature = "deterministic");
+
+
+/// The maximum number of branches of a single macro invocation.
+///
+/// The macros count their branches as a `usize` constant, and some keep a
+/// table with an entry per branch, e.g. the counters of the `coverage`
+/// feature, which must not exceed `isize::MAX` bytes. Thus, the limit is
+/// `isize::MAX / size_of::<usize>()`, i.e. 16383 branches on targets with a
+/// 16-bit `usize`, such as AVR or MSP430, and practically unlimited
+/// otherwise. The macros check this limit at compile time.
+pub const MAX_BRANCHES: usize = isize::MAX as usize / core::mem::size_of::<usize>();
+
+
+/// The counters of a call site, which are empty without the `coverage`
+/// feature, besides the location to log with the `tracing` or `log` feature.
+#[cfg(not(feature = "coverage"))]
+#[derive(Debug)]
+pub struct Site<const N: usize> {
+	#[cfg(any(feature = "tracing", feature = "log"))]
+	file: &'static str,
+	#[cfg(any(feature = "tracing", feature = "log"))]
+	line: u32,
+	#[cfg(any(feature = "tracing", feature = "log"))]
+	column: u32,
+	#[cfg(any(feature = "tracing", feature = "log"))]
+	label: Option<&'static str>,
+}
+
+#[cfg(not(feature = "coverage"))]
+impl<const N: usize> Site<N> {
+	/// Creates the empty counters.
+	#[cfg_attr(not(any(feature = "tracing", feature = "log")), allow(unused_variables))]
+	pub const fn new(file: &'static str, line: u32, column: u32) -> Self {
+		Self {
+			#[cfg(any(feature = "tracing", feature = "log"))]
+			file,
+			#[cfg(any(feature = "tracing", feature = "log"))]
+			line,
+			#[cfg(any(feature = "tracing", feature = "log"))]
+			column,
+			#[cfg(any(feature = "tracing", feature = "log"))]
+			label: None,
+		}
+	}
+
+	/// Attaches the given label, which is logged with the `tracing` or `log`
+	/// feature.
+	#[cfg_attr(not(any(feature = "tracing", feature = "log")), allow(unused_variables))]
+	pub const fn with_label(self, label: &'static str) -> Self {
+		#[cfg(any(feature = "tracing", feature = "log"))]
+		return Self { label: Some(label), ..self };
+		#[cfg(not(any(feature = "tracing", feature = "log")))]
+		self
+	}
+
+	/// Does nothing, besides logging the selection with the `tracing` or `log`
+	/// feature.
+	#[inline(always)]
+	#[cfg_attr(not(any(feature = "tracing", feature = "log")), allow(unused_variables))]
+	pub fn hit(&'static self, index: usize) {
+		#[cfg(any(feature = "tracing", feature = "log"))]
+		crate::trace::selected(self.file, self.line, self.column, self.label, index, N);
+	}
+}
+
+
+/// Selects a uniformly distributed index in `0..count`.
+#[cfg(feature = "rand")]
+#[inline]
+#[track_caller]
+pub fn uniform<R: Rng + ?Sized>(rng: &mut R, count: usize) -> usize {
+	hooked(count, || choose_index(rng, count))
+}
+
+/// Selects a uniformly distributed index within `range`, which must be
+/// within `0..count`.
+///
+/// # Panics
+///
+/// Panics if the range is empty or not within `0..count`.
+#[cfg(feature = "rand")]
+#[inline]
+#[track_caller]
+pub fn subset<R: Rng + ?Sized, B: RangeBounds<usize>>(rng: &mut R, range: B, count: usize) -> usize {
+	let start = match range.start_bound() {
+		Bound::Included(&start) => start,
+		Bound::Excluded(&start) => start.saturating_add(1),
+		Bound::Unbounded => 0,
+	};
+	let end = match range.end_bound() {
+		Bound::Included(&end) => end.saturating_add(1),
+		Bound::Excluded(&end) => end,
+		Bound::Unbounded => count,
+	};
+	if start >= end || end > count {
+		panic!(
+			"The branch range {}..{} must be non-empty and within 0..{}",
+			start, end, count,
+		);
+	}
+	start + uniform(rng, end - start)
+}
+
+/// Selects a uniformly distributed index in `0..count`, by masking a single
+/// `u32` if `count` is a power of two.
+#[cfg(feature = "rand")]
+#[inline]
+#[track_caller]
+pub fn fast<R: Rng + ?Sized>(rng: &mut R, count: usize) -> usize {
+	hooked(count, || {
+		if count.is_power_of_two() && count as u64 <= 1 << 32 {
+			(rng.gen::<u32>() as usize) & (count - 1)
+		} else {
+			choose_index(rng, count)
+		}
+	})
+}
+
+/// Selects a uniformly distributed index in `0..count` via
+/// [`generate_range`](nanorand::Rng::generate_range) of a `nanorand` RNG.
+#[cfg(feature = "nanorand")]
+#[inline]
+#[track_caller]
+pub fn nanorand_uniform<R: nanorand::Rng<N>, const N: usize>(rng: &mut R, count: usize) -> usize {
+	hooked(count, || {
+		assert!(count > 0, "You must provide at least one choice.");
+		rng.generate_range(0..count)
+	})
+}
+
+/// Selects a uniformly distributed index in `0..count` from the backend of
+/// [`branch`](crate::branch), see the [crate-level documentation](crate#backends).
+#[cfg(any(
+	feature = "std",
+	feature = "global-rng",
+	feature = "getrandom",
+	feature = "fastrand",
+	all(feature = "js-random", target_arch = "wasm32"),
+))]
+#[inline]
+#[track_caller]
+pub fn backend(count: usize) -> usize {
+	#[cfg(all(feature = "fastrand", not(any(feature = "std", feature = "global-rng", feature = "getrandom"))))]
+	return fastrand_uniform(&mut crate::FastrandGlobal, count);
+	#[cfg(not(all(feature = "fastrand", not(any(feature = "std", feature = "global-rng", feature = "getrandom")))))]
+	return uniform(&mut crate::DefaultBranchRng, count);
+}
+
+/// A `fastrand` generator to select an index from, i.e. a [`fastrand::Rng`],
+/// or the thread-local generator of `fastrand`.
+#[cfg(feature = "fastrand")]
+pub trait FastrandIndex {
+	/// Returns a uniformly distributed index in `0..count`, which must not be
+	/// empty.
+	fn index(&mut self, count: usize) -> usize;
+}
+
+/// Selects a uniformly distributed index in `0..count` via
+/// [`usize`](fastrand::Rng::usize) of a `fastrand` generator.
+#[cfg(feature = "fastrand")]
+#[inline]
+#[track_caller]
+pub fn fastrand_uniform<R: FastrandIndex + ?Sized>(rng: &mut R, count: usize) -> usize {
+	hooked(count, || {
+		assert!(count > 0, "You must provide at least one choice.");
+		rng.index(count)
+	})
+}
+
+/// Selects the first index, i.e. `0`, unless a hook selects another one,
+/// which is the selection of the macros with the `deterministic` feature.
+///
+/// Unlike the other functions of this module, it needs no RNG, so that the
+/// macros don't evaluate their RNG expression.
+#[cfg(any(feature = "rand", feature = "fastrand", feature = "nanorand"))]
+#[inline]
+#[track_caller]
+pub fn first(count: usize) -> usize {
+	hooked(count, || 0)
+}
+
+/// Selects an index in `0..count` via `draw`, unless a hook selects it.
+#[cfg(any(feature = "rand", feature = "fastrand", feature = "nanorand"))]
+#[inline(always)]
+#[track_caller]
+fn hooked(count: usize, draw: impl FnOnce() -> usize) -> usize {
+	match try_hooked(count, || Ok::<_, Infallible>(draw())) {
+		Ok(index) => index,
+		Err(never) => match never {},
+	}
+}
+
+/// Selects an index in `0..count` via the fallible `draw`, unless a hook
+/// selects it.
+///
+/// A failed draw is neither replayed nor recorded by the `recorder`.
+#[cfg(any(feature = "rand", feature = "fastrand", feature = "nanorand"))]
+#[inline(always)]
+#[track_caller]
+#[cfg_attr(
+	not(any(feature = "test-override", feature = "recorder", all(feature = "verification", kani))),
+	allow(unused_variables)
+)]
+#[cfg_attr(all(feature = "verification", kani), allow(unreachable_code))]
+pub(crate) fn try_hooked<E>(count: usize, draw: impl FnOnce() -> Result<usize, E>) -> Result<usize, E> {
+	#[cfg(all(feature = "verification", kani))]
+	if !DETERMINISTIC {
+		return Ok(crate::verification::nondet_index(count));
+	}
+
+	#[cfg(feature = "test-override")]
+	if let Some(index) = crate::test_override::next(count) {
+		return Ok(index);
+	}
+	#[cfg(feature = "test-override")]
+	if let Some(index) = crate::explore::next(count) {
+		return Ok(index);
+	}
+
+	// The `deterministic` feature only replaces the draw, so explicitly forced,
+	// explored, and replayed branches take precedence over it
+	#[cfg(feature = "recorder")]
+	let index = match crate::recorder::replay(count) {
+		Some(index) => index,
+		None if DETERMINISTIC => 0,
+		None => draw()?,
+	};
+	#[cfg(not(feature = "recorder"))]
+	let index = if DETERMINISTIC { 0 } else { draw()? };
+
+	#[cfg(feature = "recorder")]
+	crate::recorder::record(count, index);
+
+	Ok(index)
+}
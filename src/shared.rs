@@ -0,0 +1,98 @@
+//! RNG adapters for generators shared via interior mutability.
+
+#[cfg(feature = "alloc")]
+use alloc::rc::Rc;
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::sync::PoisonError;
+
+use rand::RngCore;
+
+
+/// Wraps a shared RNG, borrowing it just for each draw.
+///
+/// An RNG that is shared, e.g. across a tree of widgets, usually lives in a
+/// [`RefCell`] or a [`Mutex`](std::sync::Mutex). This adapter implements
+/// [`RngCore`] for `SharedRng<&RefCell<R>>`, `SharedRng<Rc<RefCell<R>>>`
+/// (with `alloc`), and `SharedRng<&Mutex<R>>` (with `std`), by borrowing or
+/// locking the RNG only within each call, so it can be passed to
+/// [`branch_using`](crate::branch_using) and all other macros of this crate
+/// instead of a borrowed RNG.
+///
+/// Since the macros select the branch before evaluating it, the borrow or
+/// lock is released before the branch runs. Thus, the branch can use the
+/// shared RNG again, e.g. to branch once more, without panicking because of a
+/// double borrow or deadlocking on the lock.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch_using;
+/// use random_branch::SharedRng;
+///
+/// let shared = Rc::new(RefCell::new(Lcg64Xsh32::new(42, 0)));
+///
+/// let label = branch_using!(SharedRng::new(shared.clone()), {
+///     "plain",
+///     // Uses the shared RNG again, while the outer branch is running
+///     branch_using!(SharedRng::new(&*shared), { "bold", "italic" }),
+/// });
+/// # assert!(["plain", "bold", "italic"].contains(&label));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SharedRng<T>(T);
+
+impl<T> SharedRng<T> {
+	/// Wraps the given shared RNG.
+	pub fn new(shared: T) -> Self {
+		Self(shared)
+	}
+
+	/// Returns the wrapped shared RNG.
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+/// Implements `RngCore` for a `SharedRng<$shared>`, given how to get a
+/// short-lived mutable borrow of the RNG.
+macro_rules! impl_shared {
+	( $shared:ty, |$this:ident| $borrow:expr ) => {
+		impl<R: RngCore> RngCore for SharedRng<$shared> {
+			fn next_u32(&mut self) -> u32 {
+				let $this = &self.0;
+				$borrow.next_u32()
+			}
+
+			fn next_u64(&mut self) -> u64 {
+				let $this = &self.0;
+				$borrow.next_u64()
+			}
+
+			fn fill_bytes(&mut self, dest: &mut [u8]) {
+				let $this = &self.0;
+				$borrow.fill_bytes(dest)
+			}
+
+			fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+				let $this = &self.0;
+				$borrow.try_fill_bytes(dest)
+			}
+		}
+	};
+}
+
+impl_shared!(&RefCell<R>, |shared| shared.borrow_mut());
+
+#[cfg(feature = "alloc")]
+impl_shared!(Rc<RefCell<R>>, |shared| shared.borrow_mut());
+
+// A poisoned RNG is still a valid RNG, so the poison is ignored
+#[cfg(feature = "std")]
+impl_shared!(&Mutex<R>, |shared| shared.lock().unwrap_or_else(PoisonError::into_inner));
@@ -0,0 +1,41 @@
+//! Fixed-capacity collections for [`branch_fill_using`](crate::branch_fill_using).
+
+
+/// A collection with a bounded capacity that values can be pushed into.
+///
+/// This trait is used by [`branch_fill_using`](crate::branch_fill_using) to
+/// fill a collection with random branches without allocating.
+///
+/// It is implemented for `heapless::Vec` with the `heapless` feature and for
+/// `arrayvec::ArrayVec` with the `arrayvec` feature.
+pub trait PushSink<T> {
+	/// Returns `true` if no further value can be pushed.
+	fn is_full(&self) -> bool;
+
+	/// Pushes the given value, or returns it if the collection is full.
+	fn try_push(&mut self, value: T) -> Result<(), T>;
+}
+
+#[cfg(feature = "heapless")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "heapless")))]
+impl<T, const N: usize> PushSink<T> for heapless::Vec<T, N> {
+	fn is_full(&self) -> bool {
+		heapless::Vec::is_full(self)
+	}
+
+	fn try_push(&mut self, value: T) -> Result<(), T> {
+		self.push(value)
+	}
+}
+
+#[cfg(feature = "arrayvec")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "arrayvec")))]
+impl<T, const N: usize> PushSink<T> for arrayvec::ArrayVec<T, N> {
+	fn is_full(&self) -> bool {
+		arrayvec::ArrayVec::is_full(self)
+	}
+
+	fn try_push(&mut self, value: T) -> Result<(), T> {
+		arrayvec::ArrayVec::try_push(self, value).map_err(|e| e.element())
+	}
+}
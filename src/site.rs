@@ -0,0 +1,148 @@
+//! The call-site-isolated RNG streams of [`branch_site`](crate::branch_site).
+
+use alloc::collections::BTreeMap;
+
+use rand::RngCore;
+
+use crate::bench::fill_bytes_via_u64;
+use crate::bench::mix;
+use crate::bench::next;
+use crate::bench::GOLDEN_GAMMA;
+use crate::key_hash;
+use crate::StableKey;
+
+
+/// A master seed, from which each call site derives its own RNG stream.
+///
+/// With a single seeded RNG shared by many call sites, each call consumes
+/// some of the sequence, so inserting or removing a call anywhere shifts the
+/// decisions of all subsequent calls. Instead, this type derives an
+/// independent stream for each call site from the master seed and a stable
+/// identifier of the call site, see [`stream`](Self::stream), so the
+/// decisions of each call site only depend on the seed, its identifier, and
+/// how often it was called before.
+///
+/// The streams are SplitMix64 generators, which are started from the mixed
+/// master seed and identifier, the latter being hashed by [`key_hash`]. Thus,
+/// the streams are the same on all platforms and in all versions of this
+/// crate, however, the branch selected from a stream is only as stable as
+/// the uniform range sampling of [`rand`].
+///
+/// This type requires the `alloc` feature, since it keeps the state of each
+/// stream.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch_site;
+/// use random_branch::SiteRng;
+///
+/// let mut master = SiteRng::new(42);
+/// let first = branch_site!(master, "first", { 1, 2, 3 });
+///
+/// let mut other = SiteRng::new(42);
+/// let _inserted = branch_site!(other, "inserted", { 'a', 'b' });
+/// assert_eq!(branch_site!(other, "first", { 1, 2, 3 }), first);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "alloc")))]
+pub struct SiteRng {
+	/// The master seed.
+	seed: u64,
+	/// The state of each stream that has been used, by its hashed identifier.
+	streams: BTreeMap<u64, u64>,
+}
+
+impl SiteRng {
+	/// Creates the master from the given seed.
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			streams: BTreeMap::new(),
+		}
+	}
+
+	/// Returns the master seed.
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	/// Returns the stream of the call site with the given identifier.
+	///
+	/// The first time an identifier is used, its stream starts from the mixed
+	/// seed and identifier, afterwards, it continues where it left off.
+	pub fn stream<K: StableKey + ?Sized>(&mut self, site: &K) -> SiteStream<'_> {
+		let id = key_hash(site);
+		let seed = self.seed;
+		let state = self
+			.streams
+			.entry(id)
+			.or_insert_with(|| mix(seed ^ mix(id.wrapping_add(GOLDEN_GAMMA))));
+		SiteStream { state }
+	}
+}
+
+
+/// The RNG stream of a single call site, see [`SiteRng::stream`].
+#[derive(Debug)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "alloc")))]
+pub struct SiteStream<'a> {
+	state: &'a mut u64,
+}
+
+impl RngCore for SiteStream<'_> {
+	fn next_u32(&mut self) -> u32 {
+		(self.next_u64() >> 32) as u32
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		next(self.state)
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		fill_bytes_via_u64(self, dest)
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn splitmix_reference() {
+		// The first outputs of SplitMix64 seeded with 0, with a state of 0
+		let mut state = 0;
+		let mut stream = SiteStream { state: &mut state };
+		assert_eq!(stream.next_u64(), 0xe220_a839_7b1d_cdaf);
+		assert_eq!(stream.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+	}
+
+	#[test]
+	fn streams_are_independent() {
+		let mut master = SiteRng::new(7);
+		let a: [u64; 3] = core::array::from_fn(|_| master.stream("a").next_u64());
+
+		let mut interleaved = SiteRng::new(7);
+		let mut b = [0; 3];
+		for x in &mut b {
+			interleaved.stream("b").next_u64();
+			*x = interleaved.stream("a").next_u64();
+		}
+		assert_eq!(a, b);
+		assert_ne!(master.stream("a").next_u64(), master.stream("c").next_u64());
+	}
+
+	#[test]
+	fn seed_matters() {
+		let mut one = SiteRng::new(1);
+		let mut two = SiteRng::new(2);
+		assert_ne!(one.stream("a").next_u64(), two.stream("a").next_u64());
+		assert_eq!(one.seed(), 1);
+	}
+}
@@ -0,0 +1,98 @@
+//! The state of [`branch_stratified_using`](crate::branch_stratified_using).
+
+use rand::Rng;
+
+use crate::choose_index;
+
+
+/// Stratifies the selections among `N` branches over a stream of selections.
+///
+/// This is the state used by
+/// [`branch_stratified_using`](crate::branch_stratified_using). Instead of
+/// drawing each branch independently, it keeps a credit per branch, which
+/// grows by the branch's share on every selection. The branch with the most
+/// credit is selected, and its credit is reduced by the total of all shares.
+/// Ties are broken uniformly at random using the given RNG.
+///
+/// As a result, the number of times each branch has been selected so far
+/// always tracks its expected number closely: after any number of
+/// selections, each count is less than one selection away from its
+/// expectation. So in particular, after every multiple of the total of all
+/// shares, each count is exact. With equal shares, this means that each
+/// round of `N` selections is a random permutation of all `N` branches.
+///
+/// Notice that successive selections are therefore *not* independent, e.g.
+/// with equal shares, the branch selected last in a round is fully determined
+/// by the others. Only the order within the rounds is random, and only as
+/// far as ties occur, which is always the case for equal shares. In exchange,
+/// the long-run frequencies are exact, which makes Monte-Carlo style
+/// simulations converge much faster than with independent draws.
+#[derive(Debug, Clone)]
+pub struct StratifiedPicker<const N: usize> {
+	credits: [i64; N],
+}
+
+impl<const N: usize> StratifiedPicker<N> {
+	/// Creates a new picker, where no branch has been selected yet.
+	pub const fn new() -> Self {
+		Self {
+			credits: [0; N],
+		}
+	}
+
+	/// Forgets all previous selections.
+	pub fn reset(&mut self) {
+		self.credits = [0; N];
+	}
+
+	/// Selects the index of one of the `N` branches, each with equal shares.
+	///
+	/// # Panics
+	///
+	/// Panics if `N` is zero.
+	#[track_caller]
+	pub fn select<R: Rng + ?Sized>(&mut self, rng: &mut R) -> usize {
+		self.select_weighted(&[1; N], rng)
+	}
+
+	/// Selects the index of one of the `N` branches, with shares proportional
+	/// to the given weights.
+	///
+	/// The weights should be the same for all selections of a stream,
+	/// otherwise the frequencies only follow the weights approximately.
+	///
+	/// # Panics
+	///
+	/// Panics if all weights are zero, which includes `N` being zero.
+	#[track_caller]
+	pub fn select_weighted<R: Rng + ?Sized>(&mut self, weights: &[u32; N], rng: &mut R) -> usize {
+		let total: i64 = weights.iter().map(|&w| i64::from(w)).sum();
+		assert!(total > 0, "All weights are zero.");
+
+		for (credit, &w) in self.credits.iter_mut().zip(weights) {
+			*credit += i64::from(w);
+		}
+
+		let eligible = || {
+			self.credits
+				.iter()
+				.zip(weights)
+				.enumerate()
+				.filter(|(_, (_, &w))| w > 0)
+				.map(|(index, (&credit, _))| (index, credit))
+		};
+		let max = eligible().map(|(_, credit)| credit).max().unwrap();
+		let ties = eligible().filter(|&(_, credit)| credit == max).count();
+		let nth = if ties > 1 { choose_index(rng, ties) } else { 0 };
+		let (index, _) = eligible().filter(|&(_, credit)| credit == max).nth(nth).unwrap();
+
+		self.credits[index] -= total;
+		index
+	}
+}
+
+impl<const N: usize> Default for StratifiedPicker<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
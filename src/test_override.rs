@@ -0,0 +1,150 @@
+//! Forcing the branches selected by [`branch`](crate::branch) and friends in
+//! tests.
+
+use core::cell::RefCell;
+use core::panic::Location;
+use std::boxed::Box;
+use std::vec::Vec;
+
+use rand::RngCore;
+
+
+std::thread_local! {
+	/// The remaining forced indices of the current thread, if any.
+	static SCRIPT: RefCell<Option<Vec<usize>>> = const { RefCell::new(None) };
+
+	/// The source of the secure macros on the current thread, if replaced.
+	static SECURE: RefCell<Option<Box<dyn RngCore>>> = const { RefCell::new(None) };
+}
+
+
+/// Runs `f` with the branches selected by the given script of indices.
+///
+/// While `f` runs on the current thread, every macro that selects a single
+/// branch uniformly, such as [`branch`](crate::branch),
+/// [`branch_using`](crate::branch_using) or
+/// [`branch_fast`](crate::branch_fast), doesn't draw from its RNG, but takes
+/// the next index of the script instead, i.e. `0` for the first branch, `1`
+/// for the second, and so on. Afterwards, the previous script, if any, is
+/// restored, also if `f` panics. Unused indices of the script are discarded.
+/// The script also takes precedence over the `deterministic` feature.
+///
+/// This allows unit tests to take a particular path through code that uses
+/// random branches internally. Macros that select by weights or some other
+/// state, such as [`branch_weighted`](crate::branch_weighted), are not
+/// affected.
+///
+/// This function is only available with the `test-override` feature, which
+/// should only be enabled for tests, e.g. as a dev-dependency feature.
+/// Without it, the hook in the macros compiles away entirely.
+///
+/// # Panics
+///
+/// A macro panics, reporting its call site, if it should select a branch
+/// while the script is exhausted, or if the next index is out of range for
+/// its number of branches.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::branch;
+/// use random_branch::test_override::with_forced;
+///
+/// fn greeting() -> &'static str {
+///     branch!("Hello", "Hi", "Howdy")
+/// }
+///
+/// let greetings = with_forced(&[2, 0, 1], || {
+///     [greeting(), greeting(), greeting()]
+/// });
+/// assert_eq!(greetings, ["Howdy", "Hello", "Hi"]);
+/// ```
+pub fn with_forced<T>(indices: &[usize], f: impl FnOnce() -> T) -> T {
+	/// Restores the previous script when dropped.
+	struct Restore(Option<Vec<usize>>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			let previous = self.0.take();
+			SCRIPT.with(|s| *s.borrow_mut() = previous);
+		}
+	}
+
+	// The script is stored in reverse, so the next index can be popped
+	let script = indices.iter().rev().copied().collect();
+	let _restore = Restore(SCRIPT.with(|s| s.borrow_mut().replace(script)));
+	f()
+}
+
+/// Takes the next forced index for a selection among `count` branches, if a
+/// script is active.
+#[track_caller]
+pub(crate) fn next(count: usize) -> Option<usize> {
+	let index = SCRIPT.with(|s| {
+		s.borrow_mut().as_mut().map(|script| script.pop())
+	})?;
+
+	let location = Location::caller();
+	match index {
+		Some(index) if index < count => Some(index),
+		Some(index) => panic!(
+			"The forced branch index {} is out of range for {} branches at {}",
+			index, count, location,
+		),
+		None => panic!("The forced branch script is exhausted at {}", location),
+	}
+}
+
+
+/// Runs `f` with the given RNG as the source of
+/// [`branch_secure`](crate::branch_secure) and
+/// [`try_branch_secure`](crate::try_branch_secure).
+///
+/// While `f` runs on the current thread, these macros draw from `rng`, via
+/// its [`try_fill_bytes`](RngCore::try_fill_bytes), instead of the OS RNG.
+/// This is meant to test the error path of `try_branch_secure`, with an RNG
+/// that fails, since the OS RNG rarely does. Afterwards, the previous source
+/// is restored, also if `f` panics. This is the only way to affect these
+/// macros, they ignore forced branches, see [`with_forced`], as well as the
+/// exploration and the replay of the `recorder`.
+///
+/// # Examples
+///
+/// ```rust
+/// use rand::RngCore;
+/// use random_branch::test_override::with_secure_rng;
+/// use random_branch::try_branch_secure;
+///
+/// struct Failing;
+///
+/// impl RngCore for Failing {
+///     fn next_u32(&mut self) -> u32 { panic!("no entropy") }
+///     fn next_u64(&mut self) -> u64 { panic!("no entropy") }
+///     fn fill_bytes(&mut self, _: &mut [u8]) { panic!("no entropy") }
+///     fn try_fill_bytes(&mut self, _: &mut [u8]) -> Result<(), rand::Error> {
+///         Err(rand::Error::new("no entropy"))
+///     }
+/// }
+///
+/// let result = with_secure_rng(Failing, || try_branch_secure!('a', 'b'));
+/// assert_eq!(result.unwrap_err().to_string(), "no entropy");
+/// ```
+pub fn with_secure_rng<T>(rng: impl RngCore + 'static, f: impl FnOnce() -> T) -> T {
+	/// Restores the previous source when dropped.
+	struct Restore(Option<Box<dyn RngCore>>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			let previous = self.0.take();
+			SECURE.with(|s| *s.borrow_mut() = previous);
+		}
+	}
+
+	let _restore = Restore(SECURE.with(|s| s.borrow_mut().replace(Box::new(rng))));
+	f()
+}
+
+/// Calls `f` with the source of the secure macros, if replaced.
+pub(crate) fn secure_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> Option<T> {
+	SECURE.with(|s| s.borrow_mut().as_mut().map(|rng| f(rng.as_mut())))
+}
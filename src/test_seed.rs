@@ -0,0 +1,83 @@
+//! Runtime support of the [`seeded_test`](crate::seeded_test) and
+//! [`cases`](crate::cases) attributes.
+
+use crate::key_hash;
+use crate::SeededRng;
+
+use std::format;
+
+use std::eprintln;
+use std::env;
+
+
+/// The environment variable to fix the seed of the seeded tests.
+pub const SEED_VAR: &str = "RANDOM_BRANCH_SEED";
+
+/// Returns the seed for a test, from [`SEED_VAR`] if set, else a fresh one.
+///
+/// # Panics
+///
+/// Panics if [`SEED_VAR`] is set but isn't a `u64`.
+pub fn test_seed() -> u64 {
+	match env::var(SEED_VAR) {
+		Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+			panic!("{} must be a u64, but is {:?}", SEED_VAR, value)
+		}),
+		Err(_) => rand::random(),
+	}
+}
+
+/// Returns the seed of the case `index` of the test at the given path.
+///
+/// The seed is the [`key_hash`] of `"{path}#{index}"`, thus it is stable
+/// across runs, releases of this crate, and platforms.
+pub fn case_seed(path: &str, index: usize) -> u64 {
+	key_hash(&format!("{}#{}", path, index))
+}
+
+/// Creates the RNG of the case `index` of the test at the given path, see
+/// [`case_seed`].
+pub fn case_rng(path: &str, index: usize) -> SeededRng {
+	SeededRng::new(case_seed(path, index))
+}
+
+/// Holds the seed of a running test and prints it if the test panics.
+#[derive(Debug)]
+pub struct SeedGuard {
+	test: &'static str,
+	seed: u64,
+}
+
+impl SeedGuard {
+	/// Draws the seed for the test at the given path, see [`test_seed`].
+	pub fn new(path: &'static str) -> Self {
+		// The test harness names tests without the crate name
+		let test = path.split_once("::").map_or(path, |(_, test)| test);
+		Self {
+			test,
+			seed: test_seed(),
+		}
+	}
+
+	/// Returns the seed of the test.
+	pub fn seed(&self) -> u64 {
+		self.seed
+	}
+
+	/// Creates a new RNG from the seed of the test.
+	pub fn rng(&self) -> SeededRng {
+		SeededRng::new(self.seed)
+	}
+}
+
+impl Drop for SeedGuard {
+	fn drop(&mut self) {
+		// Only dropped while panicking if the test failed (or is expected to)
+		if std::thread::panicking() {
+			eprintln!(
+				"random-branch: test {} failed with seed {}, reproduce with {}={} cargo test -- --exact {}",
+				self.test, self.seed, SEED_VAR, self.seed, self.test,
+			);
+		}
+	}
+}
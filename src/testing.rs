@@ -0,0 +1,429 @@
+//! Statistical assertions for tests of random branches.
+//!
+//! These helpers perform Pearson's chi-squared goodness-of-fit test on the
+//! number of times each branch was taken, so tests don't have to hand-roll
+//! tolerances. See [`assert_branch_uniform`](crate::assert_branch_uniform)
+//! and [`assert_branch_weighted`](crate::assert_branch_weighted) for macros
+//! that also run the branches.
+//!
+//! Such a test fails spuriously with a probability of the given
+//! significance level, so use a small one, e.g. [`DEFAULT_SIGNIFICANCE`],
+//! and preferably a seeded RNG, which makes the outcome reproducible.
+
+#[cfg(feature = "testing")]
+use core::fmt;
+use std::fmt::Write;
+use std::string::String;
+use std::vec::Vec;
+
+#[cfg(feature = "testing")]
+use rand::Rng;
+
+
+/// The significance level used by the macros of this module, i.e. one
+/// spurious failure in a thousand runs.
+pub const DEFAULT_SIGNIFICANCE: f64 = 0.001;
+
+
+/// Asserts that the given counts are compatible with a uniform distribution.
+///
+/// `counts[i]` is the number of times branch `i` was taken. The assertion
+/// fails if the probability of counts that deviate from a uniform
+/// distribution at least as much as the given ones, i.e. the p-value of the
+/// chi-squared test, is below `significance`.
+///
+/// # Panics
+///
+/// Panics with a table of the observed and expected counts if the test
+/// fails, or if there are no counts at all, or all of them are zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::testing::assert_uniform;
+///
+/// assert_uniform(&[1010, 985, 1005], 0.001);
+/// ```
+///
+/// ```rust,should_panic
+/// use random_branch::testing::assert_uniform;
+///
+/// assert_uniform(&[1200, 900, 900], 0.001);
+/// ```
+#[track_caller]
+pub fn assert_uniform(counts: &[u64], significance: f64) {
+	let ratios: Vec<f64> = counts.iter().map(|_| 1.0).collect();
+	assert_weighted(counts, &ratios, significance);
+}
+
+/// Asserts that the given counts are compatible with the given expected
+/// ratios.
+///
+/// This function dose essentially the same as [`assert_uniform`], but the
+/// expected distribution is proportional to `ratios`, which need not be
+/// normalized, e.g. the weights of the branches. A branch with a ratio of
+/// zero must never be taken.
+///
+/// # Panics
+///
+/// Panics with a table of the observed and expected counts if the test
+/// fails, or if there are no counts at all, or all of them are zero, or if
+/// `ratios` has a different length, a negative entry, or only zeros.
+///
+/// # Examples
+///
+/// ```rust
+/// use random_branch::testing::assert_weighted;
+///
+/// assert_weighted(&[3020, 0, 980], &[3.0, 0.0, 1.0], 0.001);
+/// ```
+#[track_caller]
+pub fn assert_weighted(counts: &[u64], ratios: &[f64], significance: f64) {
+	assert_eq!(counts.len(), ratios.len(), "There must be one ratio per count");
+	assert!(ratios.iter().all(|&r| r >= 0.0), "The ratios must not be negative");
+	let ratio_sum: f64 = ratios.iter().sum();
+	assert!(ratio_sum > 0.0, "The ratios must not all be zero");
+	let total: u64 = counts.iter().sum();
+	assert!(total > 0, "There are no observations");
+
+	let expected: Vec<f64> = ratios.iter().map(|&r| r / ratio_sum * total as f64).collect();
+	let p = p_value(counts, &expected);
+	if p < significance {
+		panic!(
+			"The branch counts are not distributed as expected (p = {:.3e} < {})\n{}",
+			p,
+			significance,
+			table(counts, &expected),
+		);
+	}
+}
+
+/// Checks that an RNG selects uniform indices via [`choose_index`].
+///
+/// This function draws `samples` indices in `0..n` with
+/// [`choose_index`](crate::choose_index), the selection of most macros of
+/// this crate, and checks that the relative frequency of each index is
+/// within `tolerance` of `1/n`. Thus, it validates a custom RNG, e.g. an
+/// adapter of another generator, through the same path the macros take.
+///
+/// Unlike [`assert_uniform`], this is no significance test, the tolerance is
+/// an absolute bound of the frequencies, so pick it generously for the
+/// number of samples, e.g. a few standard deviations of
+/// `sqrt(p * (1 - p) / samples)` with `p = 1/n`.
+///
+/// This function is only available with the `testing` feature.
+///
+/// # Errors
+///
+/// Returns a [`NotUniform`] error for the first index whose frequency
+/// deviates by more than `tolerance`.
+///
+/// # Panics
+///
+/// Panics if `n` or `samples` is zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use rand_pcg::Lcg64Xsh32;
+/// use random_branch::testing::assert_rng_uniform;
+///
+/// let mut rng = Lcg64Xsh32::new(42, 0);
+/// assert_rng_uniform(&mut rng, 6, 60_000, 0.01).unwrap();
+///
+/// // An RNG that only ever yields zeros always selects the first index
+/// let mut zeros = rand::rngs::mock::StepRng::new(0, 0);
+/// let err = assert_rng_uniform(&mut zeros, 3, 300, 0.01).unwrap_err();
+/// assert_eq!(err.index(), 0);
+/// assert_eq!(err.frequency(), 1.0);
+/// ```
+///
+/// [`choose_index`]: crate::choose_index
+#[cfg(feature = "testing")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "testing")))]
+pub fn assert_rng_uniform<R: Rng + ?Sized>(
+	rng: &mut R,
+	n: usize,
+	samples: usize,
+	tolerance: f64,
+) -> Result<(), NotUniform> {
+	assert!(samples > 0, "There must be at least one sample");
+	let mut counts = std::vec![0_usize; n];
+	for _ in 0..samples {
+		counts[crate::choose_index(rng, n)] += 1;
+	}
+
+	let expected = 1.0 / n as f64;
+	for (index, &count) in counts.iter().enumerate() {
+		let frequency = count as f64 / samples as f64;
+		if (frequency - expected).abs() > tolerance {
+			return Err(NotUniform {
+				index,
+				frequency,
+				expected,
+				tolerance,
+			});
+		}
+	}
+	Ok(())
+}
+
+/// The error of [`assert_rng_uniform`], if an index was selected too rarely
+/// or too often.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg(feature = "testing")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "testing")))]
+pub struct NotUniform {
+	index: usize,
+	frequency: f64,
+	expected: f64,
+	tolerance: f64,
+}
+
+#[cfg(feature = "testing")]
+impl NotUniform {
+	/// Returns the first index whose frequency is out of tolerance.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	/// Returns the observed relative frequency of the index.
+	pub fn frequency(&self) -> f64 {
+		self.frequency
+	}
+
+	/// Returns the expected relative frequency, i.e. `1/n`.
+	pub fn expected(&self) -> f64 {
+		self.expected
+	}
+
+	/// Returns the allowed absolute deviation from the expected frequency.
+	pub fn tolerance(&self) -> f64 {
+		self.tolerance
+	}
+}
+
+#[cfg(feature = "testing")]
+impl fmt::Display for NotUniform {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"index {} was selected with a frequency of {:.4}, expected {:.4} ± {}",
+			self.index, self.frequency, self.expected, self.tolerance,
+		)
+	}
+}
+
+#[cfg(feature = "testing")]
+impl std::error::Error for NotUniform {}
+
+/// Computes the p-value of the chi-squared test of the observed counts
+/// against the expected ones.
+fn p_value(observed: &[u64], expected: &[f64]) -> f64 {
+	let mut chi2 = 0.0;
+	let mut categories = 0;
+	for (&o, &e) in observed.iter().zip(expected) {
+		if e == 0.0 {
+			if o > 0 {
+				// Impossible under the expected distribution
+				return 0.0;
+			}
+			continue;
+		}
+		categories += 1;
+		chi2 += (o as f64 - e).powi(2) / e;
+	}
+	if categories < 2 {
+		return 1.0;
+	}
+	chi2_survival(chi2, categories - 1)
+}
+
+/// Formats the observed and expected counts as a table.
+fn table(observed: &[u64], expected: &[f64]) -> String {
+	let mut out = String::from("branch   observed   expected\n");
+	for (i, (&o, &e)) in observed.iter().zip(expected).enumerate() {
+		let _ = writeln!(out, "{:>6} {:>10} {:>10.1}", i, o, e);
+	}
+	out
+}
+
+
+/// The probability that a chi-squared distributed variable with `df` degrees
+/// of freedom is at least `x`.
+fn chi2_survival(x: f64, df: usize) -> f64 {
+	if x <= 0.0 {
+		return 1.0;
+	}
+	upper_gamma(df as f64 / 2.0, x / 2.0)
+}
+
+/// The regularized upper incomplete gamma function `Q(a, x)`.
+fn upper_gamma(a: f64, x: f64) -> f64 {
+	if x < a + 1.0 {
+		1.0 - lower_gamma_series(a, x)
+	} else {
+		upper_gamma_fraction(a, x)
+	}
+}
+
+/// The regularized lower incomplete gamma function `P(a, x)` via its series,
+/// which converges quickly for `x < a + 1`.
+fn lower_gamma_series(a: f64, x: f64) -> f64 {
+	let mut term = 1.0 / a;
+	let mut sum = term;
+	let mut n = a;
+	for _ in 0..1000 {
+		n += 1.0;
+		term *= x / n;
+		sum += term;
+		if term.abs() < sum.abs() * 1e-15 {
+			break;
+		}
+	}
+	sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+/// `Q(a, x)` via Lentz's continued fraction, which converges quickly for
+/// `x >= a + 1`.
+fn upper_gamma_fraction(a: f64, x: f64) -> f64 {
+	const TINY: f64 = 1e-300;
+	let mut b = x + 1.0 - a;
+	let mut c = 1.0 / TINY;
+	let mut d = 1.0 / b;
+	let mut h = d;
+	for i in 1..1000 {
+		let an = -(i as f64) * (i as f64 - a);
+		b += 2.0;
+		d = an * d + b;
+		if d.abs() < TINY {
+			d = TINY;
+		}
+		c = b + an / c;
+		if c.abs() < TINY {
+			c = TINY;
+		}
+		d = 1.0 / d;
+		let delta = d * c;
+		h *= delta;
+		if (delta - 1.0).abs() < 1e-15 {
+			break;
+		}
+	}
+	(-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+/// The natural logarithm of the gamma function, via the Lanczos
+/// approximation (`g = 7`, `n = 9`).
+fn ln_gamma(x: f64) -> f64 {
+	const COEFFICIENTS: [f64; 9] = [
+		0.999_999_999_999_809_9,
+		676.520_368_121_885_1,
+		-1_259.139_216_722_402_8,
+		771.323_428_777_653_1,
+		-176.615_029_162_140_6,
+		12.507_343_278_686_905,
+		-0.138_571_095_265_720_12,
+		9.984_369_578_019_572e-6,
+		1.505_632_735_149_311_6e-7,
+	];
+	if x < 0.5 {
+		// The reflection formula
+		let pi = core::f64::consts::PI;
+		return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+	}
+	let x = x - 1.0;
+	let t = x + 7.5;
+	let series = COEFFICIENTS[1..]
+		.iter()
+		.enumerate()
+		.fold(COEFFICIENTS[0], |acc, (i, &c)| acc + c / (x + i as f64 + 1.0));
+	0.5 * (2.0 * core::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand::Rng;
+	use rand_pcg::Lcg64Xsh32;
+
+	fn close(a: f64, b: f64) -> bool {
+		(a - b).abs() <= 1e-4 * b.abs().max(1e-6)
+	}
+
+	#[test]
+	fn known_quantiles() {
+		// The 95% and 99.9% quantiles of the chi-squared distribution
+		assert!(close(chi2_survival(3.841_459, 1), 0.05));
+		assert!(close(chi2_survival(5.991_465, 2), 0.05));
+		assert!(close(chi2_survival(27.877_16, 9), 0.001));
+		assert!(close(chi2_survival(124.342_1, 100), 0.05));
+		assert_eq!(chi2_survival(0.0, 3), 1.0);
+	}
+
+	#[test]
+	fn ln_gamma_of_integers() {
+		let mut factorial = 1.0_f64;
+		for n in 1..20 {
+			assert!(close(ln_gamma(n as f64), factorial.ln()), "n = {}", n);
+			factorial *= n as f64;
+		}
+		assert!(close(ln_gamma(0.5), core::f64::consts::PI.sqrt().ln()));
+	}
+
+	#[test]
+	fn fair_data_passes() {
+		assert_uniform(&[1000, 1000, 1000], DEFAULT_SIGNIFICANCE);
+		assert_uniform(&[7], DEFAULT_SIGNIFICANCE);
+
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		for _ in 0..20 {
+			let mut counts = [0_u64; 6];
+			for _ in 0..6000 {
+				counts[rng.gen_range(0..6)] += 1;
+			}
+			assert_uniform(&counts, DEFAULT_SIGNIFICANCE);
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "are not distributed as expected")]
+	fn biased_data_fails() {
+		assert_uniform(&[1100, 1000, 900], DEFAULT_SIGNIFICANCE);
+	}
+
+	#[test]
+	fn failure_shows_table() {
+		let message = std::panic::catch_unwind(|| assert_uniform(&[60, 40], 0.05))
+			.unwrap_err()
+			.downcast::<String>()
+			.unwrap();
+		assert!(message.contains("     0         60       50.0"), "{}", message);
+		assert!(message.contains("     1         40       50.0"), "{}", message);
+	}
+
+	#[test]
+	fn weighted_data() {
+		assert_weighted(&[300, 100], &[3.0, 1.0], DEFAULT_SIGNIFICANCE);
+		assert_weighted(&[300, 0, 100], &[0.75, 0.0, 0.25], DEFAULT_SIGNIFICANCE);
+	}
+
+	#[test]
+	#[should_panic(expected = "are not distributed as expected")]
+	fn weighted_impossible_branch_fails() {
+		assert_weighted(&[300, 1, 100], &[3.0, 0.0, 1.0], DEFAULT_SIGNIFICANCE);
+	}
+
+	#[test]
+	#[should_panic(expected = "are not distributed as expected")]
+	fn weighted_biased_data_fails() {
+		assert_weighted(&[200, 200], &[3.0, 1.0], DEFAULT_SIGNIFICANCE);
+	}
+
+	#[test]
+	#[should_panic(expected = "There are no observations")]
+	fn no_observations() {
+		assert_uniform(&[0, 0], DEFAULT_SIGNIFICANCE);
+	}
+}
@@ -0,0 +1,189 @@
+//! Procedural text generation from weighted alternatives.
+//!
+//! This module provides a small [`Grammar`] type for generating random text
+//! at runtime, and is accompanied by the
+//! [`random_sentence_using`](crate::random_sentence_using) macro for
+//! sentences whose structure is known at compile time.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use crate::weighted;
+
+
+/// The alternatives of a single symbol.
+#[derive(Debug, Clone, Default)]
+struct Rule {
+	weights: Vec<u32>,
+	templates: Vec<String>,
+}
+
+
+/// A grammar of symbols, each with weighted alternative templates.
+///
+/// Each symbol has a list of alternatives, added via [`rule`](Self::rule),
+/// and expanding a symbol picks one of its alternatives with a probability
+/// proportional to its weight. An alternative is a template text, which may
+/// refer to other symbols by their name in braces, e.g. `{noun}`, which are
+/// expanded recursively.
+///
+/// A reference is kept verbatim in the output, if its symbol has no
+/// alternatives with a non-zero weight, or if the expansion is nested deeper
+/// than [`MAX_DEPTH`](Self::MAX_DEPTH), so expanding never fails and always
+/// terminates, even for recursive grammars.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rand_pcg::Lcg64Xsh32;
+/// use random_branch::text::Grammar;
+/// let mut my_rng = /* snip */
+/// # Lcg64Xsh32::new(0,0);
+///
+/// let mut grammar = Grammar::new("sentence");
+/// grammar
+///     .rule("sentence", 1, "The {animal} {verb}.")
+///     .rule("animal", 3, "cat")
+///     .rule("animal", 1, "{adjective} dog")
+///     .rule("adjective", 1, "big")
+///     .rule("adjective", 1, "small")
+///     .rule("verb", 1, "sleeps")
+///     .rule("verb", 1, "runs");
+///
+/// let sentence = grammar.expand(&mut my_rng);
+/// assert!(sentence.starts_with("The "));
+/// assert!(sentence.ends_with("s."));
+/// assert!(!sentence.contains('{'));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "text")))]
+pub struct Grammar {
+	start: String,
+	rules: BTreeMap<String, Rule>,
+}
+
+impl Grammar {
+	/// The maximum nesting depth of symbol expansions.
+	pub const MAX_DEPTH: usize = 32;
+
+	/// Creates an empty grammar, where [`expand`](Self::expand) starts with
+	/// the given symbol.
+	pub fn new(start: &str) -> Self {
+		Self {
+			start: start.into(),
+			rules: BTreeMap::new(),
+		}
+	}
+
+	/// Adds an alternative `template` with the given `weight` to `symbol`.
+	pub fn rule(&mut self, symbol: &str, weight: u32, template: &str) -> &mut Self {
+		let rule = self.rules.entry(symbol.into()).or_default();
+		rule.weights.push(weight);
+		rule.templates.push(template.into());
+		self
+	}
+
+	/// Generates a random text from the start symbol.
+	pub fn expand<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
+		self.expand_symbol(&self.start, rng)
+	}
+
+	/// Generates a random text from the given symbol.
+	pub fn expand_symbol<R: Rng + ?Sized>(&self, symbol: &str, rng: &mut R) -> String {
+		let mut out = String::new();
+		self.expand_into(symbol, rng, 0, &mut out);
+		out
+	}
+
+	fn expand_into<R: Rng + ?Sized>(&self, symbol: &str, rng: &mut R, depth: usize, out: &mut String) {
+		let template = self.rules.get(symbol).and_then(|rule| {
+			let index = weighted::select(&rule.weights, rng).ok()?;
+			Some(&rule.templates[index])
+		});
+		let template = match template {
+			Some(template) if depth < Self::MAX_DEPTH => template,
+			_ => {
+				out.push('{');
+				out.push_str(symbol);
+				out.push('}');
+				return;
+			}
+		};
+
+		let mut rest = template.as_str();
+		while let Some(open) = rest.find('{') {
+			out.push_str(&rest[..open]);
+			match rest[open..].find('}') {
+				Some(close) => {
+					let name = &rest[open + 1..open + close];
+					self.expand_into(name, rng, depth + 1, out);
+					rest = &rest[open + close + 1..];
+				}
+				None => {
+					rest = &rest[open..];
+					break;
+				}
+			}
+		}
+		out.push_str(rest);
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand_pcg::Lcg64Xsh32;
+
+	#[test]
+	fn expands_nested_symbols() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut grammar = Grammar::new("greeting");
+		grammar
+			.rule("greeting", 1, "{hello}, {name}!")
+			.rule("hello", 1, "Hello")
+			.rule("name", 1, "World");
+		assert_eq!(grammar.expand(&mut rng), "Hello, World!");
+	}
+
+	#[test]
+	#[cfg(not(feature = "deterministic"))]
+	fn follows_weights() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut grammar = Grammar::new("coin");
+		grammar
+			.rule("coin", 3, "heads")
+			.rule("coin", 1, "tails")
+			.rule("coin", 0, "edge");
+		let mut heads = 0;
+		for _ in 0..4000 {
+			match grammar.expand(&mut rng).as_str() {
+				"heads" => heads += 1,
+				"tails" => {}
+				other => panic!("unexpected {}", other),
+			}
+		}
+		assert!((2800..3200).contains(&heads), "{}", heads);
+	}
+
+	#[test]
+	fn keeps_unknown_and_unterminated_references() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut grammar = Grammar::new("start");
+		grammar.rule("start", 1, "a {missing} b {open");
+		assert_eq!(grammar.expand(&mut rng), "a {missing} b {open");
+	}
+
+	#[test]
+	fn recursion_terminates() {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut grammar = Grammar::new("loop");
+		grammar.rule("loop", 1, "x{loop}");
+		let text = grammar.expand(&mut rng);
+		assert!(text.ends_with("{loop}"));
+		assert_eq!(text.matches('x').count(), Grammar::MAX_DEPTH);
+	}
+}
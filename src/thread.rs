@@ -0,0 +1,220 @@
+//! The thread-local RNG used by [`branch`](crate::branch) and friends.
+
+use core::cell::RefCell;
+use std::boxed::Box;
+
+use rand::RngCore;
+
+
+std::thread_local! {
+	/// The RNG override of the current thread, if any.
+	static OVERRIDE: RefCell<Option<Box<dyn RngCore>>> = const { RefCell::new(None) };
+}
+
+#[cfg(all(feature = "env-seed", not(feature = "repro")))]
+std::thread_local! {
+	/// The RNG of the current thread seeded by [`env_seed`].
+	static SEEDED: RefCell<rand_pcg::Lcg64Xsh32> =
+		RefCell::new(rand::SeedableRng::seed_from_u64(env_seed()));
+}
+
+#[cfg(all(feature = "small-rng", not(feature = "env-seed")))]
+std::thread_local! {
+	/// The fast RNG of the current thread, seeded once from entropy.
+	static SMALL: RefCell<rand::rngs::SmallRng> = RefCell::new(rand::SeedableRng::from_entropy());
+}
+
+
+/// The environment variable read by [`env_seed`].
+#[cfg(feature = "env-seed")]
+const SEED_VAR: &str = "RANDOM_BRANCH_SEED";
+
+/// Returns the process-wide seed of [`branch`](crate::branch) and friends.
+///
+/// With the `env-seed` feature, the macros that would otherwise use
+/// [`rand::thread_rng()`], such as [`branch`](crate::branch), use a
+/// deterministic generator instead, which is seeded by this seed on each
+/// thread. The seed is determined once per process, when it is first needed:
+/// * if the `RANDOM_BRANCH_SEED` environment variable is set, its value is
+///   parsed as `u64` and used as seed,
+/// * otherwise, a random seed is drawn from [`rand::thread_rng()`] and
+///   printed to stderr, so that a failing run can be replayed by setting the
+///   variable to the printed value.
+///
+/// Each thread starts with the same seed, so e.g. each test of the default
+/// test harness, which runs on its own thread, gets the same sequence of
+/// branches on every run with the same seed, regardless of the order and
+/// parallelism of the tests. Consequently, multiple threads of the same run
+/// also get the same sequence, so don't use this feature outside of testing.
+/// An override set via [`set_thread_branch_rng`] still takes precedence.
+///
+/// Besides the one-time check of the variable, this is just a thread-local
+/// lookup, and the generator is [`rand_pcg::Lcg64Xsh32`], which is cheaper
+/// than [`rand::thread_rng()`].
+///
+/// With the `repro` feature, all threads instead share a single generator,
+/// see [`repro_rng`].
+///
+/// # Panics
+///
+/// Panics if the variable is set but not a valid `u64`.
+#[cfg(feature = "env-seed")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "env-seed")))]
+pub fn env_seed() -> u64 {
+	static SEED: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+
+	*SEED.get_or_init(|| match std::env::var(SEED_VAR) {
+		Ok(value) => value.trim().parse().unwrap_or_else(|_| {
+			panic!("{} must be a u64, but is {:?}", SEED_VAR, value)
+		}),
+		Err(_) => {
+			let seed = rand::Rng::gen(&mut rand::thread_rng());
+			std::eprintln!("random-branch: {} is not set, using {}={}", SEED_VAR, SEED_VAR, seed);
+			seed
+		}
+	})
+}
+
+
+/// Calls `f` with the process-wide generator of the `repro` feature.
+///
+/// With the `repro` feature, the macros that would otherwise use
+/// [`rand::thread_rng()`], such as [`branch`](crate::branch), draw from a
+/// single [`SmallRng`](rand::rngs::SmallRng) for the whole process instead,
+/// which is seeded once by [`env_seed`]. So, to reproduce a run, e.g. of an
+/// example, set the `RANDOM_BRANCH_SEED` environment variable to the seed
+/// printed by the original run:
+///
+/// ```sh
+/// RANDOM_BRANCH_SEED=42 cargo run --example my-example --features random-branch/repro
+/// ```
+///
+/// Unlike the `deterministic` feature, the branches are still random, but
+/// the same seed yields the same branches. Unlike the plain `env-seed`
+/// feature, the threads don't start their own sequences, but they take the
+/// next values of the shared one. The generator is guarded by a
+/// [`Mutex`](std::sync::Mutex), which is locked just for each draw. Thus, a
+/// single-threaded program is fully reproducible, while the branches of a
+/// multi-threaded one only are, if its threads draw in the same order. Also,
+/// `SmallRng` may differ between platforms and versions of [`rand`], so a
+/// seed only reproduces a run of the same build.
+///
+/// An override set via [`set_thread_branch_rng`] still takes precedence.
+#[cfg(feature = "repro")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "repro")))]
+pub fn repro_rng<T>(f: impl FnOnce(&mut rand::rngs::SmallRng) -> T) -> T {
+	use std::sync::Mutex;
+	use std::sync::OnceLock;
+	use std::sync::PoisonError;
+
+	static REPRO: OnceLock<Mutex<rand::rngs::SmallRng>> = OnceLock::new();
+
+	let repro = REPRO.get_or_init(|| Mutex::new(rand::SeedableRng::seed_from_u64(env_seed())));
+	f(&mut repro.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+
+/// Overrides the RNG used by [`branch`](crate::branch) on the current thread.
+///
+/// After calling this function, all macros that would otherwise use
+/// [`rand::thread_rng()`], such as [`branch`](crate::branch), will draw
+/// from the given `rng` instead, as long as they are executed on the current
+/// thread. This allows test harnesses to inject a seeded RNG and thus get
+/// reproducible results without changing any call sites to
+/// [`branch_using`](crate::branch_using).
+///
+/// Returns the previous override, if any.
+///
+/// # Parallel Tests
+///
+/// The override is strictly thread-local. Since the default test harness runs
+/// each test on its own thread, setting the override within one test does not
+/// affect any other test, even if they run in parallel. However, this also
+/// means that threads spawned by a test will not see the override, they use
+/// [`rand::thread_rng()`] as usual.
+///
+/// # Examples
+///
+/// ```rust
+/// use rand::SeedableRng;
+/// use rand_pcg::Lcg64Xsh32;
+/// use random_branch::branch;
+/// use random_branch::reset_thread_branch_rng;
+/// use random_branch::set_thread_branch_rng;
+///
+/// let mut run = || {
+///     set_thread_branch_rng(Box::new(Lcg64Xsh32::seed_from_u64(42)));
+///     let choices: Vec<u32> = (0..10).map(|_| branch!(1, 2, 3)).collect();
+///     reset_thread_branch_rng();
+///     choices
+/// };
+///
+/// // Same seed, same choices
+/// assert_eq!(run(), run());
+/// ```
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+pub fn set_thread_branch_rng(rng: Box<dyn RngCore>) -> Option<Box<dyn RngCore>> {
+	OVERRIDE.with(|o| o.borrow_mut().replace(rng))
+}
+
+/// Removes the RNG override of the current thread.
+///
+/// Afterwards, [`branch`](crate::branch) will use [`rand::thread_rng()`]
+/// again on the current thread. See [`set_thread_branch_rng`] for details.
+///
+/// Returns the removed override, if any.
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+pub fn reset_thread_branch_rng() -> Option<Box<dyn RngCore>> {
+	OVERRIDE.with(|o| o.borrow_mut().take())
+}
+
+
+/// The RNG used by [`branch`](crate::branch) on the current thread.
+///
+/// This is a handle which forwards all requests either to the RNG set via
+/// [`set_thread_branch_rng`], or if there is none, to
+/// [`rand::thread_rng()`] (or to a generator seeded by `env_seed` with the
+/// `env-seed` or `repro` feature, or to a thread-local `SmallRng` with the
+/// `small-rng` feature). Checking for an override is just a thread-local
+/// lookup, so this is cheap if no override is set.
+///
+/// Usually, you don't need this type, since [`branch`](crate::branch) uses it
+/// implicitly, but you can pass it to other functions that expect an RNG, to
+/// make them honor the override too.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+pub struct ThreadBranchRng;
+
+impl ThreadBranchRng {
+	fn with<T>(self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+		OVERRIDE.with(|o| match o.borrow_mut().as_mut() {
+			Some(rng) => f(rng.as_mut()),
+			#[cfg(feature = "repro")]
+			None => repro_rng(|rng| f(rng)),
+			#[cfg(all(feature = "env-seed", not(feature = "repro")))]
+			None => SEEDED.with(|rng| f(&mut *rng.borrow_mut())),
+			#[cfg(all(feature = "small-rng", not(feature = "env-seed")))]
+			None => SMALL.with(|rng| f(&mut *rng.borrow_mut())),
+			#[cfg(not(any(feature = "env-seed", feature = "small-rng")))]
+			None => f(&mut rand::thread_rng()),
+		})
+	}
+}
+
+impl RngCore for ThreadBranchRng {
+	fn next_u32(&mut self) -> u32 {
+		self.with(|rng| rng.next_u32())
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.with(|rng| rng.next_u64())
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		self.with(|rng| rng.fill_bytes(dest))
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		self.with(|rng| rng.try_fill_bytes(dest))
+	}
+}
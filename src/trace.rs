@@ -0,0 +1,56 @@
+//! Logging each selection with `tracing` or `log`.
+//!
+//! With the `tracing` or `log` feature, the counters of each call site, see
+//! [`Site`](crate::select::Site), log the selection of a branch, right before
+//! it is evaluated, so it is logged even if the branch panics. Both features
+//! may be enabled together, then each selection is logged to both.
+
+
+/// Logs selecting the branch `index` out of `count` at the given call site,
+/// at the `trace` level with the target `random_branch`.
+#[inline]
+pub(crate) fn selected(
+	file: &'static str,
+	line: u32,
+	column: u32,
+	label: Option<&'static str>,
+	index: usize,
+	count: usize,
+) {
+	#[cfg(feature = "tracing")]
+	tracing::trace!(
+		target: "random_branch",
+		file,
+		line,
+		column,
+		index,
+		count,
+		label,
+		"selected branch {} of {}",
+		index,
+		count,
+	);
+
+	#[cfg(feature = "log")]
+	match label {
+		Some(label) => log::trace!(
+			target: "random_branch",
+			"{}:{}:{}: selected branch {} of {} ({})",
+			file,
+			line,
+			column,
+			index,
+			count,
+			label,
+		),
+		None => log::trace!(
+			target: "random_branch",
+			"{}:{}:{}: selected branch {} of {}",
+			file,
+			line,
+			column,
+			index,
+			count,
+		),
+	}
+}
@@ -0,0 +1,23 @@
+//! The index selection of [`branch_arbitrary`](crate::branch_arbitrary).
+
+use arbitrary::Error;
+use arbitrary::Unstructured;
+
+
+/// Selects an index in `0..count` from the given fuzzer input.
+///
+/// # Errors
+///
+/// Returns [`Error::NotEnoughData`] if the input is exhausted.
+///
+/// # Panics
+///
+/// Panics if `count` is zero.
+#[track_caller]
+pub fn select(u: &mut Unstructured<'_>, count: usize) -> arbitrary::Result<usize> {
+	assert!(count > 0, "You must provide at least one choice.");
+	if u.is_empty() {
+		return Err(Error::NotEnoughData);
+	}
+	u.int_in_range(0..=count - 1)
+}
@@ -0,0 +1,21 @@
+//! Runtime support for [`branch_until_using`](crate::branch_until_using).
+
+
+/// Calls `branch` until `predicate` accepts its value, at most `max_attempts`
+/// times.
+///
+/// Returns the first accepted value, or `None` if all attempts have been
+/// rejected. Rejected values are dropped.
+pub fn retry<T>(
+	max_attempts: usize,
+	mut branch: impl FnMut() -> T,
+	mut predicate: impl FnMut(&T) -> bool,
+) -> Option<T> {
+	for _ in 0..max_attempts {
+		let candidate = branch();
+		if predicate(&candidate) {
+			return Some(candidate);
+		}
+	}
+	None
+}
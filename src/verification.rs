@@ -0,0 +1,45 @@
+//! Nondeterministic branch selection for the Kani model checker.
+//!
+//! With the `verification` feature, when compiled by Kani (i.e. with
+//! `cfg(kani)`), every macro that selects a single branch uniformly, such as
+//! [`branch`](crate::branch) or [`branch_using`](crate::branch_using), does
+//! not draw from its RNG, but selects a nondeterministic index via
+//! `kani::any`, constrained to the number of branches. Thus, the model
+//! checker explores all branches, instead of only the ones that some stubbed
+//! RNG happens to produce. The RNG argument of the macros is still evaluated,
+//! but otherwise ignored, so any cheap RNG, e.g. from
+//! [`from_fn`](crate::from_fn), will do.
+//!
+//! In normal builds, i.e. without `cfg(kani)`, the feature has no effect.
+
+/// Selects a nondeterministic index in `0..count`.
+pub(crate) fn nondet_index(count: usize) -> usize {
+	assert!(count > 0, "You must provide at least one choice.");
+	let index: usize = kani::any();
+	kani::assume(index < count);
+	index
+}
+
+
+#[cfg(kani)]
+mod proofs {
+	use crate::branch_using;
+	use crate::from_fn;
+
+	/// A state machine, which fails only if the third branch is taken.
+	fn step(state: u8) -> u8 {
+		let mut rng = from_fn(|| 0);
+		branch_using!(rng, {
+			state.saturating_add(1),
+			state.saturating_sub(1),
+			0,
+		})
+	}
+
+	#[kani::proof]
+	#[kani::should_panic]
+	fn finds_violation_on_third_branch() {
+		let state = step(7);
+		assert_ne!(state, 0, "Only the third branch yields zero");
+	}
+}
@@ -0,0 +1,144 @@
+//! Runtime support for [`branch_weighted_using`](crate::branch_weighted_using).
+
+use core::cell::Cell;
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::ops::AddAssign;
+
+#[cfg(feature = "alloc")]
+use rand::distributions::uniform::SampleUniform;
+#[cfg(feature = "alloc")]
+use rand::distributions::Distribution;
+#[cfg(feature = "alloc")]
+use rand::distributions::WeightedIndex;
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+
+/// The error of a weighted branch, if all weights are zero.
+///
+/// There is no branch that could be selected if all weights are zero. The
+/// public fallible macros, such as
+/// [`try_branch_weighted_using`](crate::try_branch_weighted_using), report
+/// this as [`BranchError::AllWeightsZero`](crate::BranchError::AllWeightsZero)
+/// instead, which this type converts into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct AllWeightsZero;
+
+impl fmt::Display for AllWeightsZero {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("all weights are zero")
+	}
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "std")))]
+impl std::error::Error for AllWeightsZero {}
+
+
+/// A weight of a weighted macro, before its conversion into `u32`.
+///
+/// The macros convert each weight via `(&&Weight::new(weight)).get()`, which
+/// resolves to [`ExactWeight`] for `u32`, including untyped integer literals,
+/// and to [`IntoWeight`] for any other type implementing `Into<u32>`, since
+/// method resolution tries the receiver with fewer dereferences first.
+pub struct Weight<T>(Cell<Option<T>>);
+
+impl<T> Weight<T> {
+	/// Wraps the given weight.
+	pub const fn new(weight: T) -> Self {
+		Self(Cell::new(Some(weight)))
+	}
+}
+
+/// Takes a `u32` weight as is.
+pub trait ExactWeight {
+	/// Returns the weight.
+	fn get(&self) -> u32;
+}
+
+impl ExactWeight for &Weight<u32> {
+	fn get(&self) -> u32 {
+		self.0.take().unwrap_or_default()
+	}
+}
+
+/// Converts a weight into `u32`.
+pub trait IntoWeight {
+	/// Returns the converted weight.
+	fn get(&self) -> u32;
+}
+
+impl<T: Into<u32>> IntoWeight for Weight<T> {
+	fn get(&self) -> u32 {
+		self.0.take().map_or(0, Into::into)
+	}
+}
+
+
+/// Panics because all weights are zero, reporting the caller's location.
+#[cold]
+#[track_caller]
+pub fn all_weights_zero() -> ! {
+	panic!("All weights are zero.")
+}
+
+/// Selects the first index with a non-zero weight, which is the selection of
+/// the `deterministic` feature.
+///
+/// Returns an error if all weights are zero, including if there are no
+/// weights at all.
+pub fn first(weights: &[u32]) -> Result<usize, AllWeightsZero> {
+	weights.iter().position(|&w| w != 0).ok_or(AllWeightsZero)
+}
+
+/// Selects an index with a probability proportional to its weight.
+///
+/// Returns an error if all weights are zero, including if there are no
+/// weights at all.
+#[cfg(feature = "rand")]
+pub fn select<R: Rng + ?Sized>(weights: &[u32], rng: &mut R) -> Result<usize, AllWeightsZero> {
+	if crate::select::DETERMINISTIC {
+		return first(weights);
+	}
+
+	let total: u64 = weights.iter().map(|&w| u64::from(w)).sum();
+	if total == 0 {
+		return Err(AllWeightsZero);
+	}
+
+	let mut remaining = rng.gen_range(0..total);
+	let mut index = 0;
+	while remaining >= u64::from(weights[index]) {
+		remaining -= u64::from(weights[index]);
+		index += 1;
+	}
+	Ok(index)
+}
+
+/// Picks an item with a probability proportional to the weight at the same
+/// index, which is the selection of
+/// [`weighted_pick_using`](crate::weighted_pick_using).
+///
+/// Returns `None` if `items` and `weights` differ in length, or if the
+/// weights can't be sampled, e.g. if they are empty, all zero, or any is
+/// negative.
+#[cfg(feature = "alloc")]
+pub fn pick<'a, T, W, R>(rng: &mut R, items: &'a [T], weights: &[W]) -> Option<&'a T>
+where
+	W: SampleUniform + PartialOrd + Default + Clone + for<'w> AddAssign<&'w W>,
+	R: Rng + ?Sized,
+{
+	if items.len() != weights.len() {
+		return None;
+	}
+	// Validates the weights, even if deterministic
+	let index = WeightedIndex::new(weights).ok()?;
+
+	if crate::select::DETERMINISTIC {
+		let zero = W::default();
+		return weights.iter().position(|w| *w > zero).and_then(|i| items.get(i));
+	}
+
+	items.get(index.sample(rng))
+}
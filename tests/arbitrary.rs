@@ -0,0 +1,73 @@
+//! Checks that `branch_arbitrary` selects branches deterministically from the
+//! fuzzer input and propagates errors.
+
+use arbitrary::Arbitrary;
+use arbitrary::Error;
+use arbitrary::Unstructured;
+use random_branch::branch_arbitrary;
+
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+	Lit(u8),
+	Neg(Box<Expr>),
+	Add(Box<Expr>, Box<Expr>),
+}
+
+impl<'a> Arbitrary<'a> for Expr {
+	fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+		branch_arbitrary!(u, {
+			Expr::Lit(u.arbitrary()?),
+			Expr::Neg(Box::new(Expr::arbitrary(u)?)),
+			Expr::Add(Box::new(Expr::arbitrary(u)?), Box::new(Expr::arbitrary(u)?)),
+		})
+	}
+}
+
+
+#[test]
+fn deterministic_choices() {
+	let bytes = [0, 1, 2, 3, 4, 5, 255];
+	let run = || {
+		let mut u = Unstructured::new(&bytes);
+		let choices: Vec<usize> = (0..7)
+			.map(|_| branch_arbitrary!(&mut u, { 0, 1, 2 }).unwrap())
+			.collect();
+		choices
+	};
+	assert_eq!(run(), run());
+	assert_eq!(run(), [0, 1, 2, 0, 1, 2, 0]);
+}
+
+#[test]
+fn recursive_generator() {
+	let mut u = Unstructured::new(&[2, 1, 0, 7, 0, 9]);
+	let expr = Expr::arbitrary(&mut u).unwrap();
+	assert_eq!(
+		expr,
+		Expr::Add(
+			Box::new(Expr::Neg(Box::new(Expr::Lit(7)))),
+			Box::new(Expr::Lit(9)),
+		)
+	);
+	assert!(u.is_empty());
+}
+
+#[test]
+fn empty_input_is_an_error() {
+	let mut u = Unstructured::new(&[]);
+	let mut evaluated = false;
+	let result = branch_arbitrary!(&mut u, {
+		{ evaluated = true; 1 },
+		{ evaluated = true; 2 },
+	});
+	assert_eq!(result, Err(Error::NotEnoughData));
+	assert!(!evaluated);
+}
+
+#[test]
+fn exhaustion_unwinds_recursion() {
+	// Selects `Add`, then its left operand `Lit(5)`, then runs out
+	let mut u = Unstructured::new(&[2, 0, 5]);
+	assert_eq!(Expr::arbitrary(&mut u), Err(Error::NotEnoughData));
+}
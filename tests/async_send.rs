@@ -0,0 +1,64 @@
+//! Checks that branching within an `async fn` keeps its future `Send`, even
+//! if a branch awaits, i.e. that no RNG is held across an `.await`.
+
+use random_branch::branch;
+use random_branch::branch_using;
+use random_branch::branch_weighted;
+use random_branch::branch_weighted_using;
+use tokio::task::yield_now;
+
+
+/// Fails to compile unless the given future is `Send`.
+fn assert_send<F: std::future::Future + Send>(future: F) -> F {
+	future
+}
+
+async fn thread_rng_branch() -> u32 {
+	branch!(
+		{
+			yield_now().await;
+			1
+		},
+		{
+			yield_now().await;
+			yield_now().await;
+			2
+		},
+	)
+}
+
+async fn thread_rng_weighted() -> u32 {
+	branch_weighted!(
+		1 => {
+			yield_now().await;
+			1
+		},
+		2 => 2,
+	)
+}
+
+// `rand::thread_rng()` returns a `ThreadRng`, which isn't `Send`
+async fn explicit_thread_rng() -> u32 {
+	let a = branch_using!(rand::thread_rng(), {
+		{
+			yield_now().await;
+			1
+		},
+		2,
+	});
+	let b = branch_weighted_using!(rand::thread_rng(), {
+		1 => {
+			yield_now().await;
+			10
+		},
+		1 => 20,
+	});
+	a + b
+}
+
+#[tokio::test]
+async fn futures_are_send() {
+	assert!([1, 2].contains(&assert_send(thread_rng_branch()).await));
+	assert!([1, 2].contains(&assert_send(thread_rng_weighted()).await));
+	assert!([11, 12, 21, 22].contains(&assert_send(explicit_thread_rng()).await));
+}
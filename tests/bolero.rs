@@ -0,0 +1,46 @@
+//! Checks that `branch_generator` works with `bolero`'s drivers.
+
+use std::sync::Mutex;
+
+use bolero::check;
+use random_branch::branch_generator;
+
+
+#[test]
+fn all_branches_are_reachable_exhaustively() {
+	let seen = Mutex::new([0_u32; 3]);
+	check!()
+		.exhaustive()
+		.with_generator(branch_generator!({ 0_usize, 1, 2 }))
+		.cloned()
+		.for_each(|index| seen.lock().unwrap()[index] += 1);
+	assert_eq!(*seen.lock().unwrap(), [1, 1, 1]);
+}
+
+#[test]
+fn weighted_branches_exhaustively() {
+	let seen = Mutex::new([0_u32; 3]);
+	check!()
+		.exhaustive()
+		.with_generator(branch_generator!({ 2 => 0_usize, 0 => 1, 3 => 2 }))
+		.cloned()
+		.for_each(|index| seen.lock().unwrap()[index] += 1);
+	assert_eq!(*seen.lock().unwrap(), [2, 0, 3]);
+}
+
+#[test]
+fn random_driver() {
+	let seen = Mutex::new([false; 3]);
+	check!()
+		.with_iterations(200)
+		.with_generator(branch_generator!({ 'a', 'b', 'c' }))
+		.cloned()
+		.for_each(|c| seen.lock().unwrap()[(c as u8 - b'a') as usize] = true);
+	assert_eq!(*seen.lock().unwrap(), [true; 3]);
+}
+
+#[test]
+#[should_panic(expected = "All weights are zero.")]
+fn all_weights_zero() {
+	let _ = branch_generator!({ 0 => 'a', 0 => 'b' });
+}
@@ -0,0 +1,107 @@
+//! Checks the output of `branch_dbg_using` and `branch_dbg` on stderr.
+//!
+//! Since the test harness captures the output of `eprintln!` only for its own
+//! reports, each scenario runs in a child process of this test binary, whose
+//! stderr is inspected.
+
+use std::env;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_dbg;
+use random_branch::branch_dbg_using;
+
+mod support;
+
+
+/// The environment variable selecting the scenario to run in the child.
+const SCENARIO: &str = "RANDOM_BRANCH_DBG_SCENARIO";
+
+/// Runs the given test of this binary in a child process with the given
+/// scenario, and returns its stderr.
+fn run_child(test: &str, scenario: &str) -> String {
+	let output = support::run_child(test, &[(SCENARIO, Some(scenario))]);
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+	String::from_utf8(output.stderr).unwrap()
+}
+
+/// Returns the single line printed by `branch_dbg`, ignoring the output of
+/// the test harness.
+fn dbg_line(stderr: &str) -> &str {
+	let lines: Vec<&str> = stderr.lines().filter(|l| l.starts_with('[')).collect();
+	assert_eq!(lines.len(), 1, "{}", stderr);
+	lines[0]
+}
+
+
+#[test]
+fn prints_value_form() {
+	if env::var(SCENARIO).as_deref() == Ok("value") {
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		let value = branch_dbg_using!(rng, { 1, 2 * 2, 3 * 3 });
+		println!("value {} line {}", value, line!() - 1);
+		return;
+	}
+
+	let stderr = run_child("prints_value_form", "value");
+	if !cfg!(debug_assertions) {
+		// Without `@always`, release builds don't print anything
+		assert!(!stderr.lines().any(|l| l.starts_with('[')), "{}", stderr);
+		return;
+	}
+	let line = dbg_line(&stderr);
+	let expected = ["1", "2 * 2", "3 * 3"];
+	let index = expected.iter().position(|e| line.ends_with(&format!(": {}", e))).unwrap();
+	assert!(line.starts_with(&format!("[{}:", file!())), "{}", line);
+	assert!(line.contains(&format!("] branch {} of 3: ", index)), "{}", line);
+}
+
+#[test]
+fn prints_statement_form() {
+	if env::var(SCENARIO).as_deref() == Ok("statement") {
+		let mut rng = Lcg64Xsh32::new(0, 0);
+		branch_dbg_using!(@always rng, {
+			eprintln!("first");
+			eprintln!("second");
+		});
+		return;
+	}
+
+	let stderr = run_child("prints_statement_form", "statement");
+	let line = dbg_line(&stderr);
+	let (text, printed) = if line.ends_with("eprintln!(\"first\")") {
+		("0 of 2", "first")
+	} else {
+		("1 of 2", "second")
+	};
+	assert!(line.contains(&format!("] branch {}: eprintln!(\"{}\")", text, printed)), "{}", line);
+
+	// The branch is evaluated after the line is printed
+	let rest = &stderr[stderr.find(line).unwrap() + line.len()..];
+	assert!(rest.lines().any(|l| l == printed), "{}", stderr);
+}
+
+#[test]
+fn prints_location() {
+	if env::var(SCENARIO).as_deref() == Ok("location") {
+		let value = branch_dbg!(@always "only");
+		assert_eq!(value, "only");
+		return;
+	}
+
+	let stderr = run_child("prints_location", "location");
+	let expected_line = line!() - 6;
+	let line = dbg_line(&stderr);
+	assert!(
+		line.starts_with(&format!("[{}:{}:", file!(), expected_line)),
+		"{}",
+		line,
+	);
+	assert!(line.ends_with("] branch 0 of 1: \"only\""), "{}", line);
+}
+
+#[test]
+fn returns_value_unchanged() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let value = branch_dbg_using!(rng, { vec![1, 2], vec![3] });
+	assert!(value == [1, 2] || value == [3]);
+}
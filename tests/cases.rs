@@ -0,0 +1,97 @@
+//! Checks the `cases` attribute.
+
+use std::cell::RefCell;
+use std::env;
+use std::process::Command;
+
+use rand::Rng;
+use rand::RngCore;
+use random_branch::branch_using;
+use random_branch::cases;
+use random_branch::test_seed::case_seed;
+use random_branch::SeededRng;
+
+
+thread_local! {
+	/// The first value drawn by each case of `sample` on this thread.
+	static DRAWN: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cases(4)]
+fn sample(rng: &mut impl Rng) {
+	let value = rng.next_u64();
+	DRAWN.with(|drawn| drawn.borrow_mut().push(value));
+}
+
+/// Calls all cases of `sample` and returns their values.
+fn draw_all() -> Vec<u64> {
+	DRAWN.with(|drawn| drawn.borrow_mut().clear());
+	sample_case_00();
+	sample_case_01();
+	sample_case_02();
+	sample_case_03();
+	DRAWN.with(|drawn| drawn.borrow().clone())
+}
+
+#[test]
+fn cases_are_distinct_and_deterministic() {
+	let drawn = draw_all();
+	assert_eq!(drawn.len(), 4);
+	for (i, a) in drawn.iter().enumerate() {
+		for b in &drawn[i + 1..] {
+			assert_ne!(a, b);
+		}
+	}
+	assert_eq!(drawn, draw_all());
+
+	// Each case is seeded from the path of the function and its index
+	for (index, value) in drawn.iter().enumerate() {
+		let mut rng = SeededRng::new(case_seed("cases::sample", index));
+		assert_eq!(*value, rng.next_u64());
+	}
+}
+
+#[test]
+fn case_seeds_are_stable() {
+	// Changing the seeds is a breaking change
+	assert_eq!(case_seed("cases::sample", 0), 18066099334502929656);
+	assert_ne!(case_seed("cases::sample", 0), case_seed("cases::other", 0));
+}
+
+#[test]
+fn cases_can_be_filtered_by_name() {
+	let output = Command::new(env::current_exe().unwrap())
+		.args(["sample_case_02", "--list"])
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "{:?}", output);
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let tests: Vec<&str> = stdout.lines().filter(|line| line.ends_with(": test")).collect();
+	assert_eq!(tests, ["sample_case_02: test"]);
+}
+
+#[cases(12)]
+#[should_panic(expected = "Boom")]
+fn composes_with_should_panic(rng: &mut SeededRng) {
+	branch_using!(*rng, { panic!("Boom"), panic!("Boom!") });
+}
+
+#[test]
+fn cases_are_zero_padded() {
+	// These only exist if the names are padded to the width of the last one
+	let _: [fn(); 3] = [
+		composes_with_should_panic_case_00,
+		composes_with_should_panic_case_09,
+		composes_with_should_panic_case_11,
+	];
+}
+
+#[cases(3)]
+fn returns_result(rng: &mut impl Rng) -> Result<(), String> {
+	let x = branch_using!(*rng, { 1, 2, 3 });
+	if x > 0 {
+		Ok(())
+	} else {
+		Err(format!("{} isn't positive", x))
+	}
+}
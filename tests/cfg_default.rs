@@ -0,0 +1,57 @@
+//! Checks branches with `#[cfg]` attributes and a `default` branch.
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::branch_using;
+
+
+#[test]
+fn default_is_taken_if_all_branches_are_configured_out() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	for _ in 0..100 {
+		let value = branch_using!(rng, {
+			#[cfg(any())] 1,
+			#[cfg(any())] 2,
+			default: 3,
+		});
+		assert_eq!(value, 3);
+	}
+	assert_eq!(branch!(default: "only"), "only");
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn default_is_not_taken_if_any_branch_remains() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let mut seen = [false; 4];
+	for _ in 0..100 {
+		let value: usize = branch_using!(rng, {
+			#[cfg(all())] 0,
+			#[cfg(any())] 1,
+			2,
+			default: 3,
+		});
+		seen[value] = true;
+	}
+	assert_eq!(seen, [true, false, true, false]);
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn branches_follow_features() {
+	let mut seen = [false; 3];
+	for _ in 0..100 {
+		let value: usize = branch! {
+			#[cfg(feature = "pcg")] 0,
+			#[cfg(feature = "text")] 1,
+			default: 2,
+		};
+		seen[value] = true;
+	}
+	let expected = [
+		cfg!(feature = "pcg"),
+		cfg!(feature = "text"),
+		!cfg!(any(feature = "pcg", feature = "text")),
+	];
+	assert_eq!(seen, expected);
+}
@@ -0,0 +1,62 @@
+//! Checks that the macros work with a cryptographically secure RNG, such as
+//! `ChaCha20Rng`, which is neither `SmallRng` nor `thread_rng`.
+
+use rand::RngCore;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use random_branch::branch_using;
+use random_branch::branch_weighted_using;
+
+
+/// Derives the RNG from a (here, not so) secret key, e.g. of a server.
+fn keyed_rng() -> ChaCha20Rng {
+	let secret = *b"an example secret of 32 bytes!!!";
+	ChaCha20Rng::from_seed(secret)
+}
+
+#[cfg(not(feature = "deterministic"))]
+fn decide(rng: &mut ChaCha20Rng) -> u8 {
+	branch_using!(*rng, { 0, 1, 2 })
+}
+
+
+#[test]
+fn owned_rng() {
+	let mut rng = keyed_rng();
+	let value = branch_using!(rng, { 'a', 'b', 'c' });
+	assert!(('a'..='c').contains(&value));
+
+	let value = branch_weighted_using!(rng, { 1 => 'a', 0 => 'b' });
+	assert_eq!(value, 'a');
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn deterministic_for_the_same_secret() {
+	let run = || {
+		let mut rng = keyed_rng();
+		let decisions: Vec<u8> = (0..32).map(|_| decide(&mut rng)).collect();
+		decisions
+	};
+	let decisions = run();
+	assert_eq!(decisions, run());
+	assert!((0..3).all(|d| decisions.contains(&d)), "{:?}", decisions);
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn different_secrets_differ() {
+	let mut a = keyed_rng();
+	let mut b = ChaCha20Rng::from_seed([0; 32]);
+	let a: Vec<u8> = (0..32).map(|_| decide(&mut a)).collect();
+	let b: Vec<u8> = (0..32).map(|_| decide(&mut b)).collect();
+	assert_ne!(a, b);
+}
+
+#[test]
+fn trait_object() {
+	let mut rng = keyed_rng();
+	let rng: &mut dyn RngCore = &mut rng;
+	let value = branch_using!(*rng, { 1, 2 });
+	assert!(value == 1 || value == 2);
+}
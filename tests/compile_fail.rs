@@ -0,0 +1,11 @@
+//! Checks that misuses of the macros are rejected at compile time.
+//!
+//! The `pass` cases also make `trybuild` build instead of just check the
+//! cases, which is required for errors from evaluating inline `const` blocks.
+
+#[test]
+fn ui() {
+	let t = trybuild::TestCases::new();
+	t.compile_fail("tests/ui/*.rs");
+	t.pass("tests/ui/pass/*.rs");
+}
@@ -0,0 +1,60 @@
+//! Checks the branch coverage counters.
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_using;
+#[cfg(not(feature = "deterministic"))]
+use random_branch::branch_weighted_using;
+use random_branch::coverage;
+
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn counters_add_up() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let line = line!() + 2;
+	for _ in 0..1000 {
+		branch_using!(rng, { 1, 2, 3 });
+	}
+
+	let report = coverage::report();
+	let sites: Vec<_> = report.at(file!(), line).collect();
+	assert_eq!(sites.len(), 1, "{}", report);
+	let site = sites[0];
+	assert_eq!(site.hits.len(), 3);
+	assert_eq!(site.total(), 1000);
+	assert!(site.hits.iter().all(|&h| h > 200), "{}", site);
+	assert_eq!(site.never_hit().count(), 0);
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn never_hit_is_detected() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let line = line!() + 2;
+	for _ in 0..100 {
+		branch_weighted_using!(rng, { 1 => 'a', 0 => 'b', 1 => 'c' });
+	}
+
+	let report = coverage::report();
+	let site = report.at(file!(), line).next().unwrap();
+	assert_eq!(site.never_hit().collect::<Vec<_>>(), [1]);
+	assert_eq!(site.total(), 100);
+
+	let message = std::panic::catch_unwind(coverage::assert_all_branches_hit)
+		.unwrap_err()
+		.downcast::<String>()
+		.unwrap();
+	let expected = format!("{}:{}:", file!(), line);
+	assert!(message.contains(&expected), "{}", message);
+	assert!(message.contains("(never taken: [1])"), "{}", message);
+}
+
+#[test]
+fn unexecuted_sites_are_not_registered() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let line = line!() + 2;
+	if rng.clone() != rng {
+		branch_using!(rng, { 1, 2 });
+	}
+	assert_eq!(coverage::report().at(file!(), line).count(), 0);
+}
@@ -0,0 +1,47 @@
+//! Smoke-tests the locking of the global RNG with the `critical-section`
+//! feature, using the `std` implementation of the critical sections.
+
+use std::collections::HashSet;
+use std::thread;
+
+use rand::RngCore;
+use random_branch::branch_using;
+use random_branch::set_global_rng;
+use random_branch::BenchRng;
+use random_branch::GlobalRng;
+
+
+const THREADS: usize = 8;
+const DRAWS: usize = 1000;
+
+/// All checks share the global RNG, so they are run in order by one test.
+#[test]
+fn concurrent_draws_are_consistent() {
+	assert!(set_global_rng(BenchRng::new(42)).is_ok());
+
+	let handles: Vec<_> = (0..THREADS)
+		.map(|_| {
+			thread::spawn(|| {
+				(0..DRAWS)
+					.map(|_| {
+						// Also nest a draw within a branch
+						let x = GlobalRng.next_u64();
+						branch_using!(GlobalRng, { x, x });
+						x
+					})
+					.collect::<Vec<u64>>()
+			})
+		})
+		.collect();
+	let mut drawn: Vec<u64> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+
+	// Each value is from the sequence of the RNG, and none was drawn twice by
+	// racing draws. The branches may take a few `u64`s each, but rarely more
+	// than ten.
+	let mut rng = BenchRng::new(42);
+	let sequence: HashSet<u64> = (0..10 * THREADS * DRAWS).map(|_| rng.next_u64()).collect();
+	assert!(drawn.iter().all(|x| sequence.contains(x)));
+	drawn.sort_unstable();
+	drawn.dedup();
+	assert_eq!(drawn.len(), THREADS * DRAWS);
+}
@@ -0,0 +1,92 @@
+//! Checks the frames logged with the `defmt` feature, using a host-side
+//! global logger capturing the encoded frames.
+//!
+//! Each frame starts with the interned index of its format string, followed
+//! by the arguments: the branch index and count in the smallest fitting type,
+//! and the interned index of the label, if any.
+//!
+//! The other test targets and the doctests lack a global logger, thus test
+//! this feature with `cargo test --features defmt --test defmt`.
+
+use std::cell::RefCell;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_using;
+
+
+std::thread_local! {
+	static FRAME: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+#[defmt::global_logger]
+struct Capture;
+
+unsafe impl defmt::Logger for Capture {
+	fn acquire() {}
+
+	unsafe fn flush() {}
+
+	unsafe fn release() {}
+
+	unsafe fn write(bytes: &[u8]) {
+		FRAME.with(|f| f.borrow_mut().extend_from_slice(bytes));
+	}
+}
+
+defmt::timestamp!("");
+
+/// Runs `f`, and returns its value and the bytes logged meanwhile.
+fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<u8>) {
+	FRAME.with(|f| f.borrow_mut().clear());
+	let value = f();
+	(value, FRAME.with(|f| f.take()))
+}
+
+
+#[test]
+fn logs_frame_with_u8_index() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (value, frame) = capture(|| branch_using!(rng, "pick", { 0_u8, 1, 2 }));
+
+	// The interned format string, the index and count take a byte each, and
+	// the interned label
+	assert_eq!(frame.len(), 2 + 1 + 1 + 2);
+	assert_eq!(frame[2..4], [value, 3]);
+	// Each call site has its own format string
+	let (_, other) = capture(|| branch_using!(rng, "pick", { 0_u8, 1, 2 }));
+	assert_ne!(frame[..2], other[..2]);
+}
+
+#[test]
+fn logs_frame_with_u16_index() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (_, frame) = capture(|| branch_using!(rng, {
+		0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+		20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39,
+		40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59,
+		60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79,
+		80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95, 96, 97, 98, 99,
+		100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115,
+		116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 126, 127, 128, 129, 130, 131,
+		132, 133, 134, 135, 136, 137, 138, 139, 140, 141, 142, 143, 144, 145, 146, 147,
+		148, 149, 150, 151, 152, 153, 154, 155, 156, 157, 158, 159, 160, 161, 162, 163,
+		164, 165, 166, 167, 168, 169, 170, 171, 172, 173, 174, 175, 176, 177, 178, 179,
+		180, 181, 182, 183, 184, 185, 186, 187, 188, 189, 190, 191, 192, 193, 194, 195,
+		196, 197, 198, 199, 200, 201, 202, 203, 204, 205, 206, 207, 208, 209, 210, 211,
+		212, 213, 214, 215, 216, 217, 218, 219, 220, 221, 222, 223, 224, 225, 226, 227,
+		228, 229, 230, 231, 232, 233, 234, 235, 236, 237, 238, 239, 240, 241, 242, 243,
+		244, 245, 246, 247, 248, 249, 250, 251, 252, 253, 254, 255, 256,
+	}));
+
+	// The interned format string, the index and count, without any label
+	assert_eq!(frame.len(), 2 + 2 + 2);
+	assert_eq!(frame[4..], 257_u16.to_le_bytes());
+}
+
+#[test]
+fn logs_index_selection() {
+	let (value, frame) = capture(|| random_branch::branch_on!(1, { 'a', 'b' }));
+	assert_eq!(value, 'b');
+	// `branch_on` goes through the same call site counters
+	assert!(!frame.is_empty());
+}
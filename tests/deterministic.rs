@@ -0,0 +1,120 @@
+//! Checks that the `deterministic` feature takes the first branches without
+//! using the RNG.
+
+use rand::RngCore;
+use random_branch::branch;
+use random_branch::branch_array_using;
+use random_branch::branch_balanced_using;
+use random_branch::branch_batch_using;
+use random_branch::branch_fallback_using;
+use random_branch::branch_fast_using;
+use random_branch::branch_retry_using;
+use random_branch::branch_subset_using;
+use random_branch::branch_using;
+use random_branch::branch_weighted_using;
+use random_branch::choose_and_run;
+use random_branch::random_bits_using;
+use random_branch::shuffle_using;
+use random_branch::try_branch_weighted_using;
+use random_branch::weighted_pick_using;
+use random_branch::Branch;
+use random_branch::BranchError;
+use random_branch::Pick;
+
+
+/// An RNG that panics whenever it is used.
+struct PanickingRng;
+
+impl RngCore for PanickingRng {
+	fn next_u32(&mut self) -> u32 {
+		panic!("The RNG was used.")
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		panic!("The RNG was used.")
+	}
+
+	fn fill_bytes(&mut self, _dest: &mut [u8]) {
+		panic!("The RNG was used.")
+	}
+
+	fn try_fill_bytes(&mut self, _dest: &mut [u8]) -> Result<(), rand::Error> {
+		panic!("The RNG was used.")
+	}
+}
+
+/// Returns a `PanickingRng`, counting how often it is called.
+fn make_rng(calls: &mut u32) -> PanickingRng {
+	*calls += 1;
+	PanickingRng
+}
+
+
+#[test]
+fn takes_first_branch() {
+	let mut rng = PanickingRng;
+	for _ in 0..100 {
+		assert_eq!(branch_using!(rng, { 1, 2, 3 }), 1);
+		assert_eq!(branch_fast_using!(rng, { 1, 2, 3, 4 }), 1);
+		assert_eq!(branch_subset_using!(rng, 1..=2, { 1, 2, 3, 4 }), 2);
+		assert_eq!(branch!(1, 2, 3), 1);
+	}
+}
+
+#[test]
+fn rng_expression_is_not_evaluated() {
+	let mut calls = 0;
+	let value = branch_using!(make_rng(&mut calls), { "a", "b" });
+	assert_eq!(value, "a");
+	let value = branch_weighted_using!(make_rng(&mut calls), { 1 => "a", 2 => "b" });
+	assert_eq!(value, "a");
+	assert_eq!(calls, 0);
+}
+
+#[test]
+fn weighted_takes_first_non_zero_weight() {
+	let mut rng = PanickingRng;
+	let value = branch_weighted_using!(rng, {
+		0 => "never",
+		5 => "first",
+		1 => "second",
+	});
+	assert_eq!(value, "first");
+
+	let zero = 0_u32;
+	let result = try_branch_weighted_using!(rng, { zero => "a", zero => "b" });
+	assert_eq!(result, Err(BranchError::AllWeightsZero));
+}
+
+#[test]
+fn multi_picks_take_first_branches() {
+	let mut rng = PanickingRng;
+	assert_eq!(branch_array_using!(rng, 3, { 'a', 'b' }), ['a'; 3]);
+	assert_eq!(branch_batch_using!(rng, { 1, 2 }, { 'x', 'y' }), (1, 'x'));
+	assert_eq!(random_bits_using!(rng, 70), [false; 70]);
+
+	let mut values = vec![1, 2, 3, 4];
+	shuffle_using!(rng, &mut values);
+	assert_eq!(values, [1, 2, 3, 4]);
+
+	assert_eq!(weighted_pick_using!(rng, &['a', 'b', 'c'], &[0.0, 0.5, 2.0]), Some(&'b'));
+	assert_eq!(weighted_pick_using!(rng, &['a', 'b'], &[0, 0]), None);
+
+	let mut tried = Vec::new();
+	let value = branch_retry_using!(rng, {
+		{ tried.push(0); Pick::Pass },
+		{ tried.push(1); Pick::Pass },
+		{ tried.push(2); Pick::Take("third") },
+	});
+	assert_eq!(value, Some("third"));
+	assert_eq!(tried, [0, 1, 2]);
+
+	let result: Result<u8, u8> = branch_fallback_using!(rng, { Err(0), Ok(1), Ok(2) });
+	assert_eq!(result, Ok(1));
+
+	let order = branch_balanced_using!(rng, 4, { 1 => 'a', 1 => 'b' });
+	assert_eq!(order, ['a', 'a', 'b', 'b']);
+
+	let branches: Vec<Branch<u8>> = vec![Box::new(|| 1), Box::new(|| 2)];
+	assert_eq!(choose_and_run(&mut rng, branches), 1);
+}
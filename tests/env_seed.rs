@@ -0,0 +1,63 @@
+//! Checks that the `env-seed` feature makes `branch` reproducible via the
+//! `RANDOM_BRANCH_SEED` environment variable.
+//!
+//! Since the variable is read only once per process, each run is a separate
+//! process executing `child_prints_choices` of this very test binary.
+
+// The checks compare random choices
+#![cfg(not(feature = "deterministic"))]
+
+use std::env;
+
+use random_branch::branch;
+
+mod support;
+
+
+/// Marks the process as a child spawned by `run_child`.
+const CHILD_VAR: &str = "RANDOM_BRANCH_TEST_CHILD";
+
+/// Prints a sequence of choices, if running as a child.
+#[test]
+fn child_prints_choices() {
+	if env::var_os(CHILD_VAR).is_none() {
+		return;
+	}
+	let choices: Vec<String> = (0..32).map(|_| branch!(0, 1, 2, 3).to_string()).collect();
+	println!("choices: {}", choices.join(","));
+}
+
+/// Runs `child_prints_choices` in a new process and returns the printed
+/// choices and stderr.
+fn run_child(seed: Option<&str>) -> (String, String) {
+	let output = support::run_child(
+		"child_prints_choices",
+		&[(CHILD_VAR, Some("1")), ("RANDOM_BRANCH_SEED", seed)],
+	);
+	assert!(output.status.success(), "{:?}", output);
+	(support::printed_choices(&output), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn same_seed_same_choices() {
+	let (first, _) = run_child(Some("42"));
+	let (second, _) = run_child(Some("42"));
+	let (other, _) = run_child(Some("43"));
+	assert_eq!(first, second);
+	assert_ne!(first, other);
+}
+
+#[test]
+fn unset_seed_is_random_and_printed() {
+	let (first, stderr) = run_child(None);
+	let (second, _) = run_child(None);
+	assert_ne!(first, second);
+
+	// The printed seed replays the run
+	let seed = stderr
+		.lines()
+		.find_map(|line| line.split("RANDOM_BRANCH_SEED=").nth(1))
+		.unwrap();
+	let (replayed, _) = run_child(Some(seed));
+	assert_eq!(first, replayed);
+}
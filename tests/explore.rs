@@ -0,0 +1,115 @@
+//! Checks that `explore_all` runs every path of branches exactly once.
+
+use std::collections::HashSet;
+use std::panic;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::branch_using;
+use random_branch::explore::explore_all;
+use random_branch::test_override::with_forced;
+
+
+#[test]
+fn three_way_then_two_way() {
+	let mut seen = Vec::new();
+	let paths = explore_all(100, || {
+		let first = branch!(0, 1, 2);
+		let second = branch!('a', 'b');
+		seen.push((first, second));
+	});
+	assert_eq!(paths, Ok(6));
+	assert_eq!(seen, [(0, 'a'), (0, 'b'), (1, 'a'), (1, 'b'), (2, 'a'), (2, 'b')]);
+}
+
+#[test]
+fn exact_bound_is_enough() {
+	assert_eq!(explore_all(6, || {
+		branch!((), (), ());
+		branch!((), ());
+	}), Ok(6));
+}
+
+#[test]
+fn data_dependent_decisions() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let mut seen = Vec::new();
+	let paths = explore_all(100, || {
+		let value = branch_using!(rng, {
+			"leaf",
+			branch!("left", "right"),
+			branch_using!(rng, { "x", "y", "z", "w" }),
+		});
+		seen.push(value);
+	});
+	assert_eq!(paths, Ok(7));
+	assert_eq!(seen, ["leaf", "left", "right", "x", "y", "z", "w"]);
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Tree {
+	Leaf,
+	Node(Box<Tree>, Box<Tree>),
+}
+
+/// An unbounded recursive generator.
+fn tree() -> Tree {
+	branch!(Tree::Leaf, Tree::Node(Box::new(tree()), Box::new(tree())))
+}
+
+#[test]
+fn unbounded_generator_exceeds_bound() {
+	let mut trees = HashSet::new();
+	let mut runs = 0;
+	let result = explore_all(50, || {
+		runs += 1;
+		trees.insert(tree());
+	});
+	let err = result.unwrap_err();
+	assert_eq!(err.max_paths(), 50);
+	assert_eq!(err.to_string(), "there are more than 50 branch paths");
+	assert_eq!(runs, 50);
+	// Each path generated a different tree
+	assert_eq!(trees.len(), 50);
+}
+
+#[test]
+fn without_branches_runs_once() {
+	let mut runs = 0;
+	assert_eq!(explore_all(10, || runs += 1), Ok(1));
+	assert_eq!(runs, 1);
+}
+
+#[test]
+fn forced_branches_are_not_explored() {
+	let mut seen = Vec::new();
+	let paths = explore_all(100, || {
+		let forced = with_forced(&[1], || branch!(0, 1, 2));
+		let explored = branch!(0, 1);
+		seen.push((forced, explored));
+	});
+	assert_eq!(paths, Ok(2));
+	assert_eq!(seen, [(1, 0), (1, 1)]);
+}
+
+#[test]
+fn changing_decisions_panic() {
+	let mut count = 3;
+	let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+		explore_all(100, || {
+			// Has fewer branches on the second execution
+			if count == 3 {
+				branch!(0, 1, 2);
+			} else {
+				branch!(0);
+			}
+			count -= 1;
+		})
+	}));
+	assert!(result.is_err());
+
+	// The exploration is over, so the branches are random again, or always
+	// the first one with the `deterministic` feature
+	let values: HashSet<u8> = (0..100).map(|_| branch!(0, 1)).collect();
+	assert_eq!(values.len(), if cfg!(feature = "deterministic") { 1 } else { 2 });
+}
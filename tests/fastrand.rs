@@ -0,0 +1,101 @@
+//! Checks the `fastrand` backend, with the statistical checks of the
+//! `testing` module if `std` is enabled.
+//!
+//! Run with `--no-default-features --features fastrand` to also check
+//! `branch` drawing from `fastrand`, and that `rand` isn't built at all.
+
+#![no_std]
+// The checks rely on the RNG expressions being evaluated
+#![cfg(not(feature = "deterministic"))]
+
+extern crate std;
+
+use std::vec::Vec;
+
+use random_branch::branch;
+use random_branch::branch_fastrand_using;
+
+
+#[test]
+fn same_seed_same_choices() {
+	let draw = |seed| {
+		let mut rng = fastrand::Rng::with_seed(seed);
+		(0..32).map(|_| branch_fastrand_using!(rng, { 0, 1, 2, 3 })).collect::<Vec<u8>>()
+	};
+	assert_eq!(draw(42), draw(42));
+	assert_ne!(draw(42), draw(43));
+}
+
+#[test]
+fn branch_is_uniform() {
+	let mut counts = [0_u32; 4];
+	for _ in 0..4000 {
+		counts[branch!(0, 1, 2, 3)] += 1;
+	}
+	// Each branch is expected 1000 times, with a standard deviation of ~27
+	for &count in &counts {
+		assert!((800..1200).contains(&count), "{:?}", counts);
+	}
+}
+
+#[test]
+#[cfg(not(any(feature = "std", feature = "global-rng", feature = "getrandom")))]
+fn branch_uses_fastrand() {
+	fastrand::seed(7);
+	let selected: Vec<u8> = (0..32).map(|_| branch!(0, 1, 2, 3)).collect();
+	fastrand::seed(7);
+	let replayed: Vec<u8> = (0..32).map(|_| branch!(0, 1, 2, 3)).collect();
+	assert_eq!(selected, replayed);
+}
+
+#[test]
+#[cfg(not(feature = "rand"))]
+fn rand_is_not_a_dependency() {
+	use std::process::Command;
+	use std::string::String;
+
+	let output = Command::new(env!("CARGO"))
+		.args(["tree", "--offline", "--edges", "normal", "--prefix", "none"])
+		.args(["--no-default-features", "--features", "fastrand"])
+		.arg("--manifest-path")
+		.arg(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+		.output()
+		.expect("Failed to run cargo tree");
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+	let tree = String::from_utf8(output.stdout).unwrap();
+	let names: Vec<&str> = tree.lines().filter_map(|line| line.split_whitespace().next()).collect();
+	assert!(names.contains(&"fastrand"), "{}", tree);
+	assert!(
+		!names.iter().any(|name| *name == "rand" || name.starts_with("rand_")),
+		"{}",
+		tree
+	);
+}
+
+#[cfg(feature = "std")]
+mod statistical {
+	use random_branch::assert_branch_uniform;
+	use random_branch::assert_branch_weighted;
+	use random_branch::FastrandGlobal;
+	use random_branch::FastrandRng;
+
+	#[test]
+	fn uniform() {
+		let mut rng = fastrand::Rng::with_seed(1);
+		assert_branch_uniform!(FastrandRng::new(&mut rng), 10_000, { 0, 1 });
+		assert_branch_uniform!(FastrandRng::new(&mut rng), 10_000, { 0, 1, 2 });
+		assert_branch_uniform!(FastrandRng::new(&mut rng), 10_000, { 0, 1, 2, 3, 4 });
+		assert_branch_uniform!(FastrandGlobal, 10_000, { 0, 1, 2, 3, 4, 5, 6 });
+	}
+
+	#[test]
+	fn weighted() {
+		let mut rng = fastrand::Rng::with_seed(2);
+		assert_branch_weighted!(FastrandRng::new(&mut rng), 10_000, {
+			1 => 'a',
+			2 => 'b',
+			7 => 'c',
+		});
+	}
+}
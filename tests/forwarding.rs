@@ -0,0 +1,84 @@
+//! Checks that the macros can be invoked from user macros, which forward their
+//! branches as `expr` fragments.
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::branch_using;
+use random_branch::branch_weighted_using;
+use random_branch::maybe_using;
+
+
+macro_rules! my_pick {
+	( $( $arm:expr ),* $(,)? ) => {
+		branch!( $( $arm ),* )
+	};
+}
+
+macro_rules! my_pick_using {
+	( $rng:expr, $( $arm:expr ),* $(,)? ) => {
+		branch_using!($rng, { $( $arm ),* })
+	};
+}
+
+/// Forwards twice, and wraps each arm.
+macro_rules! my_pick_doubled {
+	( $rng:expr, $( $arm:expr ),* $(,)? ) => {
+		my_pick_using!($rng, $( 2 * $arm ),*)
+	};
+}
+
+macro_rules! my_weighted {
+	( $rng:expr, $( $weight:expr => $arm:expr ),* $(,)? ) => {
+		branch_weighted_using!($rng, { $( $weight => $arm ),* })
+	};
+}
+
+macro_rules! my_maybe {
+	( $rng:expr, $arm:expr ) => {
+		maybe_using!($rng, 1.0, $arm)
+	};
+}
+
+
+#[test]
+fn forwards_plain_expressions() {
+	let value = my_pick!(1, 2 + 2, { 3 });
+	assert!(value == 1 || value == 4 || value == 3);
+
+	let single = my_pick!("only");
+	assert_eq!(single, "only");
+}
+
+#[test]
+fn forwards_expressions_with_commas_and_blocks() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	for _ in 0..20 {
+		let (a, b) = my_pick_using!(
+			rng,
+			(1, 2),
+			{
+				let x = 3;
+				(x, x + 1)
+			},
+			[5, 6].iter().fold((0, 0), |(_, _), &x| (x, x + 1)),
+		);
+		assert_eq!(a + 1, b);
+	}
+}
+
+#[test]
+fn forwards_through_multiple_macros() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	for _ in 0..20 {
+		let value = my_pick_doubled!(rng, 1, 2, 3 + 1);
+		assert!(value == 2 || value == 4 || value == 8);
+	}
+}
+
+#[test]
+fn forwards_weighted_and_maybe() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let value = my_weighted!(rng, 0 => "never", 1 + 1 => "always");
+	assert_eq!(value, "always");
+	assert_eq!(my_maybe!(rng, (1, 2)), Some((1, 2)));
+}
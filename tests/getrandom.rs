@@ -0,0 +1,42 @@
+//! Checks that the `getrandom` feature provides a uniform `branch` without
+//! `std`, and that `branch` still uses the thread RNG with `std`.
+//!
+//! Run with `--no-default-features --features getrandom` to also build this
+//! crate without `std`.
+
+#![no_std]
+
+extern crate std;
+
+use random_branch::branch;
+
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn selections_are_uniform() {
+	let mut counts = [0_u32; 4];
+	for _ in 0..4000 {
+		counts[branch!(0, 1, 2, 3)] += 1;
+	}
+	// Each branch is expected 1000 times, with a standard deviation of ~27
+	for &count in &counts {
+		assert!((800..1200).contains(&count), "{:?}", counts);
+	}
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn thread_rng_is_used_with_std() {
+	use std::boxed::Box;
+	use std::vec::Vec;
+
+	use random_branch::from_fn;
+	use random_branch::reset_thread_branch_rng;
+	use random_branch::set_thread_branch_rng;
+
+	// The override of the thread RNG is honored
+	set_thread_branch_rng(Box::new(from_fn(|| 0)));
+	let selected: Vec<u8> = (0..10).map(|_| branch!(0, 1, 2)).collect();
+	reset_thread_branch_rng();
+	assert_eq!(selected, [0; 10]);
+}
@@ -0,0 +1,53 @@
+//! Checks that the `global-rng` feature provides `branch` without `std`.
+//!
+//! Run with `--no-default-features --features global-rng` to also build this
+//! crate without `std`, otherwise `branch` uses the thread RNG.
+
+#![no_std]
+// The checks rely on the RNG expressions being evaluated
+#![cfg(not(feature = "deterministic"))]
+
+extern crate std;
+
+use std::panic;
+use std::string::String;
+use std::vec::Vec;
+
+use rand_pcg::Lcg64Xsh32;
+#[cfg(not(feature = "std"))]
+use random_branch::branch;
+use random_branch::branch_using;
+use random_branch::set_global_rng;
+use random_branch::GlobalRng;
+use random_branch::SetGlobalRngError;
+
+
+/// All checks share the global RNG, so they are run in order by one test.
+#[test]
+fn global_rng() {
+	// Not registered yet
+	let err = panic::catch_unwind(|| branch_using!(GlobalRng, { 1, 2 })).unwrap_err();
+	assert_eq!(
+		err.downcast_ref::<String>().map(String::as_str),
+		Some("The global RNG is not set, call `random_branch::set_global_rng` first"),
+	);
+	#[cfg(not(feature = "std"))]
+	assert!(panic::catch_unwind(|| branch!(1, 2)).is_err());
+
+	// Registered only once
+	assert_eq!(set_global_rng(Lcg64Xsh32::new(42, 0)), Ok(()));
+	assert_eq!(set_global_rng(Lcg64Xsh32::new(43, 0)), Err(SetGlobalRngError));
+
+	// Selections are those of the registered RNG
+	let mut expected_rng = Lcg64Xsh32::new(42, 0);
+	let expected: Vec<u8> = (0..32).map(|_| branch_using!(expected_rng, { 0, 1, 2, 3 })).collect();
+	let selected: Vec<u8> = (0..32).map(|_| branch_using!(GlobalRng, { 0, 1, 2, 3 })).collect();
+	assert_eq!(selected, expected);
+
+	#[cfg(not(feature = "std"))]
+	{
+		let expected: Vec<u8> = (0..32).map(|_| branch_using!(expected_rng, { 0, 1, 2, 3 })).collect();
+		let selected: Vec<u8> = (0..32).map(|_| branch!(0, 1, 2, 3)).collect();
+		assert_eq!(selected, expected);
+	}
+}
@@ -0,0 +1,84 @@
+//! Checks the records logged with the `log` feature.
+
+use std::panic;
+use std::sync::Mutex;
+
+use log::Level;
+use log::LevelFilter;
+use log::Log;
+use log::Metadata;
+use log::Record;
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_using;
+
+
+/// The messages of all records of this crate.
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Serializes the tests, since the logger is global.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// A logger capturing the records of this crate.
+struct Capture;
+
+impl Log for Capture {
+	fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+		true
+	}
+
+	fn log(&self, record: &Record<'_>) {
+		if record.target() == "random_branch" {
+			assert_eq!(record.level(), Level::Trace);
+			MESSAGES.lock().unwrap().push(record.args().to_string());
+		}
+	}
+
+	fn flush(&self) {}
+}
+
+/// Runs `f`, and returns its value and the captured messages.
+fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+	let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+	let _ = log::set_logger(&Capture);
+	log::set_max_level(LevelFilter::Trace);
+	MESSAGES.lock().unwrap().clear();
+	let value = f();
+	let messages = std::mem::take(&mut *MESSAGES.lock().unwrap());
+	(value, messages)
+}
+
+
+#[test]
+fn logs_selection() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let ((value, line), messages) = capture(|| (branch_using!(rng, { 0, 1, 2 }), line!()));
+
+	assert_eq!(messages.len(), 1);
+	let prefix = format!("{}:{}:", file!(), line);
+	assert!(messages[0].starts_with(&prefix), "{}", messages[0]);
+	let suffix = format!(": selected branch {} of 3", value);
+	assert!(messages[0].ends_with(&suffix), "{}", messages[0]);
+}
+
+#[test]
+fn logs_label() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (value, messages) = capture(|| branch_using!(rng, "greeting", { 0, 1 }));
+
+	assert_eq!(messages.len(), 1);
+	let suffix = format!(": selected branch {} of 2 (greeting)", value);
+	assert!(messages[0].ends_with(&suffix), "{}", messages[0]);
+}
+
+#[test]
+fn logs_before_panicking_branch() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (result, messages) = capture(|| {
+		panic::catch_unwind(panic::AssertUnwindSafe(|| -> u32 {
+			branch_using!(rng, { panic!("first"), panic!("second") })
+		}))
+	});
+
+	assert!(result.is_err());
+	assert_eq!(messages.len(), 1);
+}
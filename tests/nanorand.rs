@@ -0,0 +1,94 @@
+//! Checks the `nanorand` backend, with the statistical checks of the
+//! `testing` module if `std` is enabled.
+//!
+//! Run with `--no-default-features --features nanorand` to also build this
+//! crate without `std`.
+
+#![no_std]
+
+extern crate std;
+
+use std::vec::Vec;
+
+use nanorand::ChaCha20;
+use nanorand::WyRand;
+use random_branch::branch_nanorand_using;
+
+
+#[test]
+fn same_seed_same_choices() {
+	let draw = |seed| {
+		let mut rng = WyRand::new_seed(seed);
+		(0..32).map(|_| branch_nanorand_using!(rng, { 0, 1, 2, 3 })).collect::<Vec<u8>>()
+	};
+	assert_eq!(draw(42), draw(42));
+	#[cfg(not(feature = "deterministic"))]
+	assert_ne!(draw(42), draw(43));
+}
+
+#[test]
+fn labeled() {
+	let mut rng = ChaCha20::new_key([1; 32], [0; 8], [0; 8]);
+	let x = branch_nanorand_using!(rng, "label", { 1, 2 });
+	assert!(x == 1 || x == 2);
+}
+
+#[test]
+fn single_branch() {
+	let mut rng = WyRand::new_seed(1);
+	for _ in 0..10 {
+		assert_eq!(branch_nanorand_using!(rng, { "only" }), "only");
+	}
+}
+
+#[cfg(all(feature = "std", not(feature = "deterministic")))]
+mod statistical {
+	use nanorand::ChaCha20;
+	use nanorand::WyRand;
+	use random_branch::branch;
+	use random_branch::branch_nanorand;
+	use random_branch::branch_nanorand_using;
+	use random_branch::testing::assert_uniform;
+	use random_branch::testing::DEFAULT_SIGNIFICANCE;
+
+	/// Counts the branches of `draw` taken in `iterations` draws.
+	fn counts<const N: usize>(iterations: usize, mut draw: impl FnMut() -> usize) -> [u64; N] {
+		let mut counts = [0; N];
+		for _ in 0..iterations {
+			counts[draw()] += 1;
+		}
+		counts
+	}
+
+	#[test]
+	fn wyrand_uniform() {
+		let mut rng = WyRand::new_seed(1);
+		assert_uniform(&counts::<2>(10_000, || branch_nanorand_using!(rng, { 0, 1 })), DEFAULT_SIGNIFICANCE);
+		assert_uniform(&counts::<3>(10_000, || branch_nanorand_using!(rng, { 0, 1, 2 })), DEFAULT_SIGNIFICANCE);
+		assert_uniform(
+			&counts::<5>(10_000, || branch_nanorand_using!(rng, { 0, 1, 2, 3, 4 })),
+			DEFAULT_SIGNIFICANCE,
+		);
+	}
+
+	#[test]
+	fn chacha_uniform() {
+		let mut rng = ChaCha20::new_key([7; 32], [0; 8], [0; 8]);
+		assert_uniform(
+			&counts::<7>(10_000, || branch_nanorand_using!(rng, { 0, 1, 2, 3, 4, 5, 6 })),
+			DEFAULT_SIGNIFICANCE,
+		);
+	}
+
+	#[test]
+	fn thread_local_uniform() {
+		assert_uniform(&counts::<3>(10_000, || branch_nanorand!(0, 1, 2)), DEFAULT_SIGNIFICANCE);
+	}
+
+	#[test]
+	fn alongside_rand_backend() {
+		let a: u8 = branch!(1, 2);
+		let b: u8 = branch_nanorand!(1, 2);
+		assert!((1..=2).contains(&a) && (1..=2).contains(&b));
+	}
+}
@@ -0,0 +1,194 @@
+//! Checks that `branch_vec_using`, `branch_balanced_using`,
+//! `weighted_pick_using` and `choose_and_run` work in a `no_std` crate with just `alloc`.
+//!
+//! Run with `--no-default-features --features alloc` to also build this crate
+//! without `std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_balanced_using;
+use random_branch::branch_vec_using;
+use random_branch::choose_and_run;
+use random_branch::try_choose_and_run;
+use random_branch::weighted_pick_using;
+use random_branch::Branch;
+use random_branch::BranchError;
+
+
+#[test]
+fn length_and_capacity() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let v: Vec<u8> = branch_vec_using!(rng, 100, { 1, 2, 3 });
+	assert_eq!(v.len(), 100);
+	assert_eq!(v.capacity(), 100);
+}
+
+#[test]
+fn empty_does_not_touch_rng() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let before = rng.clone();
+	let v: Vec<u8> = branch_vec_using!(rng, 0, { 1, 2, 3 });
+	assert!(v.is_empty());
+	assert_eq!(rng, before);
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn frequencies_are_uniform() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let v: Vec<usize> = branch_vec_using!(rng, 9000, { 0, 1, 2 });
+	let mut counts = [0_u32; 3];
+	for i in v {
+		counts[i] += 1;
+	}
+	for &c in &counts {
+		assert!(2700 < c && c < 3300, "{:?}", counts);
+	}
+}
+
+fn count(v: &[usize], i: usize) -> usize {
+	v.iter().filter(|&&x| x == i).count()
+}
+
+#[test]
+fn balanced_exact_counts() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let v: Vec<usize> = branch_balanced_using!(rng, 100, { 5 => 0, 3 => 1, 2 => 2 });
+	assert_eq!(v.len(), 100);
+	assert_eq!([count(&v, 0), count(&v, 1), count(&v, 2)], [50, 30, 20]);
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn balanced_order_is_random() {
+	let run = |seed| {
+		let mut rng = Lcg64Xsh32::new(seed, 0);
+		let v: Vec<usize> = branch_balanced_using!(rng, 30, { 1 => 0, 1 => 1, 1 => 2 });
+		v
+	};
+	let first = run(1);
+	assert!(first.windows(2).any(|w| w[0] > w[1]), "{:?}", first);
+	assert_ne!(first, run(2));
+	assert_eq!(first, run(1));
+}
+
+#[test]
+fn balanced_rounding() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+
+	// Exact shares 3.33.., 3.33.., 3.33.., ties go to the earlier branches
+	let v: Vec<usize> = branch_balanced_using!(rng, 10, { 1 => 0, 1 => 1, 1 => 2 });
+	assert_eq!([count(&v, 0), count(&v, 1), count(&v, 2)], [4, 3, 3]);
+
+	// Exact shares 1.4, 2.1, 3.5, the largest remainder wins
+	let v: Vec<usize> = branch_balanced_using!(rng, 7, { 2 => 0, 3 => 1, 5 => 2 });
+	assert_eq!([count(&v, 0), count(&v, 1), count(&v, 2)], [1, 2, 4]);
+
+	// Zero weights are never evaluated
+	let v: Vec<usize> = branch_balanced_using!(rng, 5, { 0 => 0, 1 => 1, 0 => 2 });
+	assert_eq!(v, [1; 5]);
+
+	let v: Vec<usize> = branch_balanced_using!(rng, 0, { 0 => 0, 0 => 1 });
+	assert!(v.is_empty());
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn choose_and_run_runs_exactly_one() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let mut counts = [0_u32; 3];
+	for _ in 0..3000 {
+		let mut runs = [0_u32; 3];
+		let branches: Vec<Branch<usize>> = runs
+			.iter_mut()
+			.enumerate()
+			.map(|(i, r)| Box::new(move || { *r += 1; i }) as Branch<usize>)
+			.collect();
+		let i = choose_and_run(&mut rng, branches);
+		assert_eq!(runs.iter().sum::<u32>(), 1);
+		assert_eq!(runs[i], 1);
+		counts[i] += 1;
+	}
+	for &c in &counts {
+		assert!(900 < c && c < 1100, "{:?}", counts);
+	}
+}
+
+#[test]
+fn choose_and_run_single() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let branches: Vec<Branch<&str>> = vec![Box::new(|| "only")];
+	assert_eq!(choose_and_run(&mut rng, branches), "only");
+}
+
+#[test]
+#[should_panic(expected = "You must provide at least one choice.")]
+fn choose_and_run_empty() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	choose_and_run::<(), _>(&mut rng, Vec::new());
+}
+
+#[test]
+fn try_choose_and_run_empty() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let before = rng.clone();
+	assert_eq!(try_choose_and_run::<(), _>(&mut rng, Vec::new()), Err(BranchError::Empty));
+	assert_eq!(rng, before);
+
+	let branches: Vec<Branch<u8>> = vec![Box::new(|| 1)];
+	assert_eq!(try_choose_and_run(&mut rng, branches), Ok(1));
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn weighted_pick_frequencies() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let items = ['a', 'b', 'c'];
+	let weights = [1_u32, 3, 0];
+	let mut counts = [0_u32; 3];
+	for _ in 0..8000 {
+		let item = weighted_pick_using!(rng, &items, &weights).unwrap();
+		counts[(*item as u8 - b'a') as usize] += 1;
+	}
+	assert!(1800 < counts[0] && counts[0] < 2200, "{:?}", counts);
+	assert!(5800 < counts[1] && counts[1] < 6200, "{:?}", counts);
+	assert_eq!(counts[2], 0);
+}
+
+#[test]
+fn weighted_pick_float_weights() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let items = vec!["only", "never"];
+	let weights = vec![0.25, 0.0];
+	for _ in 0..100 {
+		assert_eq!(weighted_pick_using!(rng, &items, &weights), Some(&"only"));
+	}
+}
+
+#[test]
+fn weighted_pick_degenerate() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let before = rng.clone();
+
+	// Empty
+	assert_eq!(weighted_pick_using!(rng, &[] as &[u8], &[] as &[u32]), None);
+	// Mismatched lengths
+	assert_eq!(weighted_pick_using!(rng, &[1, 2, 3], &[1_u32, 1]), None);
+	assert_eq!(weighted_pick_using!(rng, &[1], &[1_u32, 1]), None);
+	assert_eq!(weighted_pick_using!(rng, &[] as &[u8], &[1_u32]), None);
+	// Invalid weights
+	assert_eq!(weighted_pick_using!(rng, &[1, 2], &[0_u32, 0]), None);
+	assert_eq!(weighted_pick_using!(rng, &[1, 2], &[1.0, -1.0]), None);
+	assert_eq!(weighted_pick_using!(rng, &[1, 2], &[1.0, f64::NAN]), None);
+	assert_eq!(rng, before);
+
+	// A single item
+	assert_eq!(weighted_pick_using!(rng, &[7], &[5_u32]), Some(&7));
+}
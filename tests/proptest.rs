@@ -0,0 +1,83 @@
+//! Checks that `branch_strategy` works with `proptest`, including shrinking.
+
+use proptest::prelude::*;
+use proptest::strategy::ValueTree;
+use proptest::test_runner::Config;
+use proptest::test_runner::TestError;
+use proptest::test_runner::TestRunner;
+use random_branch::branch_strategy;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+	Read,
+	Write,
+	Flush,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+	branch_strategy!({ Op::Read, Op::Write, Op::Flush })
+}
+
+proptest! {
+	#[test]
+	fn yields_only_the_branches(op in op()) {
+		prop_assert!(matches!(op, Op::Read | Op::Write | Op::Flush));
+	}
+
+	#[test]
+	fn composes_with_collections(ops in prop::collection::vec(op(), 1..20)) {
+		prop_assert!(!ops.is_empty());
+	}
+
+	#[test]
+	fn composes_with_inner_strategies(
+		value in branch_strategy!({
+			(0..10_u32).boxed(),
+			(100..110_u32).boxed(),
+		}).prop_flat_map(|s| s)
+	) {
+		prop_assert!(value < 10 || (100..110).contains(&value));
+	}
+}
+
+
+#[test]
+fn all_branches_are_generated() {
+	let mut runner = TestRunner::deterministic();
+	let mut seen = [false; 3];
+	for _ in 0..100 {
+		let op = op().new_tree(&mut runner).unwrap().current();
+		seen[op as usize] = true;
+	}
+	assert_eq!(seen, [true; 3]);
+}
+
+#[test]
+fn shrinks_to_first_branch() {
+	let mut runner = TestRunner::new(Config {
+		failure_persistence: None,
+		..Config::default()
+	});
+	let result = runner.run(&op(), |_| Err(TestCaseError::fail("always fails")));
+	match result {
+		Err(TestError::Fail(_, value)) => assert_eq!(value, Op::Read),
+		other => panic!("unexpected result: {:?}", other),
+	}
+}
+
+#[test]
+fn shrinks_to_earliest_failing_branch() {
+	let mut runner = TestRunner::new(Config {
+		failure_persistence: None,
+		..Config::default()
+	});
+	let result = runner.run(&op(), |op| {
+		prop_assert_eq!(op, Op::Read);
+		Ok(())
+	});
+	match result {
+		Err(TestError::Fail(_, value)) => assert_eq!(value, Op::Write),
+		other => panic!("unexpected result: {:?}", other),
+	}
+}
@@ -0,0 +1,64 @@
+//! Checks that `branch_fill_using` works in a `no_std` crate without any
+//! allocator.
+//!
+//! Run with `--no-default-features --features heapless,arrayvec` to also build
+//! this crate without `std`.
+
+#![no_std]
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_fill_using;
+
+
+#[test]
+fn fills_heapless_until_full() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let mut queue: heapless::Vec<u8, 16> = heapless::Vec::new();
+	queue.push(0).unwrap();
+
+	let written = branch_fill_using!(rng, &mut queue, { 1, 2, 3 });
+	assert_eq!(written, 15);
+	assert!(queue.is_full());
+	assert_eq!(queue[0], 0);
+	assert!(queue[1..].iter().all(|&x| (1..=3).contains(&x)));
+}
+
+#[test]
+fn already_full_heapless_is_untouched() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let before = rng.clone();
+	let mut queue: heapless::Vec<u8, 2> = heapless::Vec::new();
+	queue.push(7).unwrap();
+	queue.push(8).unwrap();
+
+	let mut evaluated = false;
+	let written = branch_fill_using!(rng, &mut queue, {
+		{ evaluated = true; 1 },
+		{ evaluated = true; 2 },
+	});
+	assert_eq!(written, 0);
+	assert!(!evaluated);
+	assert_eq!(rng, before);
+	assert_eq!(&queue[..], &[7, 8]);
+}
+
+#[test]
+fn fills_arrayvec_exactly() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let mut queue: arrayvec::ArrayVec<u8, 4> = arrayvec::ArrayVec::new();
+
+	let written = branch_fill_using!(rng, &mut queue, { 1, 2 });
+	assert_eq!(written, 4);
+	assert!(queue.is_full());
+	assert!(queue.iter().all(|&x| x == 1 || x == 2));
+}
+
+#[test]
+fn already_full_arrayvec_is_untouched() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let mut queue: arrayvec::ArrayVec<u8, 0> = arrayvec::ArrayVec::new();
+
+	let written = branch_fill_using!(rng, &mut queue, { 1, 2 });
+	assert_eq!(written, 0);
+	assert!(queue.is_empty());
+}
@@ -0,0 +1,111 @@
+//! Checks that `branch_qc` works within `quickcheck` generators.
+
+use quickcheck::Arbitrary;
+use quickcheck::Gen;
+use quickcheck::QuickCheck;
+use random_branch::branch_qc;
+
+
+#[derive(Debug, Clone)]
+enum Expr {
+	Lit(i8),
+	Neg(Box<Expr>),
+	Add(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+	fn generate(g: &mut Gen, depth: usize) -> Self {
+		branch_qc!(g, depth = depth, {
+			Expr::Lit(i8::arbitrary(g)),
+			Expr::Neg(Box::new(Expr::generate(g, depth + 1))),
+			Expr::Add(
+				Box::new(Expr::generate(g, depth + 1)),
+				Box::new(Expr::generate(g, depth + 1)),
+			),
+		})
+	}
+
+	fn depth(&self) -> usize {
+		match self {
+			Expr::Lit(_) => 0,
+			Expr::Neg(e) => 1 + e.depth(),
+			Expr::Add(a, b) => 1 + a.depth().max(b.depth()),
+		}
+	}
+
+	fn eval(&self) -> i64 {
+		match self {
+			Expr::Lit(x) => i64::from(*x),
+			Expr::Neg(e) => -e.eval(),
+			Expr::Add(a, b) => a.eval() + b.eval(),
+		}
+	}
+
+	fn mirror(&self) -> Self {
+		match self {
+			Expr::Lit(x) => Expr::Lit(*x),
+			Expr::Neg(e) => Expr::Neg(Box::new(e.mirror())),
+			Expr::Add(a, b) => Expr::Add(Box::new(b.mirror()), Box::new(a.mirror())),
+		}
+	}
+}
+
+impl Arbitrary for Expr {
+	fn arbitrary(g: &mut Gen) -> Self {
+		Expr::generate(g, 0)
+	}
+}
+
+
+#[test]
+fn property_over_expression_trees() {
+	fn mirror_preserves_value(e: Expr) -> bool {
+		e.eval() == e.mirror().eval()
+	}
+	QuickCheck::new()
+		.rng(Gen::new(8))
+		.quickcheck(mirror_preserves_value as fn(Expr) -> bool);
+}
+
+#[test]
+fn depth_is_bounded_by_size() {
+	let mut g = Gen::new(4);
+	let mut max = 0;
+	for _ in 0..1000 {
+		max = max.max(Expr::arbitrary(&mut g).depth());
+	}
+	assert_eq!(max, 4);
+}
+
+#[test]
+fn uniform_selection() {
+	let mut g = Gen::new(10);
+	let mut counts = [0_u32; 3];
+	for _ in 0..3000 {
+		counts[branch_qc!(&mut g, { 0, 1, 2 })] += 1;
+	}
+	for &c in &counts {
+		assert!(900 < c && c < 1100, "{:?}", counts);
+	}
+}
+
+#[test]
+fn weighted_selection() {
+	let mut g = Gen::new(10);
+	let mut counts = [0_u32; 3];
+	for _ in 0..4000 {
+		counts[branch_qc!(&mut g, { 3 => 0, 0 => 1, 1 => 2 })] += 1;
+	}
+	assert_eq!(counts[1], 0);
+	assert!(2800 < counts[0] && counts[0] < 3200, "{:?}", counts);
+
+	// Exhausted, but the first weight is zero
+	let value = branch_qc!(&mut g, depth = 10, { 0 => 0, 1 => 1 });
+	assert_eq!(value, 1);
+}
+
+#[test]
+#[should_panic(expected = "All weights are zero.")]
+fn all_weights_zero() {
+	branch_qc!(&mut Gen::new(10), { 0 => 'a', 0 => 'b' });
+}
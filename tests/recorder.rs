@@ -0,0 +1,235 @@
+//! Checks that a `Replay` reproduces a recorded run exactly, and reports
+//! mismatches.
+
+use rand::SeedableRng;
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::branch_using;
+use random_branch::recorder::Decision;
+use random_branch::recorder::Recorder;
+use random_branch::recorder::Replay;
+
+
+/// Some code with nested branches and a data-dependent number of them.
+fn walk(rng: &mut Lcg64Xsh32) -> Vec<String> {
+	let mut steps = Vec::new();
+	for _ in 0..20 {
+		let step = branch_using!(*rng, {
+			String::from("stay"),
+			format!("go {}", branch!("north", "south", "east", "west")),
+			{
+				let jumps = branch_using!(*rng, { 1, 2, 3 });
+				format!("jump {}", jumps)
+			},
+		});
+		steps.push(step);
+	}
+	steps
+}
+
+fn other() -> u32 {
+	branch!(1, 2, 3)
+}
+
+#[test]
+fn replay_reproduces_run() {
+	let recorder = Recorder::install();
+	let recorded = walk(&mut Lcg64Xsh32::seed_from_u64(1));
+	let replay = recorder.finish();
+	assert!(replay.decisions().len() >= 20);
+
+	// A different seed would take a different path, but the replay wins
+	let replaying = replay.clone().install();
+	let replayed = walk(&mut Lcg64Xsh32::seed_from_u64(2));
+	replaying.finish();
+	assert_eq!(recorded, replayed);
+
+	// Recording the replay yields the same decisions
+	let recorder = Recorder::install();
+	let replaying = replay.clone().install();
+	walk(&mut Lcg64Xsh32::seed_from_u64(3));
+	replaying.finish();
+	assert_eq!(recorder.finish(), replay);
+}
+
+#[test]
+fn nothing_recorded_without_recorder() {
+	let recorder = Recorder::install();
+	drop(recorder);
+	other();
+	let recorder = Recorder::install();
+	assert!(recorder.finish().decisions().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "The replay diverged at decision 0: expected branch")]
+fn diverging_call_site_panics() {
+	let recorder = Recorder::install();
+	walk(&mut Lcg64Xsh32::seed_from_u64(1));
+	let replay = recorder.finish();
+
+	let _replaying = replay.install();
+	other();
+}
+
+#[test]
+#[should_panic(expected = "but got a selection among 3 branches at tests/recorder.rs")]
+fn diverging_count_panics() {
+	let recorder = Recorder::install();
+	other();
+	let mut decisions = recorder.finish().into_decisions();
+	decisions[0].count = 4;
+
+	let _replaying = Replay::from(decisions).install();
+	other();
+}
+
+#[test]
+#[should_panic(expected = "The replay is exhausted after 1 decisions")]
+fn exhausted_replay_panics() {
+	let recorder = Recorder::install();
+	other();
+	let replay = recorder.finish();
+
+	let _replaying = replay.install();
+	other();
+	other();
+}
+
+#[test]
+#[should_panic(expected = "The replay has 1 decisions left, the next is branch")]
+fn unfinished_replay_panics() {
+	let recorder = Recorder::install();
+	other();
+	other();
+	let replay = recorder.finish();
+
+	let replaying = replay.install();
+	other();
+	replaying.finish();
+}
+
+#[test]
+fn decisions_describe_call_site() {
+	let recorder = Recorder::install();
+	let value = other();
+	let replay = recorder.finish();
+	let decision: &Decision = &replay.decisions()[0];
+	assert_eq!(decision.file, "tests/recorder.rs");
+	assert_eq!(decision.line, 31);
+	assert_eq!(decision.count, 3);
+	assert_eq!(decision.index as u32 + 1, value);
+}
+
+#[test]
+fn lenient_replay_takes_first_branches() {
+	let recorder = Recorder::install();
+	let recorded = walk(&mut Lcg64Xsh32::seed_from_u64(3));
+	let replay = recorder.finish();
+
+	// Missing decisions select the first branch, so the rest just stays
+	let prefix = Replay::from(replay.decisions()[..1].to_vec());
+	let replaying = prefix.install_lenient();
+	let replayed = walk(&mut Lcg64Xsh32::seed_from_u64(4));
+	replaying.finish();
+	assert_eq!(replayed[0], recorded[0]);
+	assert!(replayed[1..].iter().all(|step| step == "stay"));
+
+	// A diverging call site ends the replay without panicking
+	let replaying = replay.install_lenient();
+	assert_eq!(other(), 1);
+	assert!(walk(&mut Lcg64Xsh32::seed_from_u64(5)).iter().all(|step| step == "stay"));
+	replaying.finish();
+}
+
+/// A long soak run, which fails only if two specific decisions are made.
+#[cfg(not(feature = "deterministic"))]
+fn soak(rng: &mut Lcg64Xsh32) -> bool {
+	let rolls: Vec<u32> = (0..1000).map(|_| branch_using!(*rng, { 0, 1, 2, 3 })).collect();
+	rolls[300] == 3 && rolls[700] == 2
+}
+
+/// Records soak runs until one fails.
+#[cfg(not(feature = "deterministic"))]
+fn failing_soak() -> Replay {
+	(0..)
+		.find_map(|seed| {
+			let recorder = Recorder::install();
+			let failed = soak(&mut Lcg64Xsh32::seed_from_u64(seed));
+			let replay = recorder.finish();
+			if failed {
+				Some(replay)
+			} else {
+				None
+			}
+		})
+		.unwrap()
+}
+
+/// Replays a candidate leniently and returns whether it fails.
+#[cfg(not(feature = "deterministic"))]
+fn soak_fails(candidate: &Replay) -> bool {
+	let _replaying = candidate.clone().install_lenient();
+	soak(&mut Lcg64Xsh32::seed_from_u64(0))
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn minimize_isolates_failing_decisions() {
+	let replay = failing_soak();
+	assert_eq!(replay.decisions().len(), 1000);
+
+	let mut runs = 0;
+	let minimal = replay.minimize(10_000, |candidate| {
+		runs += 1;
+		soak_fails(candidate)
+	});
+	assert!(soak_fails(&minimal));
+	assert!(runs < 1000, "{} runs", runs);
+
+	let picked: Vec<(usize, usize)> = minimal
+		.decisions()
+		.iter()
+		.enumerate()
+		.filter(|(_, decision)| decision.index != 0)
+		.map(|(position, decision)| (position, decision.index))
+		.collect();
+	assert_eq!(picked, [(300, 3), (700, 2)]);
+	assert_eq!(minimal.decisions().len(), 701);
+
+	// The reduction is deterministic
+	assert_eq!(replay.minimize(10_000, soak_fails), minimal);
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn minimize_limits_runs() {
+	let replay = failing_soak();
+
+	let mut runs = 0;
+	let reduced = replay.minimize(5, |candidate| {
+		runs += 1;
+		soak_fails(candidate)
+	});
+	assert_eq!(runs, 5);
+	assert!(soak_fails(&reduced));
+	assert!(reduced.decisions().len() < replay.decisions().len());
+
+	// Without any run, nothing but the trailing first branches are stripped
+	let unchanged = replay.minimize(0, |_| unreachable!());
+	assert_eq!(unchanged.decisions(), &replay.decisions()[..unchanged.decisions().len()]);
+}
+
+#[test]
+fn secure_is_neither_recorded_nor_replayed() {
+	let recorder = Recorder::install();
+	let recorded = [other(), random_branch::branch_secure!(1, 2, 3), other()];
+	let replay = recorder.finish();
+	assert_eq!(replay.decisions().len(), 2);
+
+	// The secure macro doesn't consume the replayed decision of `other`
+	let replaying = replay.install();
+	let replayed = [other(), random_branch::branch_secure!(1, 2, 3), other()];
+	replaying.finish();
+	assert_eq!([recorded[0], recorded[2]], [replayed[0], replayed[2]]);
+}
@@ -0,0 +1,96 @@
+//! Checks that a `Replay` survives serialization, and that invalid traces
+//! are rejected when deserializing.
+
+use random_branch::branch;
+use random_branch::recorder::InvalidReplay;
+use random_branch::recorder::Recorder;
+use random_branch::recorder::Replay;
+
+
+fn run() -> Vec<u32> {
+	(0..10).map(|_| branch!(1, 2, 3) * branch!(10, 100)).collect()
+}
+
+fn record() -> (Vec<u32>, Replay) {
+	let recorder = Recorder::install();
+	let values = run();
+	(values, recorder.finish())
+}
+
+#[test]
+fn json_round_trip() {
+	let (values, replay) = record();
+
+	let json = serde_json::to_string(&replay).unwrap();
+	let deserialized: Replay = serde_json::from_str(&json).unwrap();
+	assert_eq!(deserialized, replay);
+
+	let replaying = deserialized.install();
+	assert_eq!(run(), values);
+	replaying.finish();
+}
+
+#[test]
+fn json_format() {
+	let (_, replay) = record();
+	let json: serde_json::Value = serde_json::to_value(&replay).unwrap();
+	assert_eq!(json["version"], Replay::FORMAT_VERSION);
+	assert_eq!(json["decisions"].as_array().unwrap().len(), 20);
+	assert_eq!(json["decisions"][0]["file"], "tests/replay_serde.rs");
+	assert_eq!(json["decisions"][0]["line"], 11);
+	assert_eq!(json["decisions"][0]["count"], 3);
+}
+
+#[test]
+fn unknown_fields_are_ignored() {
+	let json = r#"{
+		"version": 1,
+		"recorded_by": "some future version",
+		"decisions": [
+			{ "file": "src/main.rs", "line": 3, "column": 5, "count": 2, "index": 1, "label": "x" }
+		]
+	}"#;
+	let replay: Replay = serde_json::from_str(json).unwrap();
+	assert_eq!(replay.decisions().len(), 1);
+	assert_eq!(replay.decisions()[0].file, "src/main.rs");
+	assert_eq!(replay.decisions()[0].index, 1);
+}
+
+#[test]
+fn newer_version_is_rejected() {
+	let json = r#"{ "version": 2, "decisions": [] }"#;
+	let error = serde_json::from_str::<Replay>(json).unwrap_err();
+	assert!(error.to_string().contains("unsupported replay format version 2"), "{}", error);
+}
+
+#[test]
+fn inconsistent_count_is_rejected() {
+	let json = r#"{ "version": 1, "decisions": [
+		{ "file": "src/main.rs", "line": 3, "column": 5, "count": 2, "index": 1 },
+		{ "file": "src/main.rs", "line": 4, "column": 5, "count": 3, "index": 2 },
+		{ "file": "src/main.rs", "line": 3, "column": 5, "count": 3, "index": 2 }
+	] }"#;
+	let error = serde_json::from_str::<Replay>(json).unwrap_err();
+	assert!(
+		error.to_string().contains(
+			"decision 2 has an inconsistent count: branch 2 of 3 at src/main.rs:3:5, \
+			but earlier decisions of this call site have 2 branches"
+		),
+		"{}",
+		error,
+	);
+}
+
+#[test]
+fn index_out_of_range_is_rejected() {
+	let json = r#"{ "version": 1, "decisions": [
+		{ "file": "src/main.rs", "line": 3, "column": 5, "count": 2, "index": 2 }
+	] }"#;
+	let error = serde_json::from_str::<Replay>(json).unwrap_err();
+	assert!(error.to_string().contains("decision 0 is out of range"), "{}", error);
+
+	let replay: Replay = vec![serde_json::from_str(
+		r#"{ "file": "a.rs", "line": 1, "column": 1, "count": 1, "index": 1 }"#
+	).unwrap()].into();
+	assert!(matches!(replay.validate(), Err(InvalidReplay::IndexOutOfRange { position: 0, .. })));
+}
@@ -0,0 +1,70 @@
+//! Checks that the `repro` feature makes `branch` reproducible via the
+//! `RANDOM_BRANCH_SEED` environment variable, with one sequence shared by all
+//! threads.
+//!
+//! Since the generator is seeded only once per process, each run is a
+//! separate process executing `child_prints_choices` of this very test
+//! binary.
+
+// The checks compare random choices
+#![cfg(not(feature = "deterministic"))]
+
+use std::env;
+use std::thread;
+
+use random_branch::branch;
+
+mod support;
+
+
+/// Marks the process as a child spawned by `run_child`, and whether it
+/// should split the choices between two threads.
+const CHILD_VAR: &str = "RANDOM_BRANCH_TEST_CHILD";
+
+/// Draws the given number of choices.
+fn choices(n: usize) -> Vec<String> {
+	(0..n).map(|_| branch!(0, 1, 2, 3).to_string()).collect()
+}
+
+/// Prints a sequence of choices, if running as a child.
+#[test]
+fn child_prints_choices() {
+	let choices = match env::var(CHILD_VAR).as_deref() {
+		Ok("split") => {
+			let mut first = choices(16);
+			first.extend(thread::spawn(|| choices(16)).join().unwrap());
+			first
+		},
+		Ok(_) => choices(32),
+		Err(_) => return,
+	};
+	println!("choices: {}", choices.join(","));
+}
+
+/// Runs `child_prints_choices` in a new process and returns the printed
+/// choices.
+fn run_child(mode: &str, seed: &str) -> String {
+	let output = support::run_child(
+		"child_prints_choices",
+		&[(CHILD_VAR, Some(mode)), ("RANDOM_BRANCH_SEED", Some(seed))],
+	);
+	assert!(output.status.success(), "{:?}", output);
+	support::printed_choices(&output)
+}
+
+#[test]
+fn same_seed_same_choices() {
+	assert_eq!(run_child("single", "42"), run_child("single", "42"));
+	assert_ne!(run_child("single", "42"), run_child("single", "43"));
+}
+
+#[test]
+fn threads_share_the_sequence() {
+	// The spawned thread continues the sequence instead of repeating it
+	let single = run_child("single", "42");
+	let split = run_child("split", "42");
+	assert_eq!(single, split);
+
+	let halves: Vec<&str> = single.split(',').collect();
+	assert_ne!(halves[..16], halves[16..]);
+}
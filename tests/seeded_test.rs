@@ -0,0 +1,94 @@
+//! Checks the `seeded_test` attribute.
+//!
+//! The failing test is only run as a separate process executing
+//! `child_fails` of this very test binary.
+
+use std::env;
+
+use random_branch::branch_using;
+use random_branch::seeded_test;
+use random_branch::SeededRng;
+
+mod support;
+
+
+/// Marks the process as a child spawned by `run_child`.
+const CHILD_VAR: &str = "RANDOM_BRANCH_TEST_CHILD";
+
+/// Draws a sequence of choices from the given RNG.
+fn choices(rng: &mut SeededRng) -> String {
+	let choices: Vec<String> = (0..32)
+		.map(|_| branch_using!(*rng, { 0, 1, 2, 3 }).to_string())
+		.collect();
+	choices.join(",")
+}
+
+/// Prints a sequence of choices and fails, if running as a child.
+#[seeded_test]
+fn child_fails() {
+	if env::var_os(CHILD_VAR).is_none() {
+		return;
+	}
+	println!("choices: {}", choices(&mut rng));
+	panic!("Deliberately failing");
+}
+
+/// Runs `child_fails` in a new process and returns the printed choices and
+/// stderr.
+fn run_child(seed: Option<&str>) -> (String, String) {
+	let output = support::run_child(
+		"child_fails",
+		&[(CHILD_VAR, Some("1")), ("RANDOM_BRANCH_SEED", seed)],
+	);
+	assert!(!output.status.success(), "{:?}", output);
+	(support::printed_choices(&output), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn failing_test_prints_seed() {
+	let (first, stderr) = run_child(None);
+	assert!(stderr.contains("Deliberately failing"), "{}", stderr);
+	assert!(stderr.contains("test child_fails failed with seed"), "{}", stderr);
+
+	// The printed seed replays the run
+	let seed = stderr
+		.lines()
+		.find_map(|line| line.split("reproduce with RANDOM_BRANCH_SEED=").nth(1))
+		.unwrap()
+		.split(' ')
+		.next()
+		.unwrap();
+	let (replayed, _) = run_child(Some(seed));
+	assert_eq!(first, replayed);
+}
+
+#[test]
+fn seed_from_env() {
+	let (choices_42, stderr) = run_child(Some("42"));
+	assert!(stderr.contains("reproduce with RANDOM_BRANCH_SEED=42 "), "{}", stderr);
+	assert_eq!(choices_42, choices(&mut SeededRng::new(42)));
+}
+
+#[seeded_test]
+#[should_panic(expected = "Boom")]
+fn composes_with_should_panic() {
+	branch_using!(rng, { panic!("Boom"), panic!("Boom!") });
+}
+
+#[seeded_test(my_rng)]
+fn custom_binding() -> Result<(), String> {
+	let x = branch_using!(my_rng, { 1, 2, 3 });
+	if x > 0 {
+		Ok(())
+	} else {
+		Err(format!("{} isn't positive", x))
+	}
+}
+
+#[seeded_test]
+#[tokio::test]
+async fn composes_with_async_test() {
+	let x = async { 1 }.await;
+	let y = branch_using!(rng, { x, x + 1 });
+	assert!(y >= 1);
+}
@@ -0,0 +1,87 @@
+//! Checks that `SharedRng` releases the shared RNG before the branch runs,
+//! so that the branch can use it again.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_using;
+use random_branch::branch_weighted_using;
+use random_branch::SharedRng;
+
+
+#[test]
+fn ref_cell_reentrant() {
+	let shared = RefCell::new(Lcg64Xsh32::new(42, 0));
+	for _ in 0..100 {
+		let x = branch_using!(SharedRng::new(&shared), {
+			branch_using!(SharedRng::new(&shared), { 1, 2 }),
+			branch_weighted_using!(SharedRng::new(&shared), { 1 => 3, 1 => 4 }),
+		});
+		assert!((1..=4).contains(&x));
+	}
+	// The RNG isn't borrowed anymore
+	drop(shared.borrow_mut());
+}
+
+#[test]
+fn rc_ref_cell_reentrant() {
+	let shared = Rc::new(RefCell::new(Lcg64Xsh32::new(42, 0)));
+	let mut outer = SharedRng::new(Rc::clone(&shared));
+	for _ in 0..100 {
+		let x = branch_using!(outer, {
+			branch_using!(SharedRng::new(Rc::clone(&shared)), { 1, 2 }),
+			{
+				// Even an explicit borrow is fine within the branch
+				let _rng = shared.borrow_mut();
+				3
+			},
+		});
+		assert!((1..=3).contains(&x));
+	}
+	assert!(Rc::ptr_eq(&outer.into_inner(), &shared));
+}
+
+#[test]
+fn mutex_reentrant() {
+	let shared = Mutex::new(Lcg64Xsh32::new(42, 0));
+	for _ in 0..100 {
+		let x = branch_using!(SharedRng::new(&shared), {
+			branch_using!(SharedRng::new(&shared), { 1, 2 }),
+			{
+				let _rng = shared.try_lock().expect("The RNG is still locked");
+				3
+			},
+		});
+		assert!((1..=3).contains(&x));
+	}
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn mutex_across_threads() {
+	let shared = std::sync::Arc::new(Mutex::new(Lcg64Xsh32::new(42, 0)));
+	let handles: Vec<_> = (0..4)
+		.map(|_| {
+			let shared = std::sync::Arc::clone(&shared);
+			std::thread::spawn(move || {
+				(0..100).map(|_| branch_using!(SharedRng::new(&*shared), { 0, 1 })).sum::<u32>()
+			})
+		})
+		.collect();
+	let ones: u32 = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+	assert!((100..300).contains(&ones), "{}", ones);
+}
+
+#[test]
+fn same_sequence_as_direct_use() {
+	let mut direct = Lcg64Xsh32::new(7, 0);
+	let shared = RefCell::new(Lcg64Xsh32::new(7, 0));
+	for _ in 0..50 {
+		assert_eq!(
+			branch_using!(direct, { 'a', 'b', 'c' }),
+			branch_using!(SharedRng::new(&shared), { 'a', 'b', 'c' }),
+		);
+	}
+}
@@ -0,0 +1,72 @@
+//! Golden tests of `branch_site`, showing that the decisions of a call site
+//! don't depend on other call sites.
+
+use random_branch::branch_site;
+use random_branch::SiteRng;
+
+
+/// The decisions of two call sites over some rounds, optionally with another
+/// call site inserted between them.
+fn run(seed: u64, insert: bool) -> Vec<(u32, char)> {
+	let mut master = SiteRng::new(seed);
+	(0..20)
+		.map(|_| {
+			let number = branch_site!(master, "number", { 1, 2, 3, 4, 5 });
+			if insert {
+				let _ = branch_site!(master, "inserted", { "x", "y", "z" });
+			}
+			let letter = branch_site!(master, "letter", { 'a', 'b', 'c' });
+			(number, letter)
+		})
+		.collect()
+}
+
+#[test]
+fn inserted_call_site_leaves_others_unchanged() {
+	for seed in 0..10 {
+		assert_eq!(run(seed, false), run(seed, true));
+	}
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn shared_rng_shifts_subsequent_decisions() {
+	// The counterexample, with a single RNG shared by all call sites
+	use rand::SeedableRng;
+	use rand_pcg::Lcg64Xsh32;
+	use random_branch::branch_using;
+
+	fn run_shared(seed: u64, insert: bool) -> Vec<char> {
+		let mut rng = Lcg64Xsh32::seed_from_u64(seed);
+		(0..20)
+			.map(|_| {
+				if insert {
+					let _ = branch_using!(rng, { "x", "y", "z" });
+				}
+				branch_using!(rng, { 'a', 'b', 'c' })
+			})
+			.collect()
+	}
+	assert_ne!(run_shared(0, false), run_shared(0, true));
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn golden_sequence() {
+	// Pins the streams together with the range sampling of `rand`
+	let mut master = SiteRng::new(0);
+	let letters: String = (0..16)
+		.map(|_| branch_site!(master, "golden", { 'a', 'b', 'c', 'd' }))
+		.collect();
+	assert_eq!(letters, "caadcbccbcdabcdd");
+}
+
+#[test]
+fn location_identifies_call_site() {
+	let mut master = SiteRng::new(3);
+	let mut other = SiteRng::new(3);
+	let _ = branch_site!(other, { 1, 2 });
+	let a: Vec<u8> = (0..10).map(|_| branch_site!(master, "fixed", { 0, 1, 2, 3 })).collect();
+	let b: Vec<u8> = (0..10).map(|_| branch_site!(other, "fixed", { 0, 1, 2, 3 })).collect();
+	assert_eq!(a, b);
+}
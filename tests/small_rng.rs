@@ -0,0 +1,64 @@
+//! Checks that the `small-rng` feature gives each thread its own stream for
+//! `branch`.
+
+use rand::RngCore;
+use rand::SeedableRng;
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::reset_thread_branch_rng;
+use random_branch::set_thread_branch_rng;
+use random_branch::ThreadBranchRng;
+
+
+/// Draws a few values from the RNG of `branch` on the current thread.
+fn stream() -> [u64; 4] {
+	let mut rng = ThreadBranchRng;
+	[rng.next_u64(), rng.next_u64(), rng.next_u64(), rng.next_u64()]
+}
+
+// With `env-seed`, all threads start with the same seed on purpose
+#[test]
+#[cfg(not(feature = "env-seed"))]
+fn threads_have_independent_streams() {
+	use std::collections::HashSet;
+	use std::thread;
+
+	let streams: Vec<[u64; 4]> = (0..8)
+		.map(|_| thread::spawn(stream))
+		.collect::<Vec<_>>()
+		.into_iter()
+		.map(|handle| handle.join().unwrap())
+		.collect();
+
+	let distinct: HashSet<u64> = streams.iter().flatten().copied().collect();
+	assert_eq!(distinct.len(), 8 * 4, "{:?}", streams);
+}
+
+#[test]
+fn stream_continues_within_thread() {
+	assert_ne!(stream(), stream());
+}
+
+#[test]
+#[cfg(not(feature = "deterministic"))]
+fn uniform() {
+	use random_branch::testing::assert_uniform;
+	use random_branch::testing::DEFAULT_SIGNIFICANCE;
+
+	let mut counts = [0; 5];
+	for _ in 0..10_000 {
+		counts[branch!(0, 1, 2, 3, 4)] += 1;
+	}
+	assert_uniform(&counts, DEFAULT_SIGNIFICANCE);
+}
+
+#[test]
+fn override_takes_precedence() {
+	let run = || {
+		set_thread_branch_rng(Box::new(Lcg64Xsh32::seed_from_u64(42)));
+		let choices: Vec<u8> = (0..32).map(|_| branch!(0, 1, 2, 3)).collect();
+		reset_thread_branch_rng();
+		choices
+	};
+	assert_eq!(run(), run());
+}
@@ -0,0 +1,84 @@
+//! Checks that the macro expansions are clean under strict lint
+//! configurations.
+
+#![deny(warnings)]
+#![deny(unused)]
+#![deny(unused_parens)]
+#![deny(unused_braces)]
+#![deny(unused_qualifications)]
+#![deny(unreachable_code)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_array_using;
+use random_branch::branch_fast_using;
+use random_branch::branch_named_using;
+use random_branch::branch_opt_using;
+use random_branch::branch_retry_using;
+use random_branch::branch_stratified_using;
+use random_branch::branch_using;
+use random_branch::branch_weighted_using;
+use random_branch::branch_with_count_using;
+use random_branch::branch_zip_using;
+use random_branch::maybe_using;
+use random_branch::try_branch_weighted_using;
+use random_branch::Pick;
+use random_branch::StratifiedPicker;
+
+
+#[test]
+fn plain_branches() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+
+	let value = branch_using!(rng, { 1, (2), { 3 }, 2 + 2 });
+	assert!((1..=4).contains(&value));
+
+	branch_using!(rng, {
+		{ let _ = value; },
+		{},
+		(),
+	});
+
+	let value = branch_fast_using!(rng, { 1, (2), { 3 }, 2 + 2 });
+	assert!((1..=4).contains(&value));
+
+	let (value, count) = branch_with_count_using!(rng, { 'a', 'b' });
+	assert!(count == 2 && (value == 'a' || value == 'b'));
+
+	let (label, value) = branch_named_using!(rng, { "one" => 1, "two" => 2 });
+	assert_eq!(label == "one", value == 1);
+}
+
+#[test]
+fn derived_branches() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+
+	let values = branch_array_using!(rng, 4, { 1, 2 });
+	assert!(values.iter().all(|v| *v == 1 || *v == 2));
+
+	let value = branch_opt_using!(rng, { 1, 2 });
+	assert!(value == Some(1) || value == Some(2));
+
+	let value: Option<u8> = branch_opt_using!(rng, {});
+	assert!(value.is_none());
+
+	let (x, y) = branch_zip_using!(rng, { 1, 2 }, { 'a', 'b' });
+	assert_eq!(x == 1, y == 'a');
+
+	let value = branch_weighted_using!(rng, { 1 => 'a', 2 => 'b' });
+	assert!(value == 'a' || value == 'b');
+
+	let value = try_branch_weighted_using!(rng, { 0 => 'a', 0 => 'b' });
+	assert!(value.is_err());
+
+	let value = maybe_using!(rng, 0.5, 1);
+	assert!(value.is_none() || value == Some(1));
+
+	let value = branch_retry_using!(rng, { Pick::Pass, Pick::Take(1) });
+	assert_eq!(value, Some(1));
+
+	let mut state = StratifiedPicker::<2>::new();
+	let value = branch_stratified_using!(state, rng, { 1, 2 });
+	assert!(value == 1 || value == 2);
+}
@@ -0,0 +1,37 @@
+//! Helpers shared by the test targets, which include this module via
+//! `mod support;`.
+
+// Not every test target uses every helper
+#![allow(dead_code)]
+
+use std::env;
+use std::process::Command;
+use std::process::Output;
+
+
+/// Runs the given test of this very test binary in a child process, and
+/// returns its output.
+///
+/// Each of the given environment variables is set to its value, or removed if
+/// the value is `None`, so that the child doesn't inherit it.
+pub fn run_child(test: &str, vars: &[(&str, Option<&str>)]) -> Output {
+	let mut command = Command::new(env::current_exe().unwrap());
+	command.args([test, "--exact", "--nocapture", "--test-threads=1"]);
+	for &(key, value) in vars {
+		match value {
+			Some(value) => command.env(key, value),
+			None => command.env_remove(key),
+		};
+	}
+	command.output().unwrap()
+}
+
+/// Returns the choices that a child printed as `choices: ...` to stdout.
+pub fn printed_choices(output: &Output) -> String {
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	stdout
+		.lines()
+		.find_map(|line| line.split("choices: ").nth(1))
+		.unwrap()
+		.to_owned()
+}
@@ -0,0 +1,155 @@
+//! Checks that `with_forced` takes control over the selected branches, and
+//! that `with_secure_rng` replaces the source of the secure macros.
+
+use rand::RngCore;
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::branch_fast_using;
+use random_branch::branch_secure;
+use random_branch::branch_using;
+use random_branch::explore::explore_all;
+use random_branch::test_override::with_forced;
+use random_branch::test_override::with_secure_rng;
+use random_branch::try_branch_secure;
+
+
+/// Some code under test, that records which path it took.
+fn three_way(path: &mut Vec<&'static str>) -> u32 {
+	branch!(
+		{ path.push("first"); 1 },
+		{ path.push("second"); 2 },
+		{ path.push("third"); 3 },
+	)
+}
+
+#[test]
+fn forces_each_branch() {
+	for (index, &expected) in ["first", "second", "third"].iter().enumerate() {
+		let mut path = Vec::new();
+		let value = with_forced(&[index], || three_way(&mut path));
+		assert_eq!(value, index as u32 + 1);
+		assert_eq!(path, [expected]);
+	}
+}
+
+#[test]
+fn forces_a_sequence_with_any_rng() {
+	let mut rng = Lcg64Xsh32::new(42, 0);
+	let before = rng.clone();
+	let values = with_forced(&[3, 0, 1, 1], || {
+		[
+			branch_using!(rng, { 'a', 'b', 'c', 'd' }),
+			branch_using!(rng, { 'a', 'b' }),
+			branch_fast_using!(rng, { 'a', 'b', 'c', 'd' }),
+			branch_fast_using!(rng, { 'a', 'b', 'c' }),
+		]
+	});
+	assert_eq!(values, ['d', 'a', 'b', 'b']);
+	assert_eq!(rng, before);
+}
+
+#[test]
+fn nested_scripts_are_restored() {
+	let values = with_forced(&[0, 1], || {
+		let outer = branch!('a', 'b');
+		let inner = with_forced(&[2], || branch!('a', 'b', 'c'));
+		(outer, inner, branch!('a', 'b'))
+	});
+	assert_eq!(values, ('a', 'c', 'b'));
+}
+
+#[test]
+#[should_panic(expected = "The forced branch script is exhausted at tests/test_override.rs")]
+fn exhausted_script_panics() {
+	with_forced(&[0], || {
+		let mut path = Vec::new();
+		three_way(&mut path);
+		three_way(&mut path);
+	});
+}
+
+#[test]
+#[should_panic(expected = "The forced branch index 3 is out of range for 3 branches at tests/test_override.rs")]
+fn out_of_range_panics() {
+	with_forced(&[3], || three_way(&mut Vec::new()));
+}
+
+
+/// A secure source that yields the given values, then fails.
+struct Script(Vec<u64>);
+
+impl RngCore for Script {
+	fn next_u32(&mut self) -> u32 {
+		panic!("Only try_fill_bytes is scripted.")
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		panic!("Only try_fill_bytes is scripted.")
+	}
+
+	fn fill_bytes(&mut self, _dest: &mut [u8]) {
+		panic!("Only try_fill_bytes is scripted.")
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+		if self.0.is_empty() {
+			return Err(rand::Error::new("no entropy"));
+		}
+		dest.copy_from_slice(&self.0.remove(0).to_le_bytes());
+		Ok(())
+	}
+}
+
+#[test]
+fn secure_source_selects() {
+	let values = with_secure_rng(Script(vec![4, 2, u64::MAX, 7]), || {
+		[try_branch_secure!(0, 1, 2).unwrap(), branch_secure!(0, 1, 2), branch_secure!(0, 1, 2)]
+	});
+	// `u64::MAX` is rejected for three branches
+	assert_eq!(values, [1, 2, 1]);
+}
+
+#[test]
+fn secure_source_fails() {
+	let mut evaluated = false;
+	let result = with_secure_rng(Script(Vec::new()), || {
+		try_branch_secure!(
+			{
+				evaluated = true;
+				0
+			},
+			1,
+		)
+	});
+	assert_eq!(result.unwrap_err().to_string(), "no entropy");
+	assert!(!evaluated);
+
+	// The OS RNG is used again afterwards
+	assert!(try_branch_secure!(0, 1).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "The OS RNG failed: no entropy")]
+fn secure_source_fails_panics() {
+	with_secure_rng(Script(Vec::new()), || branch_secure!(0, 1));
+}
+
+#[test]
+fn secure_ignores_forced() {
+	let values = with_secure_rng(Script(vec![0, 0]), || {
+		with_forced(&[1], || [try_branch_secure!('a', 'b').unwrap(), branch_secure!('a', 'b'), branch!('a', 'b')])
+	});
+	// The forced index is left for `branch`
+	assert_eq!(values, ['a', 'a', 'b']);
+}
+
+#[test]
+fn secure_ignores_explore() {
+	let mut paths = Vec::new();
+	let explored = explore_all(10, || {
+		paths.push(with_secure_rng(Script(vec![1]), || branch_secure!('a', 'b')));
+	});
+	// A secure decision is no decision of the exploration
+	assert_eq!(explored, Ok(1));
+	assert_eq!(paths, ['b']);
+}
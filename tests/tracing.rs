@@ -0,0 +1,136 @@
+//! Checks the events emitted with the `tracing` feature.
+
+use std::fmt;
+use std::panic;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_using;
+use random_branch::branch_weighted_using;
+use tracing::field::Field;
+use tracing::field::Visit;
+use tracing::Event;
+use tracing::Level;
+use tracing::Subscriber;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
+
+
+/// The fields of a recorded event.
+#[derive(Debug, Default, PartialEq)]
+struct Fields {
+	file: String,
+	line: u64,
+	column: u64,
+	index: u64,
+	count: u64,
+	label: Option<String>,
+}
+
+impl Visit for Fields {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		match field.name() {
+			"file" => self.file = value.to_owned(),
+			"label" => self.label = Some(value.to_owned()),
+			_ => {},
+		}
+	}
+
+	fn record_u64(&mut self, field: &Field, value: u64) {
+		match field.name() {
+			"line" => self.line = value,
+			"column" => self.column = value,
+			"index" => self.index = value,
+			"count" => self.count = value,
+			_ => {},
+		}
+	}
+
+	fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+}
+
+/// A layer recording the fields of all events of this crate.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Fields>>>);
+
+impl<S: Subscriber> Layer<S> for Recorder {
+	fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+		let metadata = event.metadata();
+		if metadata.target() == "random_branch" {
+			assert_eq!(*metadata.level(), Level::TRACE);
+			let mut fields = Fields::default();
+			event.record(&mut fields);
+			self.0.lock().unwrap().push(fields);
+		}
+	}
+}
+
+/// Runs `f` with a subscriber recording the events of this crate.
+fn record<T>(f: impl FnOnce() -> T) -> (T, Vec<Fields>) {
+	let recorder = Recorder::default();
+	let subscriber = tracing_subscriber::registry().with(recorder.clone());
+	let value = tracing::subscriber::with_default(subscriber, f);
+	let events = std::mem::take(&mut *recorder.0.lock().unwrap());
+	(value, events)
+}
+
+
+#[test]
+fn emits_event_per_selection() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let ((value, line), events) = record(|| {
+		(branch_using!(rng, { 10, 20, 30 }), line!())
+	});
+
+	assert_eq!(events, [Fields {
+		file: file!().to_owned(),
+		line: line.into(),
+		column: events[0].column,
+		index: value / 10 - 1,
+		count: 3,
+		label: None,
+	}]);
+}
+
+#[test]
+fn emits_label() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (_, events) = record(|| {
+		for _ in 0..3 {
+			branch_using!(rng, "checkout", { 1, 2 });
+		}
+	});
+
+	assert_eq!(events.len(), 3);
+	assert!(events.iter().all(|e| e.label.as_deref() == Some("checkout")));
+	assert!(events.iter().all(|e| e.count == 2));
+}
+
+#[test]
+fn emits_event_for_weighted() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (value, events) = record(|| branch_weighted_using!(rng, { 0 => 'a', 1 => 'b' }));
+
+	assert_eq!(value, 'b');
+	assert_eq!(events.len(), 1);
+	assert_eq!((events[0].index, events[0].count), (1, 2));
+}
+
+#[test]
+fn emits_event_before_panicking_branch() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (result, events) = record(|| {
+		panic::catch_unwind(panic::AssertUnwindSafe(|| -> u32 {
+			branch_using!(rng, "doomed", {
+				panic!("first"),
+				panic!("second"),
+			})
+		}))
+	});
+
+	assert!(result.is_err());
+	assert_eq!(events.len(), 1);
+	assert_eq!(events[0].label.as_deref(), Some("doomed"));
+}
@@ -0,0 +1,89 @@
+//! Checks that panics caused by invalid input report the caller's location.
+
+use std::cell::RefCell;
+use std::panic;
+use std::sync::Once;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_keyed;
+use random_branch::branch_weighted_using;
+use random_branch::choose_and_run;
+use random_branch::choose_index;
+use random_branch::Branch;
+use random_branch::StratifiedPicker;
+
+
+std::thread_local! {
+	static LOCATION: RefCell<Option<(String, u32)>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, which must panic, and returns the file and line of the panic.
+fn panic_location(f: impl FnOnce() + panic::UnwindSafe) -> (String, u32) {
+	static HOOK: Once = Once::new();
+	HOOK.call_once(|| {
+		let default = panic::take_hook();
+		panic::set_hook(Box::new(move |info| {
+			let location = info.location().map(|l| (l.file().to_owned(), l.line()));
+			let captured = LOCATION.with(|c| c.borrow().is_none() && location.is_some());
+			if captured {
+				LOCATION.with(|c| *c.borrow_mut() = location);
+			} else {
+				default(info);
+			}
+		}));
+	});
+	LOCATION.with(|c| *c.borrow_mut() = None);
+	assert!(panic::catch_unwind(f).is_err(), "Expected a panic");
+	LOCATION.with(|c| c.borrow_mut().take()).unwrap()
+}
+
+
+#[test]
+fn all_weights_zero() {
+	let line = line!() + 3;
+	let location = panic_location(|| {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		branch_weighted_using!(rng, { 0 => 'a', 0 => 'b' });
+	});
+	assert_eq!(location, (file!().to_owned(), line));
+}
+
+#[test]
+fn choose_and_run_empty() {
+	let line = line!() + 3;
+	let location = panic_location(|| {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		choose_and_run::<(), _>(&mut rng, Vec::<Branch<()>>::new());
+	});
+	assert_eq!(location, (file!().to_owned(), line));
+}
+
+#[test]
+fn choose_index_zero() {
+	let line = line!() + 3;
+	let location = panic_location(|| {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		choose_index(&mut rng, 0);
+	});
+	assert_eq!(location, (file!().to_owned(), line));
+}
+
+#[test]
+fn keyed_all_weights_zero() {
+	let line = line!() + 2;
+	let location = panic_location(|| {
+		branch_keyed!("key", { 0 => 'a', 0 => 'b' });
+	});
+	assert_eq!(location, (file!().to_owned(), line));
+}
+
+#[test]
+fn stratified_all_weights_zero() {
+	let line = line!() + 4;
+	let location = panic_location(|| {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		let mut picker = StratifiedPicker::<2>::new();
+		picker.select_weighted(&[0, 0], &mut rng);
+	});
+	assert_eq!(location, (file!().to_owned(), line));
+}
@@ -0,0 +1,17 @@
+use random_branch::branch;
+use random_branch::branch_using;
+
+fn main() {
+	let greeting = branch!(
+		#[cfg(feature = "formal")] "Good day",
+		#[cfg(feature = "casual")] "Hey",
+	);
+	println!("{}", greeting);
+
+	let mut rng = rand::thread_rng();
+	let greeting = branch_using!(rng, {
+		#[cfg(feature = "formal")] "Good day",
+		"Hello",
+	});
+	println!("{}", greeting);
+}
@@ -0,0 +1,8 @@
+use random_branch::branch_const_index;
+
+const INDEX: usize = 2;
+
+fn main() {
+	let c = branch_const_index!(INDEX, { 'a', 'b' });
+	println!("{}", c);
+}
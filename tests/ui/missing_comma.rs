@@ -0,0 +1,6 @@
+use random_branch::branch;
+
+fn main() {
+	let n: u8 = branch!(1 2);
+	println!("{}", n);
+}
@@ -0,0 +1,12 @@
+use random_branch::branch_const_index;
+
+const fn pick<const I: usize>() -> char {
+	branch_const_index!(I, { 'a', 'b', 'c' })
+}
+
+const LAST: char = pick::<2>();
+
+fn main() {
+	assert_eq!(pick::<0>(), 'a');
+	assert_eq!(LAST, 'c');
+}
@@ -0,0 +1,16 @@
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_weighted_strict_using;
+
+fn main() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let edge_weight = 0;
+
+	for _ in 0..100 {
+		let coin = branch_weighted_strict_using!(rng, {
+			49 => "heads",
+			49_u32 => "tails",
+			edge_weight => "edge",
+		});
+		assert_ne!(coin, "edge");
+	}
+}
@@ -0,0 +1,18 @@
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_weighted_strict_using;
+
+fn main() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+
+	let coin = branch_weighted_strict_using!(rng, {
+		1 => "heads",
+		0 => "tails",
+	});
+	println!("{}", coin);
+
+	let coin = branch_weighted_strict_using!(rng, {
+		1 => "heads",
+		0x0_u32 => "tails",
+	});
+	println!("{}", coin);
+}
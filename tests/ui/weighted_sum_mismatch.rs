@@ -0,0 +1,9 @@
+use random_branch::branch_weighted_sum;
+
+fn main() {
+	let coin = branch_weighted_sum!(100, {
+		60 => "heads",
+		45 => "tails",
+	});
+	println!("{}", coin);
+}
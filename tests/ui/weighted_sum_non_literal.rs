@@ -0,0 +1,10 @@
+use random_branch::branch_weighted_sum;
+
+fn main() {
+	let weight = 40;
+	let coin = branch_weighted_sum!(100, {
+		60 => "heads",
+		weight => "tails",
+	});
+	println!("{}", coin);
+}
@@ -0,0 +1,11 @@
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch_zip_using;
+
+fn main() {
+	let mut rng = Lcg64Xsh32::new(0, 0);
+	let (name, sound) = branch_zip_using!(rng,
+		{ "cat", "dog", "cow" },
+		{ "meow", "woof" },
+	);
+	println!("The {} says {}", name, sound);
+}
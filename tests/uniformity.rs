@@ -0,0 +1,49 @@
+//! Checks custom RNGs for uniformity via `testing::assert_rng_uniform`.
+
+use std::cell::RefCell;
+
+use rand::rngs::mock::StepRng;
+use rand_pcg::Lcg64Xsh32;
+use random_branch::testing::assert_rng_uniform;
+use random_branch::BenchRng;
+use random_branch::SharedRng;
+
+
+#[test]
+fn seeded_rngs_are_uniform() {
+	for n in [1, 2, 3, 7, 10] {
+		assert_rng_uniform(&mut Lcg64Xsh32::new(42, 0), n, 50_000, 0.01).unwrap();
+		assert_rng_uniform(&mut BenchRng::new(42), n, 50_000, 0.01).unwrap();
+	}
+}
+
+#[test]
+fn shared_rng_is_uniform() {
+	let shared = RefCell::new(Lcg64Xsh32::new(42, 0));
+	assert_rng_uniform(&mut SharedRng::new(&shared), 5, 50_000, 0.01).unwrap();
+}
+
+#[test]
+fn constant_rng_is_not_uniform() {
+	let err = assert_rng_uniform(&mut StepRng::new(0, 0), 4, 1000, 0.05).unwrap_err();
+	assert_eq!(err.index(), 0);
+	assert_eq!(err.frequency(), 1.0);
+	assert_eq!(err.expected(), 0.25);
+	assert_eq!(err.tolerance(), 0.05);
+	assert_eq!(
+		err.to_string(),
+		"index 0 was selected with a frequency of 1.0000, expected 0.2500 ± 0.05",
+	);
+}
+
+#[test]
+#[should_panic(expected = "at least one sample")]
+fn no_samples() {
+	let _ = assert_rng_uniform(&mut Lcg64Xsh32::new(42, 0), 3, 0, 0.01);
+}
+
+#[test]
+#[should_panic(expected = "at least one choice")]
+fn no_choices() {
+	let _ = assert_rng_uniform(&mut Lcg64Xsh32::new(42, 0), 0, 100, 0.01);
+}
@@ -0,0 +1,60 @@
+//! Checks `branch` and `branch_using` on wasm32 with `wasm-bindgen-test`.
+//!
+//! Run in Node.js via `wasm-bindgen-test-runner`, with either backend:
+//!
+//! ```sh
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test --target wasm32-unknown-unknown --test wasm --features wasm-js
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test --target wasm32-unknown-unknown --test wasm --no-default-features --features js-random,alloc
+//! ```
+//!
+//! Add `wasm_bindgen_test_configure!(run_in_browser)` to run them in a
+//! headless browser instead, via `wasm-pack test --headless --firefox`.
+
+#![cfg(target_arch = "wasm32")]
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use rand_pcg::Lcg64Xsh32;
+use random_branch::branch;
+use random_branch::branch_using;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+
+#[wasm_bindgen_test]
+fn branch_takes_every_branch() {
+	let mut seen = [false; 3];
+	for _ in 0..300 {
+		seen[branch!(0, 1, 2)] = true;
+	}
+	assert_eq!(seen, [true; 3]);
+}
+
+#[wasm_bindgen_test]
+fn branch_using_is_reproducible() {
+	let draw = || {
+		let mut rng = Lcg64Xsh32::new(42, 0);
+		(0..32).map(|_| branch_using!(rng, { 0, 1, 2, 3 })).collect::<Vec<u8>>()
+	};
+	assert_eq!(draw(), draw());
+}
+
+#[wasm_bindgen_test]
+#[cfg(all(feature = "js-random", not(feature = "std")))]
+fn js_random_is_uniform() {
+	use rand::Rng;
+	use random_branch::JsRandomRng;
+
+	let mut counts = [0_u32; 4];
+	for _ in 0..4000 {
+		counts[branch!(0, 1, 2, 3)] += 1;
+	}
+	for &count in &counts {
+		assert!((800..1200).contains(&count), "{:?}", counts);
+	}
+	assert!(JsRandomRng.gen::<u64>() != JsRandomRng.gen::<u64>());
+}